@@ -0,0 +1,252 @@
+use crate::arrays::plain_transfers_ddl;
+use crate::bulk::{bind_columns_with, copy_rows, BulkInsert};
+use crate::error::{BenchmarkError, Result};
+use crate::models::Transfer;
+use crate::report::{self, PhaseResult};
+use crate::schema::{self, column, height, TableSpec};
+use crate::stats;
+use rust_decimal::Decimal;
+use std::time::{Duration, Instant};
+use tokio_postgres::types::Type;
+use tokio_postgres::Client;
+
+/// Violating amounts listed before the rest are only counted
+const MAX_LISTED: usize = 10;
+
+/// Aggregations timed per variant
+const SUM_QUERIES: usize = 20;
+
+/// Transfers with `amount` as NUMERIC(`precision`, `scale`)
+fn numeric_ddl(precision: u32, scale: u32) -> String {
+    schema::TRANSFERS
+        .scratch_table()
+        .column(
+            "amount",
+            format!("NUMERIC({}, {}) NOT NULL", precision, scale),
+        )
+        .ddl()
+}
+
+/// `schema::TRANSFERS` with the amount bound as NUMERIC
+const TRANSFERS_NUMERIC: TableSpec = TableSpec {
+    table: "transfers",
+    columns: &[
+        column("tx_hash", Type::TEXT),
+        height("block_number"),
+        column("token", Type::TEXT),
+        column("from_address", Type::TEXT),
+        column("to_address", Type::TEXT),
+        column("amount", Type::NUMERIC),
+    ],
+};
+
+/// Whether `amount` is a decimal number Postgres stores in a
+/// `NUMERIC(precision, scale)` column unchanged: at most `scale` significant
+/// decimal places, which would otherwise be rounded away, and at most
+/// `precision - scale` integer digits, which would otherwise fail the load.
+pub fn fits_numeric(amount: &str, precision: u32, scale: u32) -> bool {
+    let digits = amount.strip_prefix('-').unwrap_or(amount);
+    let (integer, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+    if integer.is_empty() && fraction.is_empty()
+        || !integer
+            .chars()
+            .chain(fraction.chars())
+            .all(|c| c.is_ascii_digit())
+    {
+        return false;
+    }
+    let integer_digits = integer.trim_start_matches('0').len() as u32;
+    let decimal_places = fraction.trim_end_matches('0').len() as u32;
+    decimal_places <= scale && integer_digits <= precision - scale
+}
+
+/// Checks every transfer amount against `NUMERIC(precision, scale)`, listing
+/// those that don't fit, and returns the transfers that do. Violations are a
+/// warning, leaving those transfers out of the NUMERIC load rather than
+/// storing them rounded, or an error under `--strict-numeric-scale`.
+fn check_amounts(
+    transfers: &[Transfer],
+    precision: u32,
+    scale: u32,
+    strict: bool,
+) -> Result<Vec<&Transfer>> {
+    let (fitting, violations): (Vec<_>, Vec<_>) = transfers
+        .iter()
+        .enumerate()
+        .partition(|(_, t)| fits_numeric(&t.amount, precision, scale));
+    if violations.is_empty() {
+        println!(
+            "Every transfer amount fits NUMERIC({}, {})",
+            precision, scale
+        );
+        return Ok(fitting.into_iter().map(|(_, t)| t).collect());
+    }
+
+    eprintln!(
+        "transfers: {} amounts don't fit NUMERIC({}, {})",
+        violations.len(),
+        precision,
+        scale
+    );
+    for (i, transfer) in violations.iter().take(MAX_LISTED) {
+        eprintln!("  record {}: amount = {}", i, transfer.amount);
+    }
+    if violations.len() > MAX_LISTED {
+        eprintln!("  ... and {} more", violations.len() - MAX_LISTED);
+    }
+    let message = format!(
+        "{} transfer amounts exceed NUMERIC({}, {}) and would be rounded or rejected",
+        violations.len(),
+        precision,
+        scale
+    );
+    if strict {
+        return Err(BenchmarkError::ValidationError(message));
+    }
+    eprintln!("Warning: {}, leaving them out", message);
+    Ok(fitting.into_iter().map(|(_, t)| t).collect())
+}
+
+/// COPY and aggregation timings of one amount representation.
+struct AmountRun {
+    copy: Duration,
+    bytes: i64,
+    sums: Vec<Duration>,
+}
+
+/// Compares transfers stored with `amount` as TEXT, as the benchmark tables
+/// do, against `NUMERIC(precision, scale)`: COPY throughput, table size and
+/// the latency of summing every amount, which the TEXT table has to cast.
+///
+/// Amounts are first validated against the scale, so that token amounts with
+/// more decimals than it are reported instead of silently rounded. Amounts
+/// past the 28 digits the client's decimal type holds are left out of both
+/// tables.
+#[tracing::instrument(name = "phase", skip_all, fields(phase = "numeric_amount_benchmark"))]
+pub async fn run_numeric_amount_benchmark(
+    client: &Client,
+    transfers: &[Transfer],
+    (precision, scale): (u32, u32),
+    strict: bool,
+    batch_size: usize,
+) -> Result<Vec<PhaseResult>> {
+    let fitting = check_amounts(transfers, precision, scale, strict)?;
+    let parsed: Vec<(&Transfer, Decimal)> = fitting
+        .into_iter()
+        .filter_map(|t| Some((t, t.amount.parse::<Decimal>().ok()?)))
+        .collect();
+    if parsed.is_empty() {
+        println!("\nSkipping the NUMERIC amount benchmark, no transfer amount can be loaded");
+        return Ok(Vec::new());
+    }
+    let unbindable = transfers.len() - parsed.len();
+
+    let text = {
+        let previous =
+            schema::enter_scratch_schema(client, "numeric_bench_text", &plain_transfers_ddl())
+                .await?;
+        let start = Instant::now();
+        for batch in parsed.chunks(batch_size.max(1)) {
+            copy_rows(client, &schema::TRANSFERS, batch, false, |(t, _)| {
+                t.params()
+            })
+            .await?;
+        }
+        let copy = start.elapsed();
+        let run = time_sums(client, "numeric_bench_text", copy, "amount::NUMERIC").await?;
+        schema::leave_scratch_schema(client, "numeric_bench_text", &previous).await?;
+        run
+    };
+
+    let numeric = {
+        let ddl = numeric_ddl(precision, scale);
+        let previous = schema::enter_scratch_schema(client, "numeric_bench_numeric", &ddl).await?;
+        let start = Instant::now();
+        for batch in parsed.chunks(batch_size.max(1)) {
+            copy_rows(client, &TRANSFERS_NUMERIC, batch, false, |(t, amount)| {
+                bind_columns_with(*t, &TRANSFERS_NUMERIC, ("amount", amount))
+            })
+            .await?;
+        }
+        let copy = start.elapsed();
+        let run = time_sums(client, "numeric_bench_numeric", copy, "amount").await?;
+        schema::leave_scratch_schema(client, "numeric_bench_numeric", &previous).await?;
+        run
+    };
+
+    println!(
+        "\nTEXT vs NUMERIC({}, {}) Amount Benchmark ({} transfers, {} SUM queries):",
+        precision,
+        scale,
+        parsed.len(),
+        SUM_QUERIES
+    );
+    println!("-------------------------");
+    if unbindable > 0 {
+        println!("  {} transfers left out", unbindable);
+    }
+    let mut results = Vec::new();
+    for (label, run) in [("text", text), ("numeric", numeric)] {
+        let mut sums = run.sums;
+        let mean = sums.iter().sum::<Duration>() / sums.len().max(1) as u32;
+        let p99 = report::percentile(&mut sums, 99.0);
+        println!(
+            "  {:<8} COPY {:>10.0} rows/sec  {:>8.1} MiB  SUM mean {:>9.2?}  p99 {:>9.2?}",
+            label,
+            parsed.len() as f64 / run.copy.as_secs_f64(),
+            run.bytes as f64 / (1024.0 * 1024.0),
+            mean,
+            p99.unwrap_or_default()
+        );
+        results.push(PhaseResult::new(
+            &format!("amount_{}_copy", label),
+            parsed.len(),
+            run.copy,
+        ));
+        results.push(
+            PhaseResult::new(
+                &format!("amount_{}_sum", label),
+                sums.len(),
+                sums.iter().sum(),
+            )
+            .with_p99(p99),
+        );
+    }
+    Ok(results)
+}
+
+/// Times `SELECT SUM(<amount>)` over the transfers in the scratch schema
+/// `name`, whose size is measured too.
+async fn time_sums(client: &Client, name: &str, copy: Duration, amount: &str) -> Result<AmountRun> {
+    client.execute("ANALYZE transfers", &[]).await?;
+    let statement = client
+        .prepare(&format!("SELECT SUM({}) FROM transfers", amount))
+        .await?;
+    let mut sums = Vec::with_capacity(SUM_QUERIES);
+    for _ in 0..SUM_QUERIES {
+        let start = Instant::now();
+        client.query_one(&statement, &[]).await?;
+        sums.push(start.elapsed());
+    }
+    Ok(AmountRun {
+        copy,
+        bytes: stats::schema_bytes(client, name).await?,
+        sums,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn amounts_fit_within_the_scale() {
+        assert!(fits_numeric("988902439231429732", 78, 18));
+        assert!(fits_numeric("1.000000000000000001", 78, 18));
+        assert!(fits_numeric("1.50000000000000000000", 78, 18));
+        assert!(!fits_numeric("1.0000000000000000001", 78, 18));
+        assert!(!fits_numeric("12345", 6, 2));
+        assert!(!fits_numeric("0x10", 78, 18));
+        assert!(!fits_numeric("", 78, 18));
+    }
+}
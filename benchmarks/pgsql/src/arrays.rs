@@ -0,0 +1,144 @@
+use crate::bulk::{copy_rows, BulkInsert};
+use crate::error::Result;
+use crate::models::Transfer;
+use crate::report::PhaseResult;
+use crate::schema::{self, column, height, TableSpec};
+use crate::stats;
+use std::time::Instant;
+use tokio_postgres::types::Type;
+use tokio_postgres::Client;
+
+/// keccak256("Transfer(address,address,uint256)"), the first topic of every
+/// ERC-20 transfer log
+const TRANSFER_TOPIC: &str = "0xddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef";
+
+pub(crate) fn plain_transfers_ddl() -> String {
+    schema::TRANSFERS.scratch_table().ddl()
+}
+
+fn topics_transfers_ddl() -> String {
+    schema::TRANSFERS
+        .scratch_table()
+        .extra_column("topics TEXT[] NOT NULL")
+        .ddl()
+}
+
+/// `schema::TRANSFERS` with the log's topics bound as a TEXT[] column
+const TRANSFERS_TOPICS: TableSpec = TableSpec {
+    table: "transfers",
+    columns: &[
+        column("tx_hash", Type::TEXT),
+        height("block_number"),
+        column("token", Type::TEXT),
+        column("from_address", Type::TEXT),
+        column("to_address", Type::TEXT),
+        column("amount", Type::TEXT),
+        column("topics", Type::TEXT_ARRAY),
+    ],
+};
+
+/// The topics of the log a transfer was decoded from: the event signature
+/// and the sender and recipient left-padded to 32 bytes.
+fn transfer_topics(transfer: &Transfer) -> Vec<String> {
+    let padded = |address: &str| format!("0x{:0>64}", address.trim_start_matches("0x"));
+    vec![
+        TRANSFER_TOPIC.to_string(),
+        padded(&transfer.from),
+        padded(&transfer.to),
+    ]
+}
+
+/// Compares COPY throughput and size of transfers with and without a
+/// `topics TEXT[]` column holding each transfer's three log topics, bound as
+/// `Vec<String>` through `Type::TEXT_ARRAY`.
+///
+/// Topics are built before either COPY starts, so only the array encoding and
+/// the server's array input are timed.
+#[tracing::instrument(name = "phase", skip_all, fields(phase = "array_column_benchmark"))]
+pub async fn run_array_column_benchmark(
+    client: &Client,
+    transfers: &[Transfer],
+    batch_size: usize,
+) -> Result<Vec<PhaseResult>> {
+    let with_topics: Vec<(&Transfer, Vec<String>)> = transfers
+        .iter()
+        .map(|transfer| (transfer, transfer_topics(transfer)))
+        .collect();
+
+    let previous =
+        schema::enter_scratch_schema(client, "array_bench_plain", &plain_transfers_ddl()).await?;
+    let start = Instant::now();
+    for batch in transfers.chunks(batch_size.max(1)) {
+        Transfer::copy_in(client, batch, false).await?;
+    }
+    let plain = start.elapsed();
+    let plain_bytes = stats::schema_bytes(client, "array_bench_plain").await?;
+    schema::leave_scratch_schema(client, "array_bench_plain", &previous).await?;
+
+    let previous =
+        schema::enter_scratch_schema(client, "array_bench_topics", &topics_transfers_ddl()).await?;
+    let start = Instant::now();
+    for batch in with_topics.chunks(batch_size.max(1)) {
+        copy_rows(
+            client,
+            &TRANSFERS_TOPICS,
+            batch,
+            false,
+            |(transfer, topics)| {
+                let mut params = transfer.params();
+                params.push(topics);
+                params
+            },
+        )
+        .await?;
+    }
+    let array = start.elapsed();
+    let array_bytes = stats::schema_bytes(client, "array_bench_topics").await?;
+    schema::leave_scratch_schema(client, "array_bench_topics", &previous).await?;
+
+    println!(
+        "\nArray Column Benchmark ({} transfers, 3 topics each):",
+        transfers.len()
+    );
+    println!("-------------------------");
+    for (label, duration, bytes) in [
+        ("plain", plain, plain_bytes),
+        ("topics[]", array, array_bytes),
+    ] {
+        println!(
+            "  {:<10} {:>12.2?}  {:>10.0} rows/sec  {:>8.1} MiB",
+            label,
+            duration,
+            transfers.len() as f64 / duration.as_secs_f64(),
+            bytes as f64 / (1024.0 * 1024.0)
+        );
+    }
+
+    Ok(vec![
+        PhaseResult::new("array_bench_plain_copy", transfers.len(), plain),
+        PhaseResult::new("array_column_copy", transfers.len(), array),
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::BlockHeight;
+
+    #[test]
+    fn topics_pad_addresses_to_32_bytes() {
+        let transfer = Transfer {
+            tx_hash: "0x9735".to_string(),
+            block_number: BlockHeight(1),
+            token: "0xc02a".to_string(),
+            from: "0xa1b4".to_string(),
+            to: "0x2394".to_string(),
+            amount: "1".to_string(),
+        };
+        let topics = transfer_topics(&transfer);
+        assert_eq!(topics[0], TRANSFER_TOPIC);
+        assert_eq!(topics[1].len(), TRANSFER_TOPIC.len());
+        assert!(topics[1].ends_with("0000a1b4"));
+        assert!(topics[2].ends_with("2394"));
+    }
+}
@@ -0,0 +1,165 @@
+use crate::bulk::copy_all;
+use crate::error::Result;
+use crate::models::Transaction;
+use crate::report::{self, PhaseResult};
+use crate::schema;
+use crate::stats;
+use rand::Rng;
+use std::time::Instant;
+use tokio_postgres::Client;
+
+const SCHEMA: &str = "bloat_bench";
+
+/// Transactions with both addresses indexed, so updating either can't be HOT.
+/// Autovacuum is off so the bloat isn't cleaned up while it is measured.
+fn bloat_ddl() -> String {
+    schema::TRANSACTIONS
+        .scratch_table()
+        .with("autovacuum_enabled = off")
+        .ddl()
+        + "
+    CREATE INDEX transactions_from_address_idx ON transactions (from_address);
+    CREATE INDEX transactions_to_address_idx ON transactions (to_address);"
+}
+
+/// Size and usage of one index, from `pg_stat_user_indexes`.
+struct IndexStats {
+    name: String,
+    bytes: i64,
+    scans: i64,
+}
+
+/// Loads `transactions` into a scratch table with indexed addresses, runs
+/// `updates` single-row UPDATEs of a random row's `from_address` or
+/// `to_address`, and reports each index's size and scans before and after
+/// along with how many updates were HOT.
+///
+/// Updating an indexed column rules out a HOT update, so every update adds an
+/// entry to every index of the table and leaves a dead one behind; that is
+/// the bloat an append-only load never shows.
+#[tracing::instrument(name = "phase", skip_all, fields(phase = "index_bloat_benchmark"))]
+pub async fn run_index_bloat_benchmark(
+    client: &Client,
+    transactions: &[Transaction],
+    updates: usize,
+    batch_size: usize,
+) -> Result<Vec<PhaseResult>> {
+    if transactions.is_empty() {
+        println!("\nSkipping the index bloat benchmark, there are no transactions to update");
+        return Ok(Vec::new());
+    }
+    let previous = schema::enter_scratch_schema(client, SCHEMA, &bloat_ddl()).await?;
+    copy_all(client, transactions, batch_size).await?;
+    let before = index_stats(client).await?;
+
+    let statements = [
+        client
+            .prepare("UPDATE transactions SET from_address = $2 WHERE id = $1")
+            .await?,
+        client
+            .prepare("UPDATE transactions SET to_address = $2 WHERE id = $1")
+            .await?,
+    ];
+    let max_id = transactions.len() as i32;
+    let mut rng = rand::thread_rng();
+    let mut latencies = Vec::with_capacity(updates);
+    let start = Instant::now();
+    for _ in 0..updates {
+        let id = rng.gen_range(1..=max_id);
+        let address = format!("0x{:032x}{:08x}", rng.gen::<u128>(), rng.gen::<u32>());
+        let statement = &statements[rng.gen_range(0..statements.len())];
+        let update_start = Instant::now();
+        client.execute(statement, &[&id, &address]).await?;
+        latencies.push(update_start.elapsed());
+    }
+    let duration = start.elapsed();
+
+    let after = index_stats(client).await?;
+    let (updated, hot) = update_counts(client).await?;
+    schema::leave_scratch_schema(client, SCHEMA, &previous).await?;
+
+    println!(
+        "\nIndex Bloat Benchmark ({} random updates of indexed columns over {} transactions):",
+        updates,
+        transactions.len()
+    );
+    println!("-------------------------");
+    println!(
+        "  Updates: {:.2?}, {:.0} updates/sec, {} of {} counted HOT",
+        duration,
+        updates as f64 / duration.as_secs_f64(),
+        hot,
+        updated
+    );
+    println!(
+        "  {:<32} {:>10} {:>10} {:>8} {:>8}",
+        "index", "before", "after", "growth", "scans"
+    );
+    for after in &after {
+        let before = before.iter().find(|b| b.name == after.name);
+        let (bytes, scans) = before.map_or((0, 0), |b| (b.bytes, b.scans));
+        println!(
+            "  {:<32} {:>8.1}Mi {:>8.1}Mi {:>7.1}% {:>8}",
+            after.name,
+            mib(bytes),
+            mib(after.bytes),
+            100.0 * (after.bytes - bytes) as f64 / bytes.max(1) as f64,
+            after.scans - scans
+        );
+    }
+
+    let p99 = report::percentile(&mut latencies, 99.0);
+    Ok(vec![PhaseResult::new(
+        "index_bloat_updates",
+        updates,
+        duration,
+    )
+    .with_p99(p99)])
+}
+
+fn mib(bytes: i64) -> f64 {
+    bytes as f64 / (1024.0 * 1024.0)
+}
+
+/// Flushes this backend's pending statistics, which Postgres 15+ otherwise
+/// only publishes about once a second, so the views include the last statements.
+async fn flush_stats(client: &Client) -> Result<()> {
+    if stats::server_version(client).await? >= 150000 {
+        client
+            .batch_execute("SELECT pg_stat_force_next_flush()")
+            .await?;
+    }
+    Ok(())
+}
+
+async fn index_stats(client: &Client) -> Result<Vec<IndexStats>> {
+    flush_stats(client).await?;
+    let rows = client
+        .query(
+            "SELECT indexrelname::TEXT, pg_relation_size(indexrelid), idx_scan
+             FROM pg_stat_user_indexes WHERE schemaname = $1 ORDER BY indexrelname",
+            &[&SCHEMA],
+        )
+        .await?;
+    Ok(rows
+        .iter()
+        .map(|row| IndexStats {
+            name: row.get(0),
+            bytes: row.get(1),
+            scans: row.get(2),
+        })
+        .collect())
+}
+
+/// Rows updated in the scratch table and how many of those updates were HOT.
+async fn update_counts(client: &Client) -> Result<(i64, i64)> {
+    flush_stats(client).await?;
+    let row = client
+        .query_one(
+            "SELECT n_tup_upd, n_tup_hot_upd FROM pg_stat_user_tables
+             WHERE schemaname = $1 AND relname = 'transactions'",
+            &[&SCHEMA],
+        )
+        .await?;
+    Ok((row.get(0), row.get(1)))
+}
@@ -0,0 +1,150 @@
+use crate::bulk::copy_all;
+use crate::error::Result;
+use crate::models::{BlockHeight, Transaction};
+use crate::report::{self, PhaseResult};
+use crate::schema;
+use crate::variants::plain_transactions_ddl;
+use rand::Rng;
+use std::time::{Duration, Instant};
+use tokio_postgres::Client;
+
+const SCHEMA: &str = "brin_bench";
+
+/// Block-range queries timed per index variant
+const RANGE_QUERIES: usize = 200;
+
+/// Index variants on `transactions.block`, by label and DDL
+const VARIANTS: [(&str, &str); 2] = [
+    (
+        "btree",
+        "CREATE INDEX transactions_block_idx ON transactions (block)",
+    ),
+    (
+        "brin",
+        "CREATE INDEX transactions_block_idx ON transactions USING brin (block)",
+    ),
+];
+
+/// Index build and range query timings of one variant.
+struct RangeRun {
+    label: &'static str,
+    build: Duration,
+    index_bytes: i64,
+    queries: Vec<Duration>,
+    /// Rows matched by each range query, to compare answers across variants
+    matched: Vec<i64>,
+}
+
+/// Compares a BTREE index on `transactions.block` against a BRIN one, over a
+/// scratch copy of the transactions loaded in block order as a chain indexer
+/// appends them: index build time and size, and the latency of random
+/// `block BETWEEN` range queries. BRIN only keeps the block range of each
+/// group of pages, so it stays tiny while the heap is ordered by block.
+#[tracing::instrument(name = "phase", skip_all, fields(phase = "brin_benchmark"))]
+pub async fn run_brin_benchmark(
+    client: &Client,
+    transactions: &[Transaction],
+    batch_size: usize,
+) -> Result<Vec<PhaseResult>> {
+    let (Some(first), Some(last)) = (
+        transactions.iter().map(|tx| tx.block.0).min(),
+        transactions.iter().map(|tx| tx.block.0).max(),
+    ) else {
+        println!("\nSkipping the BRIN benchmark, there are no transactions");
+        return Ok(Vec::new());
+    };
+    let mut ordered: Vec<Transaction> = transactions.to_vec();
+    ordered.sort_by_key(|tx| (tx.block.0, tx.index));
+
+    // Windows of a hundredth of the dataset's block span, at random offsets
+    let window = ((last - first) / 100).max(1);
+    let mut rng = rand::thread_rng();
+    let ranges: Vec<(BlockHeight, BlockHeight)> = (0..RANGE_QUERIES)
+        .map(|_| {
+            let from = rng.gen_range(first..=(last - window).max(first));
+            (BlockHeight(from), BlockHeight(from + window))
+        })
+        .collect();
+
+    let mut runs = Vec::with_capacity(VARIANTS.len());
+    for (label, index) in VARIANTS {
+        runs.push(range_run(client, label, index, &ordered, &ranges, batch_size).await?);
+    }
+
+    println!(
+        "\nBRIN vs BTREE Block Range Benchmark ({} transactions, {} queries of {} blocks):",
+        transactions.len(),
+        RANGE_QUERIES,
+        window + 1
+    );
+    println!("-------------------------");
+    let mut results = Vec::new();
+    for run in &runs {
+        let mut queries = run.queries.clone();
+        let duration: Duration = queries.iter().sum();
+        let mean = duration / queries.len().max(1) as u32;
+        let p99 = report::percentile(&mut queries, 99.0);
+        println!(
+            "  {:<6} build {:>9.2?}  index {:>8.1} KiB  range query mean {:>9.2?}  p99 {:>9.2?}",
+            run.label,
+            run.build,
+            run.index_bytes as f64 / 1024.0,
+            mean,
+            p99.unwrap_or_default()
+        );
+        results.push(
+            PhaseResult::new(
+                &format!("block_range_{}", run.label),
+                queries.len(),
+                duration,
+            )
+            .with_p99(p99),
+        );
+    }
+    if runs[0].matched != runs[1].matched {
+        println!("  The BTREE and BRIN tables matched different rows for the same ranges");
+    }
+    Ok(results)
+}
+
+/// Loads `transactions` into a scratch table, builds `index` and times a
+/// `COUNT(*)` over each of `ranges`.
+async fn range_run(
+    client: &Client,
+    label: &'static str,
+    index: &str,
+    transactions: &[Transaction],
+    ranges: &[(BlockHeight, BlockHeight)],
+    batch_size: usize,
+) -> Result<RangeRun> {
+    let previous = schema::enter_scratch_schema(client, SCHEMA, &plain_transactions_ddl()).await?;
+    copy_all(client, transactions, batch_size).await?;
+    let start = Instant::now();
+    client.batch_execute(index).await?;
+    let build = start.elapsed();
+    client.execute("ANALYZE transactions", &[]).await?;
+    let index_bytes: i64 = client
+        .query_one("SELECT pg_relation_size('transactions_block_idx')", &[])
+        .await?
+        .get(0);
+
+    let statement = client
+        .prepare("SELECT COUNT(*) FROM transactions WHERE block BETWEEN $1 AND $2")
+        .await?;
+    let mut queries = Vec::with_capacity(ranges.len());
+    let mut matched = Vec::with_capacity(ranges.len());
+    for (from, to) in ranges {
+        let start = Instant::now();
+        let count: i64 = client.query_one(&statement, &[from, to]).await?.get(0);
+        queries.push(start.elapsed());
+        matched.push(count);
+    }
+    schema::leave_scratch_schema(client, SCHEMA, &previous).await?;
+    Ok(RangeRun {
+        label,
+        build,
+        index_bytes,
+        queries,
+        matched,
+    })
+}
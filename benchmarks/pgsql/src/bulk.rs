@@ -0,0 +1,984 @@
+use crate::cli::{Args, InsertMethod};
+use crate::dataset::{DataSource, Dataset};
+use crate::error::{BenchmarkError, Result};
+use crate::faults;
+use crate::models::{Block, BlockHeight, Pool, Transaction, Transfer};
+use crate::report::{self, PhaseResult};
+use crate::schema::{self, TableSpec, TABLES};
+use crate::stream;
+use crate::tui;
+use futures::future::try_join_all;
+use futures::pin_mut;
+use postgres::types::ToSql;
+use serde::de::DeserializeOwned;
+use std::io::{self, IsTerminal};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+use tokio_postgres::binary_copy::BinaryCopyInWriter;
+use tokio_postgres::error::SqlState;
+use tokio_postgres::types::Type;
+use tokio_postgres::Client;
+
+/// Default records per batch in streaming mode and the scratch-table benchmarks,
+/// where the batch size isn't derived from the dataset
+pub const DEFAULT_STREAM_BATCH_SIZE: usize = 10_000;
+
+/// Statements in flight at once per round of `--insert-method pipelined`
+const PIPELINE_DEPTH: usize = 256;
+
+/// A model that can be written into its table with each `--insert-method`.
+pub(crate) trait BulkInsert: Sized {
+    /// The table's COPY columns, in the order `params` binds them
+    const SPEC: &'static TableSpec;
+
+    /// Single-row INSERT with one `$n` parameter per column, in COPY column order
+    const INSERT: &'static str;
+
+    /// The value written to the `SPEC` column `name`. Values are looked up by
+    /// column name rather than listed by position, so reordering the spec or
+    /// the model's fields can't bind e.g. `from` to `to_address`.
+    fn column(&self, name: &str) -> &(dyn ToSql + Sync);
+
+    /// Column values in `SPEC` order, which `INSERT`'s parameters follow too
+    fn params(&self) -> Vec<&(dyn ToSql + Sync)> {
+        bind_columns(self, Self::SPEC)
+    }
+
+    /// Identifies the row in logs, e.g. its block number or hash
+    fn key(&self) -> String;
+
+    /// One binary COPY for the whole batch, binding block heights as BIGINT
+    /// when `bigint_blocks` is set
+    async fn copy_in(client: &Client, rows: &[Self], bigint_blocks: bool) -> Result<u64> {
+        copy_rows(client, Self::SPEC, rows, bigint_blocks, Self::params).await
+    }
+
+    /// One `INSERT ... SELECT * FROM unnest(...)` binding a column array per column
+    async fn insert_unnest(client: &Client, rows: &[Self]) -> Result<u64>;
+}
+
+/// `row`'s values for each of `spec`'s columns, in the spec's order.
+pub(crate) fn bind_columns<'a, T: BulkInsert>(
+    row: &'a T,
+    spec: &TableSpec,
+) -> Vec<&'a (dyn ToSql + Sync)> {
+    spec.columns.iter().map(|c| row.column(c.name)).collect()
+}
+
+/// Like `bind_columns`, but binds `value` to the column `name` in place of
+/// the row's own, for variant tables that store one column as another type.
+pub(crate) fn bind_columns_with<'a, T: BulkInsert>(
+    row: &'a T,
+    spec: &TableSpec,
+    (name, value): (&str, &'a (dyn ToSql + Sync)),
+) -> Vec<&'a (dyn ToSql + Sync)> {
+    if !spec.columns.iter().any(|c| c.name == name) {
+        unknown_column(spec, name);
+    }
+    spec.columns
+        .iter()
+        .map(|c| {
+            if c.name == name {
+                value
+            } else {
+                row.column(c.name)
+            }
+        })
+        .collect()
+}
+
+/// Panics for a column `BulkInsert::column` has no value for, which means a
+/// `TableSpec` names a column its model doesn't map.
+fn unknown_column(spec: &TableSpec, name: &str) -> ! {
+    panic!("{} has no value for column {:?}", spec.table, name)
+}
+
+/// Binary COPYs `rows` into `spec`'s table, binding each row with `bind` in the
+/// spec's column order, and returns the row count the server reported.
+pub(crate) async fn copy_rows<T>(
+    client: &Client,
+    spec: &TableSpec,
+    rows: &[T],
+    bigint_blocks: bool,
+    bind: impl Fn(&T) -> Vec<&(dyn ToSql + Sync)>,
+) -> Result<u64> {
+    let statement = spec.copy_statement();
+    write_copy(client, &statement, &spec.types(bigint_blocks), rows, bind).await
+}
+
+/// `COPY ... FREEZE`s `rows` into the `T` table, which must have been created
+/// or truncated earlier in the same transaction; fails with an explanation if
+/// it wasn't.
+pub(crate) async fn copy_rows_frozen<T: BulkInsert>(client: &Client, rows: &[T]) -> Result<u64> {
+    let statement = T::SPEC.copy_freeze_statement();
+    let types = T::SPEC.types(false);
+    match write_copy(client, &statement, &types, rows, T::params).await {
+        Err(BenchmarkError::DatabaseError(e))
+            if e.code() == Some(&SqlState::OBJECT_NOT_IN_PREREQUISITE_STATE) =>
+        {
+            Err(BenchmarkError::ConfigError(format!(
+                "COPY FREEZE into {} needs the table created or truncated earlier in the same transaction: {}",
+                T::SPEC.table,
+                e
+            )))
+        }
+        copied => copied,
+    }
+}
+
+async fn write_copy<T>(
+    client: &Client,
+    statement: &str,
+    types: &[Type],
+    rows: &[T],
+    bind: impl Fn(&T) -> Vec<&(dyn ToSql + Sync)>,
+) -> Result<u64> {
+    let sink = client.copy_in(statement).await?;
+    let writer = BinaryCopyInWriter::new(sink, types);
+    pin_mut!(writer);
+    for row in rows {
+        writer.as_mut().write(&bind(row)).await?;
+    }
+    Ok(writer.as_mut().finish().await?)
+}
+
+/// Writes one batch with the selected insert method, returning the rows the server reported.
+async fn insert_batch<T: BulkInsert>(client: &Client, args: &Args, rows: &[T]) -> Result<u64> {
+    match args.insert_method {
+        InsertMethod::Copy => T::copy_in(client, rows, args.bigint_blocks).await,
+        InsertMethod::Insert => {
+            let statement = client.prepare(T::INSERT).await?;
+            let mut inserted = 0;
+            for row in rows {
+                inserted += client.execute(&statement, &row.params()).await?;
+            }
+            Ok(inserted)
+        }
+        InsertMethod::Unnest => T::insert_unnest(client, rows).await,
+        InsertMethod::Pipelined => {
+            // tokio-postgres pipelines every statement that is in flight at the same time
+            let statement = client.prepare(T::INSERT).await?;
+            let mut inserted = 0;
+            for chunk in rows.chunks(PIPELINE_DEPTH) {
+                let counts = try_join_all(
+                    chunk
+                        .iter()
+                        .map(|row| client.execute_raw(&statement, row.params())),
+                )
+                .await?;
+                inserted += counts.iter().sum::<u64>();
+            }
+            Ok(inserted)
+        }
+    }
+}
+
+/// COPYs `rows` in `batch_size` chunks into the `T` table the search_path
+/// resolves to, returning the time taken.
+pub(crate) async fn copy_all<T: BulkInsert>(
+    client: &Client,
+    rows: &[T],
+    batch_size: usize,
+) -> Result<Duration> {
+    let start = Instant::now();
+    for batch in rows.chunks(batch_size.max(1)) {
+        T::copy_in(client, batch, false).await?;
+    }
+    Ok(start.elapsed())
+}
+
+/// Writes one batch and checks the reported row count, adding the rows the
+/// server reported written to `written` and returning how many were skipped.
+///
+/// Under `--skip-bad-rows` a batch the server rejects is retried one INSERT per
+/// row, logging and skipping the rows that still fail, since a failed COPY
+/// doesn't say which row caused it. Methods that write a batch one statement
+/// per row then run it in a transaction, rolled back before the retry so rows
+/// written ahead of the bad one aren't inserted twice. A batch failed by
+/// `--inject-failure` that this writes in full counts as recovered.
+async fn write_batch<T: BulkInsert>(
+    client: &Client,
+    args: &Args,
+    table: &str,
+    batch: usize,
+    rows: &[T],
+    written: &AtomicUsize,
+) -> Result<usize> {
+    if args.sample_output && batch == 1 {
+        if let Some(row) = rows.first() {
+            print_sample(table, row, args.bigint_blocks);
+        }
+    }
+    let injected = !rows.is_empty() && faults::injects(args, batch);
+    let attempt = if injected {
+        faults::fail_batch(client, table, batch).await
+    } else if args.skip_bad_rows && !args.insert_method.writes_batch_atomically() {
+        in_transaction(client, insert_batch(client, args, rows)).await
+    } else {
+        insert_batch(client, args, rows).await
+    };
+    let error = match attempt {
+        Ok(copied) => {
+            verify_copied(args, table, batch, rows.len(), copied)?;
+            written.fetch_add(copied as usize, Ordering::Relaxed);
+            return Ok(0);
+        }
+        Err(BenchmarkError::DatabaseError(e)) if args.skip_bad_rows && !e.is_closed() => e,
+        Err(e) => return Err(e),
+    };
+    eprintln!(
+        "{} batch {} failed ({}), retrying row by row",
+        table, batch, error
+    );
+    let statement = client.prepare(T::INSERT).await?;
+    let mut skipped = 0;
+    for row in rows {
+        match client.execute(&statement, &row.params()).await {
+            Ok(inserted) => {
+                written.fetch_add(inserted as usize, Ordering::Relaxed);
+            }
+            Err(e) if e.is_closed() => return Err(e.into()),
+            Err(e) => {
+                eprintln!("  skipped {} row {}: {}", table, row.key(), e);
+                skipped += 1;
+            }
+        }
+    }
+    if injected && skipped == 0 {
+        faults::record_recovered();
+    }
+    Ok(skipped)
+}
+
+/// Runs `write` in a transaction, committing it if it succeeds and rolling it
+/// back if it fails.
+async fn in_transaction(
+    client: &Client,
+    write: impl std::future::Future<Output = Result<u64>>,
+) -> Result<u64> {
+    client.batch_execute("BEGIN").await?;
+    match write.await {
+        Ok(written) => {
+            client.batch_execute("COMMIT").await?;
+            Ok(written)
+        }
+        Err(e) => {
+            client.batch_execute("ROLLBACK").await?;
+            Err(e)
+        }
+    }
+}
+
+/// Prints a table's first row as it is bound: each COPY column with its binary
+/// type and the value handed to `ToSql`, for `--sample-output`.
+fn print_sample<T: BulkInsert>(table: &str, row: &T, bigint_blocks: bool) {
+    println!("First {} row ({}):", table, row.key());
+    let columns = T::SPEC.columns.iter().zip(T::SPEC.types(bigint_blocks));
+    for ((column, ty), value) in columns.zip(row.params()) {
+        println!("  {:<16} {:<6} {:?}", column.name, ty, value);
+    }
+}
+
+/// Under `--verify-row-count-per-batch`, fails if the row count COPY reported
+/// for a batch differs from the rows written into it.
+fn verify_copied(
+    args: &Args,
+    table: &str,
+    batch: usize,
+    written: usize,
+    copied: u64,
+) -> Result<()> {
+    if args.verify_row_count_per_batch && copied != written as u64 {
+        return Err(BenchmarkError::CopyFailed {
+            table: table.to_string(),
+            batch,
+            written,
+            copied,
+        });
+    }
+    Ok(())
+}
+
+/// Returns `rows[start..end]`, clamped to the rows available in a shorter table.
+pub(crate) fn batch_slice<T>(rows: &[T], start: usize, end: usize) -> &[T] {
+    &rows[start.min(rows.len())..end.min(rows.len())]
+}
+
+/// Number of `batch_size` batches needed for `total` records, treating a
+/// batch size of 0 as 1. Never overflows, unlike `(total + batch_size - 1) / batch_size`.
+pub fn compute_batches(total: usize, batch_size: usize) -> usize {
+    total.div_ceil(batch_size.max(1))
+}
+
+/// COPYs the dataset in batches, one binary COPY per table per batch.
+#[tracing::instrument(name = "phase", skip_all, fields(phase = "bulk_insert"))]
+pub async fn run_bulk_insert(
+    client: &Client,
+    args: &Args,
+    dataset: &Dataset,
+) -> Result<PhaseResult> {
+    let start = Instant::now();
+    let total_records = dataset.table_lens().into_iter().max().unwrap_or(0);
+    let batch_size = args.batch_size.unwrap_or(total_records / 100).max(1);
+    let num_batches = compute_batches(total_records, batch_size);
+
+    println!("\nStarting Bulk Insert Tests:");
+    println!("Total batches: {}", num_batches);
+    println!("Batch size: {}", batch_size);
+    println!("Insert method: {:?}", args.insert_method);
+
+    let mut dashboard = if args.tui && io::stdout().is_terminal() {
+        Some(tui::Dashboard::start(num_batches)?)
+    } else {
+        if args.tui {
+            println!("stdout is not a terminal, falling back to plain output");
+        }
+        None
+    };
+
+    let mut batch_latencies = Vec::with_capacity(num_batches);
+    // Counted from what the server reports, since batch_slice truncates the shorter tables
+    let written: [AtomicUsize; 4] = Default::default();
+    let mut skipped = [0; 4];
+    let mut table_totals = [Duration::ZERO; 4];
+    for i in 0..num_batches {
+        let batch_start = Instant::now();
+        let mut table_time = [Duration::ZERO; 4];
+        // i < num_batches keeps the start below total_records
+        let start_index = i * batch_size;
+        let end_index = start_index.saturating_add(batch_size).min(total_records);
+
+        // Get batches for each type
+        let block_batch = batch_slice(&dataset.blocks, start_index, end_index);
+        let transaction_batch = batch_slice(&dataset.transactions, start_index, end_index);
+        let transfer_batch = batch_slice(&dataset.transfers, start_index, end_index);
+        let pool_batch = batch_slice(&dataset.pools, start_index, end_index);
+
+        // 1. Bulk insert blocks
+        let table_start = Instant::now();
+        skipped[0] += write_batch(client, args, "blocks", i + 1, block_batch, &written[0]).await?;
+        table_time[0] = table_start.elapsed();
+
+        // 2. Bulk insert transactions
+        let table_start = Instant::now();
+        skipped[1] += write_batch(
+            client,
+            args,
+            "transactions",
+            i + 1,
+            transaction_batch,
+            &written[1],
+        )
+        .await?;
+        table_time[1] = table_start.elapsed();
+
+        // 3. Bulk insert transfers
+        let table_start = Instant::now();
+        skipped[2] += write_batch(
+            client,
+            args,
+            "transfers",
+            i + 1,
+            transfer_batch,
+            &written[2],
+        )
+        .await?;
+        table_time[2] = table_start.elapsed();
+
+        // 4. Bulk insert pools
+        let table_start = Instant::now();
+        skipped[3] += write_batch(client, args, "pools", i + 1, pool_batch, &written[3]).await?;
+        table_time[3] = table_start.elapsed();
+
+        batch_latencies.push(batch_start.elapsed());
+        for (total, time) in table_totals.iter_mut().zip(table_time) {
+            *total += time;
+        }
+        if let Some(dashboard) = dashboard.as_mut() {
+            let rows = [
+                block_batch.len(),
+                transaction_batch.len(),
+                transfer_batch.len(),
+                pool_batch.len(),
+            ];
+            dashboard.record_batch(rows, table_time, batch_start.elapsed())?;
+        } else if i % 10 == 0 || i == num_batches - 1 {
+            println!("Processed batch {}/{}", i + 1, num_batches);
+        }
+    }
+    drop(dashboard);
+
+    let bulk_insert_duration = start.elapsed();
+    println!("\nBulk Insert Test Results:");
+    println!("-------------------------");
+    let written = written.map(AtomicUsize::into_inner);
+    println!("Total records processed:");
+    for (table, rows) in ["Blocks", "Transactions", "Transfers", "Pools"]
+        .into_iter()
+        .zip(written)
+        .zip(TABLES)
+        .filter(|(_, table)| args.includes_table(table))
+        .map(|(label, _)| label)
+    {
+        println!("  {}: {}", table, rows);
+    }
+    if args.skip_bad_rows {
+        println!(
+            "Rows skipped (--skip-bad-rows): {}",
+            skipped.iter().sum::<usize>()
+        );
+    }
+    faults::print_summary(args);
+    let inserted: usize = written.iter().sum();
+    println!("Total duration: {:?}", bulk_insert_duration);
+    println!(
+        "Average insertion rate: {} records/sec",
+        inserted as f64 / bulk_insert_duration.as_secs_f64()
+    );
+
+    let tables = TABLES
+        .into_iter()
+        .zip(written)
+        .zip(table_totals)
+        .map(|((table, rows), time)| (table, rows, time))
+        .filter(|(table, _, _)| args.includes_table(table))
+        .collect();
+    Ok(
+        PhaseResult::new("bulk_insert", inserted, bulk_insert_duration)
+            .with_p50(report::percentile(&mut batch_latencies, 50.0))
+            .with_p99(report::percentile(&mut batch_latencies, 99.0))
+            .with_tables(tables),
+    )
+}
+
+/// Streams each selected table's JSON file straight into COPY batches without
+/// loading the dataset into memory, one table to completion after another.
+///
+/// See `stream::stream_records` for the batching contract.
+#[tracing::instrument(name = "phase", skip_all, fields(phase = "bulk_insert"))]
+pub async fn run_streaming_insert(client: &Client, args: &Args) -> Result<PhaseResult> {
+    let batch_size = args.batch_size.unwrap_or(DEFAULT_STREAM_BATCH_SIZE).max(1);
+    println!("\nStarting Streaming Insert Tests:");
+    println!("Batch size: {}", batch_size);
+    println!("Insert method: {:?}", args.insert_method);
+
+    let start = Instant::now();
+    let written: [AtomicUsize; 4] = Default::default();
+    let mut skipped = 0;
+    let mut tables = Vec::new();
+    for (i, table) in TABLES.into_iter().enumerate() {
+        if !args.includes_table(table) {
+            continue;
+        }
+        let table_start = Instant::now();
+        let source = DataSource::for_table(args, table);
+        let counter = &written[i];
+        skipped += match table {
+            "blocks" => {
+                stream_table::<Block>(client, args, table, source, batch_size, counter).await?
+            }
+            "transactions" => {
+                stream_table::<Transaction>(client, args, table, source, batch_size, counter)
+                    .await?
+            }
+            "transfers" => {
+                stream_table::<Transfer>(client, args, table, source, batch_size, counter).await?
+            }
+            _ => stream_table::<Pool>(client, args, table, source, batch_size, counter).await?,
+        };
+        let streamed = counter.load(Ordering::Relaxed);
+        tables.push((table, streamed, table_start.elapsed()));
+        println!("Streamed {} {}", streamed, table);
+    }
+    let duration = start.elapsed();
+    let rows = written.map(AtomicUsize::into_inner);
+    let total: usize = rows.iter().sum();
+
+    println!("\nStreaming Insert Test Results:");
+    println!("-------------------------");
+    println!("Total records processed:");
+    for (table, rows) in ["Blocks", "Transactions", "Transfers", "Pools"]
+        .into_iter()
+        .zip(rows)
+        .zip(TABLES)
+        .filter(|(_, table)| args.includes_table(table))
+        .map(|(label, _)| label)
+    {
+        println!("  {}: {}", table, rows);
+    }
+    if args.skip_bad_rows {
+        println!("Rows skipped (--skip-bad-rows): {}", skipped);
+    }
+    faults::print_summary(args);
+    println!("Total duration: {:?}", duration);
+    println!(
+        "Average insertion rate: {} records/sec",
+        total as f64 / duration.as_secs_f64()
+    );
+
+    Ok(PhaseResult::new("bulk_insert", total, duration).with_tables(tables))
+}
+
+/// COPYs every batch streamed from one table's file, counting the rows
+/// written into `written` and returning how many were skipped.
+async fn stream_table<T>(
+    client: &Client,
+    args: &Args,
+    table: &str,
+    source: DataSource,
+    batch_size: usize,
+    written: &AtomicUsize,
+) -> Result<usize>
+where
+    T: BulkInsert + DeserializeOwned + Send + 'static,
+{
+    let (mut batches, reader) = stream::stream_records::<T>(source, args.ndjson, batch_size);
+    let mut skipped = 0;
+    let mut batch_number = 0;
+    while let Some(batch) = batches.recv().await {
+        batch_number += 1;
+        skipped += write_batch(client, args, table, batch_number, &batch, written).await?;
+    }
+    reader.await.map_err(io::Error::other)??;
+    Ok(skipped)
+}
+
+/// Runs a blocks statement taking one array per column, in COPY column order,
+/// such as `BLOCKS_UNNEST` or `BLOCKS_UPSERT`.
+pub(crate) async fn unnest_blocks(client: &Client, statement: &str, rows: &[Block]) -> Result<u64> {
+    let block_number: Vec<BlockHeight> = rows.iter().map(|r| r.block_number).collect();
+    let block_hash: Vec<&str> = rows.iter().map(|r| r.block_hash.as_str()).collect();
+    let parent_hash: Vec<&str> = rows.iter().map(|r| r.parent_hash.as_str()).collect();
+    let block_timestamp: Vec<&str> = rows.iter().map(|r| r.block_timestamp.as_str()).collect();
+    let created_at: Vec<&str> = rows.iter().map(|r| r.created_at.as_str()).collect();
+    let updated_at: Vec<&str> = rows.iter().map(|r| r.updated_at.as_str()).collect();
+    Ok(client
+        .execute(
+            statement,
+            &[
+                &block_number,
+                &block_hash,
+                &parent_hash,
+                &block_timestamp,
+                &created_at,
+                &updated_at,
+            ],
+        )
+        .await?)
+}
+
+impl BulkInsert for Block {
+    const SPEC: &'static TableSpec = &schema::BLOCKS;
+    const INSERT: &'static str = schema::BLOCKS_INSERT;
+
+    fn key(&self) -> String {
+        format!("block_number={}", self.block_number.0)
+    }
+
+    fn column(&self, name: &str) -> &(dyn ToSql + Sync) {
+        match name {
+            "block_number" => &self.block_number,
+            "block_hash" => &self.block_hash,
+            "parent_hash" => &self.parent_hash,
+            "block_timestamp" => &self.block_timestamp,
+            "created_at" => &self.created_at,
+            "updated_at" => &self.updated_at,
+            _ => unknown_column(Self::SPEC, name),
+        }
+    }
+
+    async fn insert_unnest(client: &Client, rows: &[Self]) -> Result<u64> {
+        unnest_blocks(client, schema::BLOCKS_UNNEST, rows).await
+    }
+}
+
+impl BulkInsert for Transaction {
+    const SPEC: &'static TableSpec = &schema::TRANSACTIONS;
+    const INSERT: &'static str = schema::TRANSACTIONS_INSERT;
+
+    fn key(&self) -> String {
+        format!("hash={}", self.hash)
+    }
+
+    fn column(&self, name: &str) -> &(dyn ToSql + Sync) {
+        match name {
+            "block" => &self.block,
+            "index" => &self.index,
+            "timestamp" => &self.timestamp,
+            "hash" => &self.hash,
+            "from_address" => &self.from,
+            "to_address" => &self.to,
+            "value" => &self.value,
+            _ => unknown_column(Self::SPEC, name),
+        }
+    }
+
+    async fn insert_unnest(client: &Client, rows: &[Self]) -> Result<u64> {
+        let block: Vec<BlockHeight> = rows.iter().map(|r| r.block).collect();
+        let index: Vec<i32> = rows.iter().map(|r| r.index).collect();
+        let timestamp: Vec<&str> = rows.iter().map(|r| r.timestamp.as_str()).collect();
+        let hash: Vec<&str> = rows.iter().map(|r| r.hash.as_str()).collect();
+        let from: Vec<&str> = rows.iter().map(|r| r.from.as_str()).collect();
+        let to: Vec<&str> = rows.iter().map(|r| r.to.as_str()).collect();
+        let value: Vec<&str> = rows.iter().map(|r| r.value.as_str()).collect();
+        Ok(client
+            .execute(
+                schema::TRANSACTIONS_UNNEST,
+                &[&block, &index, &timestamp, &hash, &from, &to, &value],
+            )
+            .await?)
+    }
+}
+
+impl BulkInsert for Transfer {
+    const SPEC: &'static TableSpec = &schema::TRANSFERS;
+    const INSERT: &'static str = schema::TRANSFERS_INSERT;
+
+    fn key(&self) -> String {
+        format!("tx_hash={}", self.tx_hash)
+    }
+
+    fn column(&self, name: &str) -> &(dyn ToSql + Sync) {
+        match name {
+            "tx_hash" => &self.tx_hash,
+            "block_number" => &self.block_number,
+            "token" => &self.token,
+            "from_address" => &self.from,
+            "to_address" => &self.to,
+            "amount" => &self.amount,
+            _ => unknown_column(Self::SPEC, name),
+        }
+    }
+
+    async fn insert_unnest(client: &Client, rows: &[Self]) -> Result<u64> {
+        let tx_hash: Vec<&str> = rows.iter().map(|r| r.tx_hash.as_str()).collect();
+        let block_number: Vec<BlockHeight> = rows.iter().map(|r| r.block_number).collect();
+        let token: Vec<&str> = rows.iter().map(|r| r.token.as_str()).collect();
+        let from: Vec<&str> = rows.iter().map(|r| r.from.as_str()).collect();
+        let to: Vec<&str> = rows.iter().map(|r| r.to.as_str()).collect();
+        let amount: Vec<&str> = rows.iter().map(|r| r.amount.as_str()).collect();
+        Ok(client
+            .execute(
+                schema::TRANSFERS_UNNEST,
+                &[&tx_hash, &block_number, &token, &from, &to, &amount],
+            )
+            .await?)
+    }
+}
+
+impl BulkInsert for Pool {
+    const SPEC: &'static TableSpec = &schema::POOLS;
+    const INSERT: &'static str = schema::POOLS_INSERT;
+
+    fn key(&self) -> String {
+        format!("address={}", self.address)
+    }
+
+    fn column(&self, name: &str) -> &(dyn ToSql + Sync) {
+        match name {
+            "deployer" => &self.deployer,
+            "address" => &self.address,
+            "quote_token" => &self.quote_token,
+            "token" => &self.token,
+            "init_block" => &self.init_block,
+            "created_at" => &self.created_at,
+            _ => unknown_column(Self::SPEC, name),
+        }
+    }
+
+    async fn insert_unnest(client: &Client, rows: &[Self]) -> Result<u64> {
+        let deployer: Vec<&str> = rows.iter().map(|r| r.deployer.as_str()).collect();
+        let address: Vec<&str> = rows.iter().map(|r| r.address.as_str()).collect();
+        let quote_token: Vec<&str> = rows.iter().map(|r| r.quote_token.as_str()).collect();
+        let token: Vec<&str> = rows.iter().map(|r| r.token.as_str()).collect();
+        let init_block: Vec<BlockHeight> = rows.iter().map(|r| r.init_block).collect();
+        let created_at: Vec<i64> = rows.iter().map(|r| r.created_at).collect();
+        Ok(client
+            .execute(
+                schema::POOLS_UNNEST,
+                &[
+                    &deployer,
+                    &address,
+                    &quote_token,
+                    &token,
+                    &init_block,
+                    &created_at,
+                ],
+            )
+            .await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytes::BytesMut;
+
+    /// Encodes `row` the way `copy_rows` binds it, failing on a value count or
+    /// type that doesn't match the table's spec.
+    fn bind_row<T: BulkInsert>(row: &T, bigint_blocks: bool) {
+        let params = row.params();
+        let types = T::SPEC.types(bigint_blocks);
+        assert_eq!(params.len(), types.len(), "{}", T::SPEC.table);
+        let mut buf = BytesMut::new();
+        for ((param, ty), column) in params.iter().zip(&types).zip(T::SPEC.columns) {
+            param
+                .to_sql_checked(ty, &mut buf)
+                .unwrap_or_else(|e| panic!("{}.{} as {}: {}", T::SPEC.table, column.name, ty, e));
+        }
+    }
+
+    #[test]
+    fn params_bind_to_every_spec_column() {
+        let block = Block {
+            block_number: BlockHeight(1),
+            block_hash: "0xb".to_string(),
+            parent_hash: "0xa".to_string(),
+            block_timestamp: "2024-01-01T00:00:00Z".to_string(),
+            created_at: "2024-01-01T00:00:00Z".to_string(),
+            updated_at: "2024-01-01T00:00:00Z".to_string(),
+        };
+        let transaction = Transaction {
+            block: BlockHeight(1),
+            index: 0,
+            timestamp: "2024-01-01T00:00:00Z".to_string(),
+            hash: "0xt".to_string(),
+            from: "0x1".to_string(),
+            to: "0x2".to_string(),
+            value: "100".to_string(),
+        };
+        let transfer = Transfer {
+            tx_hash: "0xt".to_string(),
+            block_number: BlockHeight(1),
+            token: "0xc".to_string(),
+            from: "0x1".to_string(),
+            to: "0x2".to_string(),
+            amount: "100".to_string(),
+        };
+        let pool = Pool {
+            deployer: "0x1".to_string(),
+            address: "0xp".to_string(),
+            quote_token: "0xq".to_string(),
+            token: "0xc".to_string(),
+            init_block: BlockHeight(1),
+            created_at: 1_700_000_000,
+        };
+        for bigint_blocks in [false, true] {
+            bind_row(&block, bigint_blocks);
+            bind_row(&transaction, bigint_blocks);
+            bind_row(&transfer, bigint_blocks);
+            bind_row(&pool, bigint_blocks);
+        }
+    }
+
+    /// Binary encoding of `value` as `ty`, to compare bound values.
+    fn encoded(value: &(dyn ToSql + Sync), ty: &Type) -> BytesMut {
+        let mut buf = BytesMut::new();
+        value.to_sql_checked(ty, &mut buf).unwrap();
+        buf
+    }
+
+    /// A transaction model with its fields declared in another order than
+    /// `Transaction` and the transactions spec.
+    struct ReorderedTransaction {
+        value: String,
+        to: String,
+        hash: String,
+        from: String,
+        index: i32,
+        timestamp: String,
+        block: BlockHeight,
+    }
+
+    impl BulkInsert for ReorderedTransaction {
+        const SPEC: &'static TableSpec = &schema::TRANSACTIONS;
+        const INSERT: &'static str = schema::TRANSACTIONS_INSERT;
+
+        fn key(&self) -> String {
+            format!("hash={}", self.hash)
+        }
+
+        fn column(&self, name: &str) -> &(dyn ToSql + Sync) {
+            match name {
+                "value" => &self.value,
+                "to_address" => &self.to,
+                "hash" => &self.hash,
+                "from_address" => &self.from,
+                "index" => &self.index,
+                "timestamp" => &self.timestamp,
+                "block" => &self.block,
+                _ => unknown_column(Self::SPEC, name),
+            }
+        }
+
+        async fn insert_unnest(_: &Client, _: &[Self]) -> Result<u64> {
+            unreachable!("only bound, never inserted")
+        }
+    }
+
+    /// Fails unless `bound` encodes the same values as `expected` for each of
+    /// `spec`'s columns.
+    fn assert_bound(
+        bound: &[&(dyn ToSql + Sync)],
+        expected: &[&(dyn ToSql + Sync)],
+        spec: &TableSpec,
+    ) {
+        let types = spec.types(false);
+        assert_eq!(bound.len(), expected.len());
+        for (((value, expected), ty), column) in
+            bound.iter().zip(expected).zip(&types).zip(spec.columns)
+        {
+            assert_eq!(
+                encoded(*value, ty),
+                encoded(*expected, ty),
+                "{}",
+                column.name
+            );
+        }
+    }
+
+    #[test]
+    fn values_bind_by_column_name_not_position() {
+        use crate::schema::{column, height};
+
+        // The transactions columns shuffled, with the address pair swapped
+        const SHUFFLED: TableSpec = TableSpec {
+            table: "transactions",
+            columns: &[
+                column("to_address", Type::TEXT),
+                column("value", Type::TEXT),
+                column("from_address", Type::TEXT),
+                height("block"),
+                column("hash", Type::TEXT),
+                column("index", Type::INT4),
+                column("timestamp", Type::TEXT),
+            ],
+        };
+        let transaction = Transaction {
+            block: BlockHeight(7),
+            index: 3,
+            timestamp: "2024-01-01T00:00:00Z".to_string(),
+            hash: "0xt".to_string(),
+            from: "0xsender".to_string(),
+            to: "0xrecipient".to_string(),
+            value: "100".to_string(),
+        };
+        let reordered = ReorderedTransaction {
+            value: transaction.value.clone(),
+            to: transaction.to.clone(),
+            hash: transaction.hash.clone(),
+            from: transaction.from.clone(),
+            index: transaction.index,
+            timestamp: transaction.timestamp.clone(),
+            block: transaction.block,
+        };
+        let expected: [&(dyn ToSql + Sync); 7] = [
+            &transaction.to,
+            &transaction.value,
+            &transaction.from,
+            &transaction.block,
+            &transaction.hash,
+            &transaction.index,
+            &transaction.timestamp,
+        ];
+        assert_bound(&bind_columns(&transaction, &SHUFFLED), &expected, &SHUFFLED);
+        assert_bound(&bind_columns(&reordered, &SHUFFLED), &expected, &SHUFFLED);
+        assert_bound(
+            &reordered.params(),
+            &transaction.params(),
+            &schema::TRANSACTIONS,
+        );
+
+        // An override replaces only its own column, wherever the spec puts it
+        let timestamp = "2025-06-01T00:00:00Z".to_string();
+        let mut expected = expected;
+        expected[6] = &timestamp;
+        for bound in [
+            bind_columns_with(&transaction, &SHUFFLED, ("timestamp", &timestamp)),
+            bind_columns_with(&reordered, &SHUFFLED, ("timestamp", &timestamp)),
+        ] {
+            assert_bound(&bound, &expected, &SHUFFLED);
+        }
+    }
+
+    /// Fails if `T::INSERT` doesn't list `T::SPEC`'s columns in spec order.
+    fn insert_follows_spec<T: BulkInsert>() {
+        let columns: Vec<&str> = T::SPEC.columns.iter().map(|c| c.name).collect();
+        let prefix = format!(
+            "INSERT INTO {} ({}) VALUES",
+            T::SPEC.table,
+            columns.join(", ")
+        );
+        assert!(T::INSERT.starts_with(&prefix), "{}", T::INSERT);
+    }
+
+    #[test]
+    fn inserts_list_the_spec_columns() {
+        insert_follows_spec::<Block>();
+        insert_follows_spec::<Transaction>();
+        insert_follows_spec::<Transfer>();
+        insert_follows_spec::<Pool>();
+    }
+
+    /// Needs a server: runs against DATABASE_URL, and passes without one.
+    #[tokio::test]
+    async fn skipping_a_bad_row_keeps_the_rest_of_an_insert_batch_once() {
+        use clap::Parser;
+
+        let Ok(database_url) = std::env::var("DATABASE_URL") else {
+            eprintln!("skipped, DATABASE_URL isn't set");
+            return;
+        };
+        let client = crate::db::connect(&database_url).await.unwrap();
+        let ddl = format!(
+            "{}ALTER TABLE transactions ADD CHECK (value <> 'bad');",
+            crate::variants::plain_transactions_ddl()
+        );
+        let previous = schema::enter_scratch_schema(&client, "skip_bad_rows_test", &ddl)
+            .await
+            .unwrap();
+        let rows: Vec<Transaction> = ["1", "bad", "3"]
+            .into_iter()
+            .enumerate()
+            .map(|(index, value)| Transaction {
+                block: BlockHeight(1),
+                index: index as i32,
+                timestamp: "2024-01-01T00:00:00Z".to_string(),
+                hash: format!("0x{}", index),
+                from: "0x1".to_string(),
+                to: "0x2".to_string(),
+                value: value.to_string(),
+            })
+            .collect();
+        let args = Args::parse_from(["pgsql", "--insert-method", "insert", "--skip-bad-rows"]);
+        let written = AtomicUsize::new(0);
+
+        let skipped = write_batch(&client, &args, "transactions", 1, &rows, &written).await;
+        let stored: i64 = client
+            .query_one("SELECT COUNT(*) FROM transactions", &[])
+            .await
+            .unwrap()
+            .get(0);
+        schema::leave_scratch_schema(&client, "skip_bad_rows_test", &previous)
+            .await
+            .unwrap();
+
+        assert_eq!(skipped.unwrap(), 1);
+        assert_eq!(written.load(Ordering::Relaxed), 2);
+        assert_eq!(stored, 2);
+    }
+
+    #[test]
+    fn compute_batches_handles_edge_cases() {
+        assert_eq!(compute_batches(0, 100), 0);
+        assert_eq!(compute_batches(0, 0), 0);
+        assert_eq!(compute_batches(5, 0), 5);
+        assert_eq!(compute_batches(5, 100), 1);
+        assert_eq!(compute_batches(100, 100), 1);
+        assert_eq!(compute_batches(101, 100), 2);
+        assert_eq!(compute_batches(usize::MAX, 1), usize::MAX);
+        assert_eq!(compute_batches(usize::MAX, 2), usize::MAX / 2 + 1);
+        assert_eq!(compute_batches(usize::MAX, usize::MAX), 1);
+        assert_eq!(compute_batches(1, usize::MAX), 1);
+    }
+}
@@ -0,0 +1,247 @@
+/// A benchmark phase as described by `--list-benchmarks`.
+pub struct Benchmark {
+    pub name: &'static str,
+    pub description: &'static str,
+    /// Options that enable or tune the phase
+    pub flags: &'static [&'static str],
+}
+
+/// Every phase the tool can run, in the order a run performs them. New phases
+/// should be added here so `--list-benchmarks` stays complete.
+pub const BENCHMARKS: &[Benchmark] = &[
+    Benchmark {
+        name: "bulk_insert",
+        description: "Loads the dataset into the four tables in batches (always runs)",
+        flags: &[
+            "--batch-size",
+            "--tables",
+            "--insert-method",
+            "--stream",
+            "--stdin-table",
+            "--temp-tables",
+            "--db-schema",
+            "--bigint-blocks",
+            "--verify-row-count-per-batch",
+            "--skip-bad-rows",
+            "--sample-output",
+            "--expect-rows",
+            "--tui",
+            "--table-stats-interval",
+            "--table-stats-csv",
+            "--replica-url",
+            "--flamegraph",
+        ],
+    },
+    Benchmark {
+        name: "analyze",
+        description: "Refreshes planner statistics after the load (runs unless skipped)",
+        flags: &["--skip-analyze"],
+    },
+    Benchmark {
+        name: "vacuum_full",
+        description: "VACUUM (FULL, ANALYZE) after each writing phase, with space reclaimed",
+        flags: &["--vacuum-between-phases"],
+    },
+    Benchmark {
+        name: "check_chain",
+        description: "Counts blocks whose parent_hash matches no loaded block",
+        flags: &["--check-chain"],
+    },
+    Benchmark {
+        name: "toast_benchmark",
+        description: "Insert and read cost of TOASTed wide rows against inline rows",
+        flags: &["--toast-pad-bytes"],
+    },
+    Benchmark {
+        name: "key_benchmark",
+        description: "COPY throughput with SERIAL surrogate keys against natural keys",
+        flags: &["--key-bench", "--batch-size"],
+    },
+    Benchmark {
+        name: "insert_order_benchmark",
+        description: "COPY of blocks into an indexed table in descending against ascending order",
+        flags: &["--reverse", "--batch-size"],
+    },
+    Benchmark {
+        name: "deferred_fk_benchmark",
+        description: "Batched COPY with a foreign key checked immediately against deferred",
+        flags: &["--deferred-fk-bench", "--batch-size"],
+    },
+    Benchmark {
+        name: "copy_freeze_benchmark",
+        description: "Plain COPY against COPY ... FREEZE, each with the VACUUM (FREEZE) after it",
+        flags: &["--copy-freeze", "--batch-size"],
+    },
+    Benchmark {
+        name: "send_buffer_benchmark",
+        description:
+            "Transactions COPY throughput over connections with several TCP send buffer sizes",
+        flags: &["--send-buffer-bench", "--batch-size"],
+    },
+    Benchmark {
+        name: "encoding_benchmark",
+        description: "Transfers COPY throughput under UTF8, LATIN1 and SQL_ASCII client encodings",
+        flags: &["--encoding-bench", "--batch-size"],
+    },
+    Benchmark {
+        name: "array_column_benchmark",
+        description: "COPY throughput and size with a TEXT[] topics column on transfers",
+        flags: &["--array-bench", "--batch-size"],
+    },
+    Benchmark {
+        name: "timestamp_benchmark",
+        description: "COPY and time-range queries with TEXT against TIMESTAMPTZ timestamps",
+        flags: &["--timestamp-bench", "--timestamp-format", "--batch-size"],
+    },
+    Benchmark {
+        name: "numeric_amount_benchmark",
+        description: "COPY and SUM with TEXT against NUMERIC transfer amounts, scale checked",
+        flags: &["--numeric-scale", "--strict-numeric-scale", "--batch-size"],
+    },
+    Benchmark {
+        name: "index_bloat_benchmark",
+        description: "Index growth and HOT updates after random UPDATEs of indexed columns",
+        flags: &["--index-bloat-bench", "--batch-size"],
+    },
+    Benchmark {
+        name: "upsert_update_benchmark",
+        description: "INSERT ... ON CONFLICT DO UPDATE throughput with a share of existing keys",
+        flags: &["--upsert-bench", "--conflict-ratio", "--batch-size"],
+    },
+    Benchmark {
+        name: "merge_benchmark",
+        description: "MERGE against INSERT ... ON CONFLICT for the same upsert (Postgres 15+)",
+        flags: &["--merge-bench", "--conflict-ratio", "--batch-size"],
+    },
+    Benchmark {
+        name: "generated_column_benchmark",
+        description: "COPY throughput and size with a stored generated column",
+        flags: &["--generated-column-bench", "--batch-size"],
+    },
+    Benchmark {
+        name: "compression_benchmark",
+        description: "COPY throughput and size with COMPRESSION lz4 on the hash and amount columns",
+        flags: &["--compression-bench", "--batch-size"],
+    },
+    Benchmark {
+        name: "copy_abort_test",
+        description: "Checks an aborted COPY leaves no rows behind and its batch can be resent",
+        flags: &["--copy-abort-test", "--batch-size"],
+    },
+    Benchmark {
+        name: "in_list_benchmark",
+        description: "Latency of fetching 10, 100 and 1000 transactions by a list of hashes",
+        flags: &["--in-list-bench", "--read-benchmarks", "--batch-size"],
+    },
+    Benchmark {
+        name: "scale_sweep",
+        description: "COPY throughput of 10%, 50% and 100% of the transactions into fresh tables",
+        flags: &["--scale-sweep", "--batch-size"],
+    },
+    Benchmark {
+        name: "parallel_copy_benchmark",
+        description: "COPY into one table over one connection against several concurrent ones",
+        flags: &["--parallel-copy", "--connection-jitter-ms", "--batch-size"],
+    },
+    Benchmark {
+        name: "partial_index_benchmark",
+        description: "COPY and lookups with a partial against a full index on to_address",
+        flags: &["--partial-index-bench", "--batch-size"],
+    },
+    Benchmark {
+        name: "prefix_search_benchmark",
+        description: "LIKE prefix searches on from_address with BTREE and trigram GIN indexes",
+        flags: &["--prefix-search-bench", "--batch-size"],
+    },
+    Benchmark {
+        name: "brin_benchmark",
+        description: "Block-range queries and index size with BRIN against BTREE on block",
+        flags: &["--brin-bench", "--batch-size"],
+    },
+    Benchmark {
+        name: "pk_after_load_benchmark",
+        description: "COPY with the primary key in place against adding it after the load",
+        flags: &["--pk-after-load-bench", "--batch-size"],
+    },
+    Benchmark {
+        name: "matview_benchmark",
+        description: "Materialized view refresh cost and reads against aggregating transfers",
+        flags: &["--matview-bench", "--batch-size"],
+    },
+    Benchmark {
+        name: "wal_level_benchmark",
+        description:
+            "COPY and WAL volume into an existing table against one created in the same transaction",
+        flags: &["--wal-level-bench", "--batch-size"],
+    },
+    Benchmark {
+        name: "trigger_benchmark",
+        description: "COPY with no trigger, a row-level and a statement-level counting trigger",
+        flags: &["--trigger-bench", "--batch-size"],
+    },
+    Benchmark {
+        name: "single_insert",
+        description: "One autocommitted INSERT per transaction into the loaded table (opt-in)",
+        flags: &[
+            "--single-insert-rows",
+            "--rate",
+            "--skip-conflicts",
+            "--slow-threshold-ms",
+            "--explain-analyze-insert",
+        ],
+    },
+    Benchmark {
+        name: "returning_benchmark",
+        description: "Single-row INSERT throughput with and without RETURNING id",
+        flags: &["--returning-bench", "--single-insert-rows"],
+    },
+    Benchmark {
+        name: "multirow_insert",
+        description: "Prepared multi-row INSERT ... VALUES throughput per rows-per-statement size",
+        flags: &["--multirow-bench", "--rows-per-stmt"],
+    },
+    Benchmark {
+        name: "cold_cache_benchmark",
+        description: "Read query latency with the tables in and evicted from shared buffers",
+        flags: &["--cold-cache", "--read-benchmarks"],
+    },
+    Benchmark {
+        name: "mixed_workload",
+        description: "Block-range read latency while another connection COPYs, against idle",
+        flags: &["--mixed-readers", "--connection-jitter-ms", "--batch-size"],
+    },
+    Benchmark {
+        name: "pagination_benchmark",
+        description: "Keyset against OFFSET pagination over the whole transactions table",
+        flags: &["--pagination-page-size", "--read-benchmarks"],
+    },
+    Benchmark {
+        name: "pk_lookup_benchmark",
+        description: "Latency percentiles of random SELECT-by-primary-key lookups on blocks",
+        flags: &["--pk-lookups", "--read-benchmarks"],
+    },
+    Benchmark {
+        name: "work_mem_sweep",
+        description: "GROUP BY aggregation latency and disk spills per work_mem setting",
+        flags: &["--work-mem-sweep"],
+    },
+    Benchmark {
+        name: "copy_microbench",
+        description: "COPY throughput per column type, run instead of the suite",
+        flags: &["--copy-microbench", "--microbench-rows"],
+    },
+    Benchmark {
+        name: "connect_bench",
+        description: "Connection establishment latency, run instead of the suite",
+        flags: &["--connect-bench", "--no-tls"],
+    },
+];
+
+/// Prints every benchmark phase with its description and options.
+pub fn print_benchmarks() {
+    let width = BENCHMARKS.iter().map(|b| b.name.len()).max().unwrap_or(0);
+    for benchmark in BENCHMARKS {
+        println!("{:<width$}  {}", benchmark.name, benchmark.description);
+        println!("{:<width$}  options: {}", "", benchmark.flags.join(" "));
+    }
+}
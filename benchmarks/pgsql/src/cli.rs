@@ -0,0 +1,1041 @@
+use crate::config::FileConfig;
+use crate::error::{BenchmarkError, Result};
+use crate::schema::TABLES;
+use chrono::format::{Item, StrftimeItems};
+use clap::builder::PossibleValuesParser;
+use clap::{Parser, ValueEnum};
+use serde::Deserialize;
+use std::env;
+use std::fs;
+use std::num::NonZeroU32;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+const DEFAULT_DATA_DIR: &str = "../../data/";
+const DEFAULT_MICROBENCH_ROWS: usize = 1_000_000;
+const DEFAULT_TABLE_STATS_CSV: &str = "table_sizes.csv";
+/// --single-insert-rows for --returning-bench when it isn't given
+const DEFAULT_SINGLE_INSERT_ROWS: usize = 1000;
+const DEFAULT_CONFLICT_RATIO: f64 = 0.5;
+const DEFAULT_ROWS_PER_STMT: [usize; 4] = [1, 10, 100, 1000];
+const DEFAULT_PAGE_SIZE: usize = 1000;
+const DEFAULT_PK_LOOKUPS: usize = 10_000;
+
+/// Read benchmarks `--read-benchmarks` selects from
+pub const READ_BENCHMARKS: [&str; 4] = ["cold_cache", "pagination", "pk_lookup", "in_list"];
+
+/// libpq's connection variables and the connection string keys they set,
+/// used when neither `--connection-url-file` nor DATABASE_URL is given
+const PG_ENV: [(&str, &str); 5] = [
+    ("PGHOST", "host"),
+    ("PGPORT", "port"),
+    ("PGUSER", "user"),
+    ("PGPASSWORD", "password"),
+    ("PGDATABASE", "dbname"),
+];
+
+/// Format of the structured phase logs written to stderr.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    Text,
+    /// One JSON object per line, for Loki, Elasticsearch and the like
+    Json,
+}
+
+/// How the results are printed at the end of a run.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    /// The per-phase reports only
+    #[default]
+    Text,
+    /// Also a GitHub-flavored Markdown table of the phases, for pull requests
+    Markdown,
+}
+
+/// Strategy used to write the dataset during the bulk load.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum InsertMethod {
+    /// One binary COPY per table per batch
+    #[default]
+    Copy,
+    /// One prepared INSERT per row, awaited one at a time
+    Insert,
+    /// One INSERT per batch binding a column array per column via unnest()
+    Unnest,
+    /// Prepared single-row INSERTs pipelined on the connection
+    Pipelined,
+}
+
+impl InsertMethod {
+    /// Whether a batch is written by one statement, so a failed batch leaves
+    /// none of its rows behind.
+    pub fn writes_batch_atomically(self) -> bool {
+        matches!(self, InsertMethod::Copy | InsertMethod::Unnest)
+    }
+}
+
+/// Postgres bulk load and query benchmark for blockchain data
+#[derive(Parser, Debug)]
+#[command(version, about)]
+pub struct Args {
+    /// Read settings from a TOML file; flags given on the command line take precedence
+    #[arg(long)]
+    pub config: Option<PathBuf>,
+
+    /// Directory holding blocks.json, transactions.json, transfers.json and pools.json
+    /// [default: ../../data/]
+    #[arg(long)]
+    pub data_dir: Option<PathBuf>,
+
+    /// Records per COPY batch [default: 1% of the largest table]
+    #[arg(long)]
+    pub batch_size: Option<usize>,
+
+    /// After the load, fail unless each listed table holds exactly this many
+    /// rows, e.g. `blocks=100,transactions=500`; tables keep rows from earlier
+    /// runs, so start from an empty database
+    #[arg(long, value_delimiter = ',', value_name = "TABLE=ROWS", value_parser = parse_expected_rows)]
+    pub expect_rows: Option<Vec<(String, i64)>>,
+
+    /// Comma-separated list of tables to load [default: all]
+    #[arg(long, value_delimiter = ',', value_parser = PossibleValuesParser::new(TABLES))]
+    pub tables: Option<Vec<String>>,
+
+    /// Fail if a selected table's data file is missing instead of warning and
+    /// skipping the table
+    #[arg(long)]
+    pub require_all_tables: bool,
+
+    /// How the bulk load writes rows
+    #[arg(long, value_enum, default_value_t)]
+    pub insert_method: InsertMethod,
+
+    /// Abort if the row count COPY reports for any batch differs from the rows written
+    #[arg(long)]
+    pub verify_row_count_per_batch: bool,
+
+    /// Stream each table's JSON file straight into COPY instead of loading the
+    /// dataset into memory first; tables are loaded one after another
+    #[arg(long)]
+    pub stream: bool,
+
+    /// Create the tables as TEMP tables that vanish on disconnect, so repeated runs
+    /// need no reset and leave nothing behind
+    #[arg(long)]
+    pub temp_tables: bool,
+
+    /// Create and load the tables in this schema, created if absent, instead of
+    /// the first schema on the search_path; `DROP SCHEMA ... CASCADE` cleans up
+    #[arg(long, value_name = "NAME")]
+    pub db_schema: Option<String>,
+
+    /// Show a live dashboard during the bulk load (plain output when stdout isn't a terminal)
+    #[arg(long)]
+    pub tui: bool,
+
+    /// Run the suite against each of these connection URLs in turn and print the
+    /// results side by side (repeat the flag per target; DATABASE_URL is ignored)
+    #[arg(long = "compare", value_name = "URL")]
+    pub compare: Vec<String>,
+
+    /// Sample how far this standby's WAL replay falls behind the primary during
+    /// the bulk load, reporting the peak lag and how long it takes to catch up
+    #[arg(long, value_name = "URL")]
+    pub replica_url: Option<String>,
+
+    /// Read the connection URL from this file instead of DATABASE_URL, keeping
+    /// it out of the environment of child processes; should be readable only
+    /// by its owner
+    #[arg(long, value_name = "PATH")]
+    pub connection_url_file: Option<PathBuf>,
+
+    /// Before loading each table, print its first row's values with the column
+    /// types they are bound as
+    #[arg(long)]
+    pub sample_output: bool,
+
+    /// Read each table from <data-dir>/<table>.ndjson, one JSON object per
+    /// line, instead of a JSON array in <table>.json
+    #[arg(long)]
+    pub ndjson: bool,
+
+    /// Read this table's JSON array (NDJSON with --ndjson) from stdin instead of its file,
+    /// e.g. `cat blocks.json | pgsql --stdin-table blocks --tables blocks`
+    #[arg(long, value_name = "TABLE", value_parser = PossibleValuesParser::new(TABLES))]
+    pub stdin_table: Option<String>,
+
+    /// Skip the post-load ANALYZE to measure queries against cold planner statistics
+    #[arg(long)]
+    pub skip_analyze: bool,
+
+    /// Run the single record insert test, inserting this many transactions one
+    /// autocommitted statement at a time into the loaded transactions table,
+    /// which adds them to the row counts and sizes later phases report. Also
+    /// the rows per variant of --returning-bench [default: 1000]
+    #[arg(long, value_name = "ROWS")]
+    pub single_insert_rows: Option<usize>,
+
+    /// Pace the single record insert test at this many rows/sec instead of as
+    /// fast as possible, to check the database keeps up with a target rate
+    #[arg(long, value_name = "ROWS_PER_SEC")]
+    pub rate: Option<NonZeroU32>,
+
+    /// In the single record insert test, log every insert slower than this
+    /// with its row key and latency, and report how many there were
+    #[arg(long, value_name = "MS")]
+    pub slow_threshold_ms: Option<u64>,
+
+    /// Before the single record insert test, print the EXPLAIN ANALYZE plan of
+    /// one insert, rolled back, to show index and trigger maintenance costs
+    #[arg(long)]
+    pub explain_analyze_insert: bool,
+
+    /// Compare single-row INSERTs with and without `RETURNING id` into scratch
+    /// tables, --single-insert-rows rows each
+    #[arg(long)]
+    pub returning_bench: bool,
+
+    /// Insert transactions with prepared multi-row INSERT ... VALUES statements,
+    /// as ORMs generate, once per --rows-per-stmt size
+    #[arg(long)]
+    pub multirow_bench: bool,
+
+    /// Rows per statement swept by --multirow-bench [default: 1,10,100,1000]
+    #[arg(long, value_name = "N,...", value_delimiter = ',')]
+    pub rows_per_stmt: Option<Vec<usize>>,
+
+    /// In the single record insert test, count and skip rows rejected by a
+    /// constraint instead of failing the run
+    #[arg(long)]
+    pub skip_conflicts: bool,
+
+    /// Time the read queries with the tables in shared buffers and again after
+    /// evicting them
+    #[arg(long)]
+    pub cold_cache: bool,
+
+    /// COPY half the transactions into a scratch table while this many other
+    /// connections run block-range queries on it, comparing read latency against
+    /// the same queries on the idle table
+    #[arg(long, value_name = "N")]
+    pub mixed_readers: Option<usize>,
+
+    /// Stagger the extra connections of --mixed-readers and --parallel-copy,
+    /// each opening after a random delay of up to this many milliseconds,
+    /// instead of all at once
+    #[arg(long, value_name = "MS")]
+    pub connection_jitter_ms: Option<u64>,
+
+    /// Page through transactions this many rows at a time with keyset and OFFSET
+    /// pagination and compare them
+    #[arg(long, value_name = "ROWS")]
+    pub pagination_page_size: Option<usize>,
+
+    /// Time this many random primary key lookups on blocks
+    #[arg(long, value_name = "N")]
+    pub pk_lookups: Option<usize>,
+
+    /// Comma-separated list of read benchmarks to run, replacing their own
+    /// flags, which still set their parameters (--pagination-page-size
+    /// defaults to 1000, --pk-lookups to 10000)
+    #[arg(long, value_delimiter = ',', value_parser = PossibleValuesParser::new(READ_BENCHMARKS))]
+    pub read_benchmarks: Option<Vec<String>>,
+
+    /// Time a GROUP BY aggregation over transactions under each of these
+    /// comma-separated work_mem settings, e.g. `64kB,1MB,16MB`
+    #[arg(long, value_delimiter = ',', value_name = "SIZE")]
+    pub work_mem_sweep: Option<Vec<String>>,
+
+    /// Run VACUUM (FULL, ANALYZE) on the tables after each phase that writes
+    /// to them, reporting its duration and the space reclaimed
+    #[arg(long)]
+    pub vacuum_between_phases: bool,
+
+    /// After the load, verify that every block's parent_hash links to a loaded block
+    #[arg(long)]
+    pub check_chain: bool,
+
+    /// Declare block heights (block_number, block, init_block) as BIGINT instead
+    /// of INTEGER, to measure what the wider column costs
+    #[arg(long)]
+    pub bigint_blocks: bool,
+
+    /// Before loading, check that every block height is non-negative and fits
+    /// the INTEGER columns, listing any records that don't
+    #[arg(long)]
+    pub check_block_range: bool,
+
+    /// Before loading, estimate the disk space each table and its primary key
+    /// index will take and warn if the server's data directory lacks it
+    #[arg(long)]
+    pub estimate_disk: bool,
+
+    /// After loading the dataset, count records with an empty hash, from, to
+    /// or token field per table
+    #[arg(long)]
+    pub validate: bool,
+
+    /// Make problems found by --validate a hard error instead of a warning
+    #[arg(long, requires = "validate")]
+    pub strict: bool,
+
+    /// Compare insert and read throughput of transactions with `value` padded to
+    /// this many bytes (e.g. 4096, enough to be TOASTed) against unpadded rows
+    #[arg(long, value_name = "BYTES")]
+    pub toast_pad_bytes: Option<usize>,
+
+    /// Report mean server-side execution time per statement from pg_stat_statements
+    /// (created if permitted) with track_io_timing enabled
+    #[arg(long)]
+    pub server_timing: bool,
+
+    /// Sample each table's total size every this many seconds during the bulk
+    /// load, for plotting size against time
+    #[arg(long, value_name = "SECS")]
+    pub table_stats_interval: Option<f64>,
+
+    /// CSV file the --table-stats-interval samples are written to [default: table_sizes.csv]
+    #[arg(long, value_name = "PATH")]
+    pub table_stats_csv: Option<PathBuf>,
+
+    /// Format of the results printed at the end of the run
+    #[arg(long, value_enum, default_value_t)]
+    pub output: OutputFormat,
+
+    /// Also log phase start/end and results as structured events on stderr
+    #[arg(long, value_enum)]
+    pub log_format: Option<LogFormat>,
+
+    /// Serve task scheduling data for `tokio-console` to attach to while the
+    /// benchmark runs (needs a build with `--features console` and
+    /// RUSTFLAGS="--cfg tokio_unstable")
+    #[arg(long)]
+    pub profile: bool,
+
+    /// Profile the bulk insert phase and write its CPU flamegraph to this SVG
+    /// file (needs a build with `--features flamegraph`)
+    #[arg(long, value_name = "OUT.svg")]
+    pub flamegraph: Option<PathBuf>,
+
+    /// Compare COPY into transactions with and without a stored generated
+    /// lower(from_address) column, in scratch tables
+    #[arg(long)]
+    pub generated_column_bench: bool,
+
+    /// Compare COPY throughput and size of transactions and transfers with
+    /// COMPRESSION lz4 on their hash, value and amount columns, in scratch
+    /// tables (Postgres 14+)
+    #[arg(long)]
+    pub compression_bench: bool,
+
+    /// Abort a COPY of half a batch of transactions midway, check no rows
+    /// landed, then resend the batch and check all of it did, in a scratch table
+    #[arg(long)]
+    pub copy_abort_test: bool,
+
+    /// Time `WHERE hash = ANY($1)` lookups of 10, 100 and 1000 transaction
+    /// hashes, in a scratch table indexed on hash
+    #[arg(long)]
+    pub in_list_bench: bool,
+
+    /// Load 10%, 50% and 100% of the transactions into a fresh scratch table
+    /// each and compare throughput across the scales
+    #[arg(long)]
+    pub scale_sweep: bool,
+
+    /// Compare COPYing the transactions into one scratch table over a single
+    /// connection against splitting them across this many concurrent ones
+    #[arg(long, value_name = "CONNECTIONS")]
+    pub parallel_copy: Option<usize>,
+
+    /// Compare COPY throughput, index size and lookup latency of transactions
+    /// with a full index on to_address against a partial one skipping empty
+    /// recipients, in scratch tables
+    #[arg(long)]
+    pub partial_index_bench: bool,
+
+    /// Compare LIKE prefix searches on from_address with no index, a
+    /// text_pattern_ops BTREE index and a pg_trgm GIN index, in scratch tables
+    #[arg(long)]
+    pub prefix_search_bench: bool,
+
+    /// Compare block-range query latency and index size of a BRIN index on
+    /// transactions.block against a BTREE one, in scratch tables loaded in
+    /// block order
+    #[arg(long)]
+    pub brin_bench: bool,
+
+    /// Compare COPYing transactions into a scratch table with its primary key
+    /// against COPYing into one without and adding the primary key afterwards
+    #[arg(long)]
+    pub pk_after_load_bench: bool,
+
+    /// Time refreshing a materialized view of transfer totals per token over
+    /// a scratch copy of transfers, and compare reads from it against
+    /// aggregating the base table
+    #[arg(long)]
+    pub matview_bench: bool,
+
+    /// Report wal_level and compare COPYing transactions into an existing
+    /// scratch table against one created in the same transaction, which skips
+    /// WAL under wal_level = minimal
+    #[arg(long)]
+    pub wal_level_bench: bool,
+
+    /// Compare COPY throughput of transactions into a scratch table without a
+    /// trigger, with a row-level one and with a statement-level one doing the
+    /// same per-block counting
+    #[arg(long)]
+    pub trigger_bench: bool,
+
+    /// Write results.json and results.csv into a new
+    /// <timestamp>-<commit>-<run id> directory here and list the run in
+    /// index.json
+    #[arg(long, value_name = "DIR")]
+    pub results_dir: Option<PathBuf>,
+
+    /// Compare two runs written by --results-dir (run directories or their
+    /// results.json), phase by phase, then exit
+    #[arg(long, num_args = 2, value_names = ["A", "B"])]
+    pub diff_results: Option<Vec<PathBuf>>,
+
+    /// Push records/sec and p99 latency gauges to this Prometheus pushgateway
+    /// at the end of the run
+    #[arg(long, value_name = "URL")]
+    pub pushgateway: Option<String>,
+
+    /// If a phase fails, still print, log, push and write the results of the
+    /// phases that finished before returning the error
+    #[arg(long)]
+    pub output_on_error: bool,
+
+    /// When the server rejects a load batch, retry it row by row and log and
+    /// skip the rows that fail instead of aborting
+    #[arg(long)]
+    pub skip_bad_rows: bool,
+
+    /// Fail every Nth load batch of each table server-side, given as
+    /// `every=N`, to check that --skip-bad-rows recovers it (needs a build
+    /// with `--features fault-injection`)
+    #[arg(long, value_name = "every=N", value_parser = parse_inject_failure)]
+    pub inject_failure: Option<usize>,
+
+    /// Compare COPY throughput of the SERIAL-keyed tables against natural primary
+    /// keys (block_number, hash) in scratch tables
+    #[arg(long)]
+    pub key_bench: bool,
+
+    /// Compare COPY of the blocks in descending block_number order against
+    /// ascending, into scratch tables with a block_number index, to show the
+    /// cost of B-tree page splits
+    #[arg(long)]
+    pub reverse: bool,
+
+    /// Compare COPY of blocks and the transactions referencing them with the
+    /// foreign key checked per row against DEFERRABLE INITIALLY DEFERRED, one
+    /// transaction per batch, in scratch tables
+    #[arg(long)]
+    pub deferred_fk_bench: bool,
+
+    /// Compare a plain COPY load followed by VACUUM (FREEZE) against TRUNCATE
+    /// and COPY ... FREEZE in one transaction, in scratch tables
+    #[arg(long)]
+    pub copy_freeze: bool,
+
+    /// Set the TCP send buffer (SO_SNDBUF) of the benchmark connection to this
+    /// many bytes, a tuning knob for COPY over high-latency links
+    #[arg(long, value_name = "BYTES")]
+    pub send_buffer_size: Option<u32>,
+
+    /// Compare COPY throughput of transactions over connections with the
+    /// default and several TCP send buffer sizes, in a scratch table
+    #[arg(long)]
+    pub send_buffer_bench: bool,
+
+    /// Set the session's client_encoding, e.g. LATIN1, before running anything;
+    /// results are read back as UTF-8, so only safe for ASCII data
+    #[arg(long, value_name = "ENCODING")]
+    pub client_encoding: Option<String>,
+
+    /// Compare COPY throughput of transfers under the UTF8, LATIN1 and
+    /// SQL_ASCII client encodings, in a scratch table
+    #[arg(long)]
+    pub encoding_bench: bool,
+
+    /// Compare COPY throughput and size of transfers with and without a
+    /// `topics TEXT[]` array column, in scratch tables
+    #[arg(long)]
+    pub array_bench: bool,
+
+    /// Compare COPY throughput, size and time-range query latency of transactions
+    /// with TEXT timestamps, compared as strings, against native TIMESTAMPTZ
+    #[arg(long)]
+    pub timestamp_bench: bool,
+
+    /// strftime format of the dataset's timestamps, e.g. "%d/%m/%Y %H:%M:%S",
+    /// taken as UTC; without it they are parsed as RFC 3339. Only used by
+    /// --timestamp-bench, which also writes its TEXT range bounds in it
+    #[arg(long, value_name = "FORMAT")]
+    pub timestamp_format: Option<String>,
+
+    /// Compare COPY throughput, size and SUM latency of transfers with TEXT
+    /// amounts against NUMERIC(PRECISION, SCALE), e.g. `78,18`, first checking
+    /// that no amount has more decimal places than SCALE
+    #[arg(long, value_name = "PRECISION,SCALE", value_parser = parse_numeric_scale)]
+    pub numeric_scale: Option<(u32, u32)>,
+
+    /// Make amounts that don't fit --numeric-scale a hard error instead of a
+    /// warning that leaves them out
+    #[arg(long, requires = "numeric_scale")]
+    pub strict_numeric_scale: bool,
+
+    /// Run this many random UPDATEs of indexed address columns against a
+    /// scratch copy of transactions and report the index growth they cause
+    #[arg(long, value_name = "UPDATES")]
+    pub index_bloat_bench: Option<usize>,
+
+    /// Time INSERT ... ON CONFLICT (block_number) DO UPDATE of the blocks into
+    /// a scratch table already holding some of them
+    #[arg(long)]
+    pub upsert_bench: bool,
+
+    /// Compare upserting the blocks with MERGE against INSERT ... ON CONFLICT,
+    /// in scratch tables already holding some of them (Postgres 15+)
+    #[arg(long)]
+    pub merge_bench: bool,
+
+    /// Fraction of the blocks already present when --upsert-bench or
+    /// --merge-bench starts, so that share of rows takes the update path
+    /// [default: 0.5]
+    #[arg(long, value_name = "RATIO")]
+    pub conflict_ratio: Option<f64>,
+
+    /// Open and close this many connections serially and report connect
+    /// latency, instead of the dataset load
+    #[arg(long, value_name = "N")]
+    pub connect_bench: Option<usize>,
+
+    /// With --connect-bench, also time plain connections to show the TLS handshake cost
+    #[arg(long, requires = "connect_bench")]
+    pub no_tls: bool,
+
+    /// Print every benchmark phase, what it measures and the options it uses, then exit
+    #[arg(long)]
+    pub list_benchmarks: bool,
+
+    /// Print the target's readiness as JSON (server version, database, and
+    /// each table's existence, row count and primary key), then exit; fails
+    /// if the target isn't ready
+    #[arg(long)]
+    pub status: bool,
+
+    /// Drop the benchmark tables with their indexes and sequences, and the
+    /// --db-schema schema once it is empty, then exit
+    #[arg(long)]
+    pub drop: bool,
+
+    /// Print the JSON Schema of this table's input file, e.g. blocks.json, then exit
+    #[arg(long, value_name = "TABLE", value_parser = PossibleValuesParser::new(TABLES))]
+    pub json_schema: Option<String>,
+
+    /// Run the per-column-type COPY microbenchmark instead of the dataset load
+    #[arg(long)]
+    pub copy_microbench: bool,
+
+    /// Rows COPYed into each single-column table by --copy-microbench [default: 1000000]
+    #[arg(long)]
+    pub microbench_rows: Option<usize>,
+}
+
+impl Args {
+    /// Parses the command line and fills unset options from the `--config` file, if any.
+    pub fn load() -> Result<Self> {
+        let mut args = Args::parse();
+        if let Some(path) = &args.config {
+            let config = FileConfig::load(path)?;
+            args.merge(config)?;
+        }
+        args.validate()?;
+        args.select_read_benchmarks();
+        Ok(args)
+    }
+
+    /// Under `--read-benchmarks`, enables exactly the listed read benchmarks.
+    fn select_read_benchmarks(&mut self) {
+        let Some(selected) = &self.read_benchmarks else {
+            return;
+        };
+        let runs = |name: &str| selected.iter().any(|s| s == name);
+        self.cold_cache = runs("cold_cache");
+        self.pagination_page_size =
+            runs("pagination").then(|| self.pagination_page_size.unwrap_or(DEFAULT_PAGE_SIZE));
+        self.pk_lookups = runs("pk_lookup").then(|| self.pk_lookups.unwrap_or(DEFAULT_PK_LOOKUPS));
+        self.in_list_bench = runs("in_list");
+    }
+
+    /// Rejects option values clap's types can't rule out.
+    fn validate(&self) -> Result<()> {
+        if let Some(secs) = self.table_stats_interval {
+            if !(secs.is_finite() && secs > 0.0) {
+                return Err(BenchmarkError::ConfigError(format!(
+                    "table-stats-interval must be a positive number of seconds, got {}",
+                    secs
+                )));
+            }
+        }
+        if self.profile && !cfg!(feature = "console") {
+            return Err(BenchmarkError::ConfigError(
+                "--profile needs a build with --features console".to_string(),
+            ));
+        }
+        if self.flamegraph.is_some() && !cfg!(feature = "flamegraph") {
+            return Err(BenchmarkError::ConfigError(
+                "--flamegraph needs a build with --features flamegraph".to_string(),
+            ));
+        }
+        if self.inject_failure.is_some() && !cfg!(feature = "fault-injection") {
+            return Err(BenchmarkError::ConfigError(
+                "--inject-failure needs a build with --features fault-injection".to_string(),
+            ));
+        }
+        // Interpolated into SET, which can't take a bind parameter
+        if let Some(encoding) = &self.client_encoding {
+            if encoding.is_empty()
+                || !encoding
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+            {
+                return Err(BenchmarkError::ConfigError(format!(
+                    "client-encoding must be an encoding name such as UTF8 or LATIN1, got {:?}",
+                    encoding
+                )));
+            }
+        }
+        if let Some(name) = &self.db_schema {
+            if !name.starts_with(|c: char| c.is_ascii_lowercase() || c == '_')
+                || !name
+                    .chars()
+                    .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+            {
+                return Err(BenchmarkError::ConfigError(format!(
+                    "db-schema must be a lowercase identifier such as bench, got {:?}",
+                    name
+                )));
+            }
+            if self.temp_tables {
+                return Err(BenchmarkError::ConfigError(
+                    "--db-schema and --temp-tables can't be combined, TEMP tables always live in pg_temp"
+                        .to_string(),
+                ));
+            }
+        }
+        if let Some(format) = &self.timestamp_format {
+            if StrftimeItems::new(format).any(|item| item == Item::Error) {
+                return Err(BenchmarkError::ConfigError(format!(
+                    "timestamp-format {:?} isn't a valid strftime format",
+                    format
+                )));
+            }
+        }
+        if self.replica_url.is_some() && !self.compare.is_empty() {
+            return Err(BenchmarkError::ConfigError(
+                "--replica-url can't be combined with --compare".to_string(),
+            ));
+        }
+        if self.rows_per_stmt.iter().flatten().any(|&n| n == 0) {
+            return Err(BenchmarkError::ConfigError(
+                "rows-per-stmt sizes must be at least 1".to_string(),
+            ));
+        }
+        if let Some(settings) = &self.work_mem_sweep {
+            if let Some(bad) = settings.iter().find(|s| !is_memory_size(s)) {
+                return Err(BenchmarkError::ConfigError(format!(
+                    "work-mem-sweep values must be sizes such as 64kB or 4MB, got {:?}",
+                    bad
+                )));
+            }
+        }
+        if self.parallel_copy == Some(0) {
+            return Err(BenchmarkError::ConfigError(
+                "parallel-copy must be at least 1".to_string(),
+            ));
+        }
+        if self.mixed_readers == Some(0) {
+            return Err(BenchmarkError::ConfigError(
+                "mixed-readers must be at least 1".to_string(),
+            ));
+        }
+        if let Some(ratio) = self.conflict_ratio {
+            if !(0.0..=1.0).contains(&ratio) {
+                return Err(BenchmarkError::ConfigError(format!(
+                    "conflict-ratio must be between 0 and 1, got {}",
+                    ratio
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    fn merge(&mut self, config: FileConfig) -> Result<()> {
+        if let Some(tables) = &config.tables {
+            if let Some(unknown) = tables.iter().find(|t| !TABLES.contains(&t.as_str())) {
+                return Err(BenchmarkError::ConfigError(format!(
+                    "unknown table '{}' in tables, expected one of {}",
+                    unknown,
+                    TABLES.join(", ")
+                )));
+            }
+        }
+
+        if let Some(names) = &config.read_benchmarks {
+            if let Some(unknown) = names
+                .iter()
+                .find(|n| !READ_BENCHMARKS.contains(&n.as_str()))
+            {
+                return Err(BenchmarkError::ConfigError(format!(
+                    "unknown read benchmark '{}' in read-benchmarks, expected one of {}",
+                    unknown,
+                    READ_BENCHMARKS.join(", ")
+                )));
+            }
+        }
+        if let Some(expected) = &config.expect_rows {
+            if let Some(unknown) = expected.keys().find(|t| !TABLES.contains(&t.as_str())) {
+                return Err(BenchmarkError::ConfigError(format!(
+                    "unknown table '{}' in expect-rows, expected one of {}",
+                    unknown,
+                    TABLES.join(", ")
+                )));
+            }
+        }
+
+        self.data_dir = self.data_dir.take().or(config.data_dir);
+        self.batch_size = self.batch_size.or(config.batch_size);
+        self.tables = self.tables.take().or(config.tables);
+        self.require_all_tables |= config.require_all_tables.unwrap_or(false);
+        self.expect_rows = self
+            .expect_rows
+            .take()
+            .or_else(|| Some(config.expect_rows?.into_iter().collect()));
+        if self.insert_method == InsertMethod::default() {
+            self.insert_method = config.insert_method.unwrap_or_default();
+        }
+        self.skip_bad_rows |= config.skip_bad_rows.unwrap_or(false);
+        self.output_on_error |= config.output_on_error.unwrap_or(false);
+        self.verify_row_count_per_batch |= config.verify_row_count_per_batch.unwrap_or(false);
+        self.stream |= config.stream.unwrap_or(false);
+        self.ndjson |= config.ndjson.unwrap_or(false);
+        self.sample_output |= config.sample_output.unwrap_or(false);
+        self.temp_tables |= config.temp_tables.unwrap_or(false);
+        self.db_schema = self.db_schema.take().or(config.db_schema);
+        self.tui |= config.tui.unwrap_or(false);
+        self.replica_url = self.replica_url.take().or(config.replica_url);
+        self.connection_url_file = self
+            .connection_url_file
+            .take()
+            .or(config.connection_url_file);
+        if self.compare.is_empty() {
+            self.compare = config.compare.unwrap_or_default();
+        }
+        self.skip_analyze |= config.skip_analyze.unwrap_or(false);
+        self.check_chain |= config.check_chain.unwrap_or(false);
+        self.single_insert_rows = self.single_insert_rows.or(config.single_insert_rows);
+        self.rate = self.rate.or(config.rate);
+        self.skip_conflicts |= config.skip_conflicts.unwrap_or(false);
+        self.slow_threshold_ms = self.slow_threshold_ms.or(config.slow_threshold_ms);
+        self.explain_analyze_insert |= config.explain_analyze_insert.unwrap_or(false);
+        self.returning_bench |= config.returning_bench.unwrap_or(false);
+        self.multirow_bench |= config.multirow_bench.unwrap_or(false);
+        self.rows_per_stmt = self.rows_per_stmt.take().or(config.rows_per_stmt);
+        self.cold_cache |= config.cold_cache.unwrap_or(false);
+        self.mixed_readers = self.mixed_readers.or(config.mixed_readers);
+        self.connection_jitter_ms = self.connection_jitter_ms.or(config.connection_jitter_ms);
+        self.pagination_page_size = self.pagination_page_size.or(config.pagination_page_size);
+        self.pk_lookups = self.pk_lookups.or(config.pk_lookups);
+        self.read_benchmarks = self.read_benchmarks.take().or(config.read_benchmarks);
+        self.work_mem_sweep = self.work_mem_sweep.take().or(config.work_mem_sweep);
+        self.vacuum_between_phases |= config.vacuum_between_phases.unwrap_or(false);
+        self.bigint_blocks |= config.bigint_blocks.unwrap_or(false);
+        self.check_block_range |= config.check_block_range.unwrap_or(false);
+        self.estimate_disk |= config.estimate_disk.unwrap_or(false);
+        self.validate |= config.validate.unwrap_or(false);
+        self.strict |= config.strict.unwrap_or(false);
+        self.table_stats_interval = self.table_stats_interval.or(config.table_stats_interval);
+        self.table_stats_csv = self.table_stats_csv.take().or(config.table_stats_csv);
+        if self.output == OutputFormat::default() {
+            self.output = config.output.unwrap_or_default();
+        }
+        self.log_format = self.log_format.or(config.log_format);
+        self.profile |= config.profile.unwrap_or(false);
+        self.flamegraph = self.flamegraph.take().or(config.flamegraph);
+        self.results_dir = self.results_dir.take().or(config.results_dir);
+        self.pushgateway = self.pushgateway.take().or(config.pushgateway);
+        self.key_bench |= config.key_bench.unwrap_or(false);
+        self.reverse |= config.reverse.unwrap_or(false);
+        self.deferred_fk_bench |= config.deferred_fk_bench.unwrap_or(false);
+        self.copy_freeze |= config.copy_freeze.unwrap_or(false);
+        self.send_buffer_size = self.send_buffer_size.or(config.send_buffer_size);
+        self.send_buffer_bench |= config.send_buffer_bench.unwrap_or(false);
+        self.client_encoding = self.client_encoding.take().or(config.client_encoding);
+        self.encoding_bench |= config.encoding_bench.unwrap_or(false);
+        self.array_bench |= config.array_bench.unwrap_or(false);
+        self.timestamp_bench |= config.timestamp_bench.unwrap_or(false);
+        self.timestamp_format = self.timestamp_format.take().or(config.timestamp_format);
+        if self.numeric_scale.is_none() {
+            self.numeric_scale = config
+                .numeric_scale
+                .as_deref()
+                .map(parse_numeric_scale)
+                .transpose()
+                .map_err(|e| BenchmarkError::ConfigError(format!("numeric-scale: {}", e)))?;
+        }
+        self.strict_numeric_scale |= config.strict_numeric_scale.unwrap_or(false);
+        self.index_bloat_bench = self.index_bloat_bench.or(config.index_bloat_bench);
+        self.upsert_bench |= config.upsert_bench.unwrap_or(false);
+        self.merge_bench |= config.merge_bench.unwrap_or(false);
+        self.conflict_ratio = self.conflict_ratio.or(config.conflict_ratio);
+        self.generated_column_bench |= config.generated_column_bench.unwrap_or(false);
+        self.compression_bench |= config.compression_bench.unwrap_or(false);
+        self.copy_abort_test |= config.copy_abort_test.unwrap_or(false);
+        self.in_list_bench |= config.in_list_bench.unwrap_or(false);
+        self.scale_sweep |= config.scale_sweep.unwrap_or(false);
+        self.parallel_copy = self.parallel_copy.or(config.parallel_copy);
+        self.partial_index_bench |= config.partial_index_bench.unwrap_or(false);
+        self.prefix_search_bench |= config.prefix_search_bench.unwrap_or(false);
+        self.brin_bench |= config.brin_bench.unwrap_or(false);
+        self.pk_after_load_bench |= config.pk_after_load_bench.unwrap_or(false);
+        self.matview_bench |= config.matview_bench.unwrap_or(false);
+        self.wal_level_bench |= config.wal_level_bench.unwrap_or(false);
+        self.trigger_bench |= config.trigger_bench.unwrap_or(false);
+        self.server_timing |= config.server_timing.unwrap_or(false);
+        self.toast_pad_bytes = self.toast_pad_bytes.or(config.toast_pad_bytes);
+        self.microbench_rows = self.microbench_rows.or(config.microbench_rows);
+        self.connect_bench = self.connect_bench.or(config.connect_bench);
+        self.no_tls |= config.no_tls.unwrap_or(false);
+        Ok(())
+    }
+
+    pub fn data_dir(&self) -> &Path {
+        self.data_dir
+            .as_deref()
+            .unwrap_or(Path::new(DEFAULT_DATA_DIR))
+    }
+
+    pub fn table_stats_csv(&self) -> PathBuf {
+        self.table_stats_csv
+            .clone()
+            .unwrap_or_else(|| PathBuf::from(DEFAULT_TABLE_STATS_CSV))
+    }
+
+    /// The target's connection URL: the trimmed contents of
+    /// `--connection-url-file` if given, otherwise `DATABASE_URL`, otherwise a
+    /// key/value connection string from the `PG*` variables if PGHOST is set.
+    pub fn database_url(&self) -> Result<String> {
+        let Some(path) = &self.connection_url_file else {
+            return match env::var("DATABASE_URL") {
+                Ok(url) => Ok(url),
+                Err(source) => pg_env_conninfo().ok_or(BenchmarkError::EnvVarError {
+                    var: "DATABASE_URL",
+                    source,
+                }),
+            };
+        };
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(path)?.permissions().mode();
+            if mode & 0o077 != 0 {
+                eprintln!(
+                    "Warning: {} is accessible by other users (mode {:o}), consider chmod 600",
+                    path.display(),
+                    mode & 0o777
+                );
+            }
+        }
+        let url = fs::read_to_string(path)?.trim().to_string();
+        if url.is_empty() {
+            return Err(BenchmarkError::ConfigError(format!(
+                "connection URL file {} is empty",
+                path.display()
+            )));
+        }
+        Ok(url)
+    }
+
+    /// Rows for --returning-bench, which runs in scratch tables and so
+    /// defaults to some even when the single record insert test is off.
+    pub fn single_insert_rows(&self) -> usize {
+        self.single_insert_rows
+            .unwrap_or(DEFAULT_SINGLE_INSERT_ROWS)
+    }
+
+    pub fn rows_per_stmt(&self) -> Vec<usize> {
+        self.rows_per_stmt
+            .clone()
+            .unwrap_or_else(|| DEFAULT_ROWS_PER_STMT.to_vec())
+    }
+
+    pub fn connection_jitter(&self) -> Duration {
+        Duration::from_millis(self.connection_jitter_ms.unwrap_or(0))
+    }
+
+    pub fn conflict_ratio(&self) -> f64 {
+        self.conflict_ratio.unwrap_or(DEFAULT_CONFLICT_RATIO)
+    }
+
+    pub fn microbench_rows(&self) -> usize {
+        self.microbench_rows.unwrap_or(DEFAULT_MICROBENCH_ROWS)
+    }
+
+    /// Whether `table` should be loaded, given the `--tables` selection.
+    pub fn includes_table(&self, table: &str) -> bool {
+        match &self.tables {
+            Some(tables) => tables.iter().any(|t| t == table),
+            None => true,
+        }
+    }
+}
+
+/// Whether `value` is a Postgres memory size: a number of kB, or a number
+/// with a kB, MB, GB or TB unit.
+fn is_memory_size(value: &str) -> bool {
+    let digits = value.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+    let unit = &value[digits.len()..];
+    !digits.is_empty()
+        && digits.chars().all(|c| c.is_ascii_digit())
+        && ["", "kB", "MB", "GB", "TB"].contains(&unit)
+}
+
+/// Parses `--inject-failure`, `every=<N>` with N at least 1.
+fn parse_inject_failure(value: &str) -> std::result::Result<usize, String> {
+    let every = value
+        .strip_prefix("every=")
+        .ok_or_else(|| format!("expected every=N, got {:?}", value))?;
+    match every.parse() {
+        Ok(0) => Err("every=N needs N of at least 1".to_string()),
+        Ok(n) => Ok(n),
+        Err(e) => Err(format!("invalid batch count {:?}: {}", every, e)),
+    }
+}
+
+/// Parses `--numeric-scale`, `<precision>,<scale>` within the limits Postgres
+/// puts on a NUMERIC column's type modifiers.
+fn parse_numeric_scale(value: &str) -> std::result::Result<(u32, u32), String> {
+    let (precision, scale) = value
+        .split_once(',')
+        .ok_or_else(|| format!("expected PRECISION,SCALE, got {:?}", value))?;
+    let precision: u32 = precision
+        .trim()
+        .parse()
+        .map_err(|e| format!("invalid precision {:?}: {}", precision, e))?;
+    let scale: u32 = scale
+        .trim()
+        .parse()
+        .map_err(|e| format!("invalid scale {:?}: {}", scale, e))?;
+    if !(1..=1000).contains(&precision) || scale > precision {
+        return Err(format!(
+            "precision must be 1..=1000 and scale 0..=precision, got {},{}",
+            precision, scale
+        ));
+    }
+    Ok((precision, scale))
+}
+
+/// Parses one `--expect-rows` entry, `<table>=<rows>`.
+fn parse_expected_rows(entry: &str) -> std::result::Result<(String, i64), String> {
+    let (table, rows) = entry
+        .split_once('=')
+        .ok_or_else(|| format!("expected TABLE=ROWS, got {:?}", entry))?;
+    if !TABLES.contains(&table) {
+        return Err(format!(
+            "unknown table {:?}, expected one of {}",
+            table,
+            TABLES.join(", ")
+        ));
+    }
+    let rows = rows
+        .parse()
+        .map_err(|e| format!("invalid row count {:?} for {}: {}", rows, table, e))?;
+    Ok((table.to_string(), rows))
+}
+
+/// A key/value connection string from the `PG_ENV` variables that are set,
+/// or `None` without PGHOST.
+fn pg_env_conninfo() -> Option<String> {
+    env::var("PGHOST").ok()?;
+    let pairs: Vec<(&str, String)> = PG_ENV
+        .iter()
+        .filter_map(|(var, key)| Some((*key, env::var(var).ok()?)))
+        .collect();
+    Some(conninfo(&pairs))
+}
+
+/// Joins `key='value'` pairs, quoting values so spaces and quotes survive.
+fn conninfo(pairs: &[(&str, String)]) -> String {
+    pairs
+        .iter()
+        .map(|(key, value)| {
+            format!(
+                "{}='{}'",
+                key,
+                value.replace('\\', "\\\\").replace('\'', "\\'")
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn conninfo_values_are_quoted() {
+        let info = conninfo(&[
+            ("host", "/var/run/postgresql".to_string()),
+            ("password", "it's a \\secret".to_string()),
+        ]);
+        let config: tokio_postgres::Config = info.parse().unwrap();
+        assert_eq!(config.get_password(), Some("it's a \\secret".as_bytes()));
+    }
+
+    #[test]
+    fn memory_sizes_need_a_known_unit() {
+        assert!(is_memory_size("64kB"));
+        assert!(is_memory_size("4MB"));
+        assert!(is_memory_size("1024"));
+        assert!(!is_memory_size("4mb"));
+        assert!(!is_memory_size("MB"));
+        assert!(!is_memory_size("4MB'; DROP TABLE blocks; --"));
+    }
+
+    #[test]
+    fn numeric_scale_fits_the_precision() {
+        assert_eq!(parse_numeric_scale("78,18"), Ok((78, 18)));
+        assert!(parse_numeric_scale("18").is_err());
+        assert!(parse_numeric_scale("10,18").is_err());
+        assert!(parse_numeric_scale("0,0").is_err());
+    }
+
+    #[test]
+    fn inject_failure_takes_a_positive_interval() {
+        assert_eq!(parse_inject_failure("every=3"), Ok(3));
+        assert!(parse_inject_failure("3").is_err());
+        assert!(parse_inject_failure("every=0").is_err());
+        assert!(parse_inject_failure("every=some").is_err());
+    }
+
+    #[test]
+    fn expected_rows_name_a_known_table() {
+        assert_eq!(
+            parse_expected_rows("blocks=100"),
+            Ok(("blocks".to_string(), 100))
+        );
+        assert!(parse_expected_rows("block=100").is_err());
+        assert!(parse_expected_rows("blocks").is_err());
+        assert!(parse_expected_rows("blocks=many").is_err());
+    }
+}
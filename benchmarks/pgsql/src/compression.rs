@@ -0,0 +1,126 @@
+use crate::bulk::copy_all;
+use crate::dataset::Dataset;
+use crate::error::{BenchmarkError, Result};
+use crate::report::PhaseResult;
+use crate::schema;
+use crate::stats;
+use std::time::Duration;
+use tokio_postgres::error::SqlState;
+use tokio_postgres::Client;
+
+/// Transactions and transfers with `compression`, nothing for the default or
+/// ` COMPRESSION lz4`, after each hash and amount column's type
+fn compression_ddl(compression: &str) -> String {
+    schema::TRANSACTIONS
+        .scratch_table()
+        .column("hash", format!("TEXT{} NOT NULL", compression))
+        .column("value", format!("TEXT{} NOT NULL", compression))
+        .ddl()
+        + &schema::TRANSFERS
+            .scratch_table()
+            .column("tx_hash", format!("TEXT{} NOT NULL", compression))
+            .column("amount", format!("TEXT{} NOT NULL", compression))
+            .ddl()
+}
+
+/// One variant's load time, size and how many values ended up compressed.
+struct CompressionRun {
+    duration: Duration,
+    bytes: i64,
+    compressed: i64,
+}
+
+/// COPYs transactions and transfers into scratch tables whose hash, value and
+/// amount columns use the default compression (pglz) and then `COMPRESSION
+/// lz4`, comparing throughput and total size.
+///
+/// Postgres only compresses values too big to store inline (about 2 kB), so
+/// the count of compressed values shows whether the setting had anything to
+/// act on; short hex strings never reach it.
+#[tracing::instrument(name = "phase", skip_all, fields(phase = "compression_benchmark"))]
+pub async fn run_compression_benchmark(
+    client: &Client,
+    dataset: &Dataset,
+    batch_size: usize,
+) -> Result<Vec<PhaseResult>> {
+    // Per-column compression arrived in Postgres 14
+    if stats::server_version(client).await? < 140000 {
+        println!("\nSkipping the column compression benchmark, it needs Postgres 14 or later");
+        return Ok(Vec::new());
+    }
+    let default = load(client, "compression_bench_default", "", dataset, batch_size).await?;
+    let lz4 = match load(
+        client,
+        "compression_bench_lz4",
+        " COMPRESSION lz4",
+        dataset,
+        batch_size,
+    )
+    .await
+    {
+        Err(BenchmarkError::DatabaseError(e))
+            if e.code() == Some(&SqlState::FEATURE_NOT_SUPPORTED) =>
+        {
+            println!(
+                "\nSkipping the column compression benchmark, the server lacks lz4 support: {}",
+                e
+            );
+            return Ok(Vec::new());
+        }
+        run => run?,
+    };
+
+    let records = dataset.transactions.len() + dataset.transfers.len();
+    println!(
+        "\nColumn Compression Benchmark ({} transactions and transfers):",
+        records
+    );
+    println!("-------------------------");
+    for (label, run) in [("default", &default), ("lz4", &lz4)] {
+        println!(
+            "  {:<8} {:>12.2?}  {:>10.0} rows/sec  {:>8.1} MiB  {} values compressed",
+            label,
+            run.duration,
+            records as f64 / run.duration.as_secs_f64(),
+            run.bytes as f64 / (1024.0 * 1024.0),
+            run.compressed
+        );
+    }
+
+    Ok(vec![
+        PhaseResult::new("default_compression_copy", records, default.duration),
+        PhaseResult::new("lz4_compression_copy", records, lz4.duration),
+    ])
+}
+
+async fn load(
+    client: &Client,
+    name: &str,
+    compression: &str,
+    dataset: &Dataset,
+    batch_size: usize,
+) -> Result<CompressionRun> {
+    let ddl = compression_ddl(compression);
+    let previous = schema::enter_scratch_schema(client, name, &ddl).await?;
+    let duration = copy_all(client, &dataset.transactions, batch_size).await?
+        + copy_all(client, &dataset.transfers, batch_size).await?;
+    let bytes = stats::schema_bytes(client, name).await?;
+    let compressed: i64 = client
+        .query_one(
+            "SELECT (SELECT count(*) FROM transactions
+                     WHERE pg_column_compression(hash) IS NOT NULL
+                        OR pg_column_compression(value) IS NOT NULL)
+                  + (SELECT count(*) FROM transfers
+                     WHERE pg_column_compression(tx_hash) IS NOT NULL
+                        OR pg_column_compression(amount) IS NOT NULL)",
+            &[],
+        )
+        .await?
+        .get(0);
+    schema::leave_scratch_schema(client, name, &previous).await?;
+    Ok(CompressionRun {
+        duration,
+        bytes,
+        compressed,
+    })
+}
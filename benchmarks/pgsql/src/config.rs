@@ -0,0 +1,107 @@
+use crate::cli::{InsertMethod, LogFormat, OutputFormat};
+use crate::error::Result;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::num::NonZeroU32;
+use std::path::{Path, PathBuf};
+
+/// Benchmark settings read from a TOML file such as `benchmark.toml`.
+///
+/// Keys mirror the command-line flags; any flag given on the command line
+/// overrides the value from the file.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct FileConfig {
+    pub data_dir: Option<PathBuf>,
+    pub batch_size: Option<usize>,
+    pub tables: Option<Vec<String>>,
+    pub require_all_tables: Option<bool>,
+    pub expect_rows: Option<BTreeMap<String, i64>>,
+    pub insert_method: Option<InsertMethod>,
+    pub verify_row_count_per_batch: Option<bool>,
+    pub skip_bad_rows: Option<bool>,
+    pub stream: Option<bool>,
+    pub ndjson: Option<bool>,
+    pub sample_output: Option<bool>,
+    pub temp_tables: Option<bool>,
+    pub db_schema: Option<String>,
+    pub tui: Option<bool>,
+    pub compare: Option<Vec<String>>,
+    pub replica_url: Option<String>,
+    pub connection_url_file: Option<PathBuf>,
+    pub skip_analyze: Option<bool>,
+    pub check_chain: Option<bool>,
+    pub vacuum_between_phases: Option<bool>,
+    pub cold_cache: Option<bool>,
+    pub mixed_readers: Option<usize>,
+    pub connection_jitter_ms: Option<u64>,
+    pub pagination_page_size: Option<usize>,
+    pub pk_lookups: Option<usize>,
+    pub read_benchmarks: Option<Vec<String>>,
+    pub work_mem_sweep: Option<Vec<String>>,
+    pub single_insert_rows: Option<usize>,
+    pub skip_conflicts: Option<bool>,
+    pub slow_threshold_ms: Option<u64>,
+    pub explain_analyze_insert: Option<bool>,
+    pub returning_bench: Option<bool>,
+    pub multirow_bench: Option<bool>,
+    pub rows_per_stmt: Option<Vec<usize>>,
+    pub rate: Option<NonZeroU32>,
+    pub check_block_range: Option<bool>,
+    pub estimate_disk: Option<bool>,
+    pub validate: Option<bool>,
+    pub strict: Option<bool>,
+    pub bigint_blocks: Option<bool>,
+    pub toast_pad_bytes: Option<usize>,
+    pub server_timing: Option<bool>,
+    pub key_bench: Option<bool>,
+    pub reverse: Option<bool>,
+    pub deferred_fk_bench: Option<bool>,
+    pub copy_freeze: Option<bool>,
+    pub send_buffer_size: Option<u32>,
+    pub send_buffer_bench: Option<bool>,
+    pub client_encoding: Option<String>,
+    pub encoding_bench: Option<bool>,
+    pub array_bench: Option<bool>,
+    pub timestamp_bench: Option<bool>,
+    pub timestamp_format: Option<String>,
+    pub numeric_scale: Option<String>,
+    pub strict_numeric_scale: Option<bool>,
+    pub index_bloat_bench: Option<usize>,
+    pub upsert_bench: Option<bool>,
+    pub merge_bench: Option<bool>,
+    pub conflict_ratio: Option<f64>,
+    pub generated_column_bench: Option<bool>,
+    pub compression_bench: Option<bool>,
+    pub copy_abort_test: Option<bool>,
+    pub in_list_bench: Option<bool>,
+    pub scale_sweep: Option<bool>,
+    pub parallel_copy: Option<usize>,
+    pub partial_index_bench: Option<bool>,
+    pub prefix_search_bench: Option<bool>,
+    pub brin_bench: Option<bool>,
+    pub pk_after_load_bench: Option<bool>,
+    pub matview_bench: Option<bool>,
+    pub wal_level_bench: Option<bool>,
+    pub trigger_bench: Option<bool>,
+    pub pushgateway: Option<String>,
+    pub results_dir: Option<PathBuf>,
+    pub output_on_error: Option<bool>,
+    pub output: Option<OutputFormat>,
+    pub log_format: Option<LogFormat>,
+    pub profile: Option<bool>,
+    pub flamegraph: Option<PathBuf>,
+    pub table_stats_interval: Option<f64>,
+    pub table_stats_csv: Option<PathBuf>,
+    pub microbench_rows: Option<usize>,
+    pub connect_bench: Option<usize>,
+    pub no_tls: Option<bool>,
+}
+
+impl FileConfig {
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = fs::read_to_string(path)?;
+        Ok(toml::from_str(&contents)?)
+    }
+}
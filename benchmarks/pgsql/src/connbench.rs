@@ -0,0 +1,55 @@
+use crate::db;
+use crate::error::Result;
+use crate::report::{self, PhaseResult};
+use std::time::{Duration, Instant};
+use tokio_postgres::tls::MakeTlsConnect;
+use tokio_postgres::{Config, NoTls, Socket};
+
+/// Opens and closes `n` fresh connections one after another, including the TLS
+/// handshake when the URL's `sslmode` and the server negotiate one, and reports
+/// mean and p99 connect latency. With `compare_no_tls` the same is repeated
+/// over plain connections so the handshake cost shows up directly.
+#[tracing::instrument(name = "phase", skip_all, fields(phase = "connect_bench"))]
+pub async fn run_connect_benchmark(
+    database_url: &str,
+    n: usize,
+    compare_no_tls: bool,
+) -> Result<Vec<PhaseResult>> {
+    let config: Config = database_url.parse()?;
+    println!("\nConnection Establishment Benchmark ({} connections):", n);
+    println!("-------------------------");
+
+    let mut results = vec![time_connects(&config, n, "connect_tls", db::tls_connector()?).await?];
+    if compare_no_tls {
+        results.push(time_connects(&config, n, "connect_no_tls", NoTls).await?);
+    }
+    Ok(results)
+}
+
+async fn time_connects<T>(config: &Config, n: usize, phase: &str, tls: T) -> Result<PhaseResult>
+where
+    T: MakeTlsConnect<Socket> + Clone,
+    T::Stream: Send + 'static,
+{
+    let mut latencies = Vec::with_capacity(n);
+    let start = Instant::now();
+    for _ in 0..n {
+        let connect_start = Instant::now();
+        let (client, connection) = config.connect(tls.clone()).await?;
+        latencies.push(connect_start.elapsed());
+        // Dropping both ends closes the socket without a round trip
+        drop(client);
+        drop(connection);
+    }
+    let duration = start.elapsed();
+
+    let mean = latencies.iter().sum::<Duration>() / n.max(1) as u32;
+    let p99 = report::percentile(&mut latencies, 99.0);
+    println!(
+        "  {:<16} mean {:>10.2?}  p99 {:>10.2?}",
+        phase,
+        mean,
+        p99.unwrap_or_default()
+    );
+    Ok(PhaseResult::new(phase, n, duration).with_p99(p99))
+}
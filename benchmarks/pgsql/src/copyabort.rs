@@ -0,0 +1,90 @@
+use crate::bulk::BulkInsert;
+use crate::error::{BenchmarkError, Result};
+use crate::models::Transaction;
+use crate::report::PhaseResult;
+use crate::schema;
+use crate::variants::plain_transactions_ddl;
+use futures::pin_mut;
+use std::time::Instant;
+use tokio_postgres::binary_copy::BinaryCopyInWriter;
+use tokio_postgres::Client;
+
+const SCHEMA: &str = "copy_abort_test";
+
+/// COPYs half of one batch of transactions into a scratch table and drops the
+/// COPY without finishing it, checks that none of those rows landed, then
+/// COPYs the whole batch again and checks all of it did.
+///
+/// This is the guarantee resuming a failed load relies on: an interrupted COPY
+/// rolls back entirely, so its batch can simply be sent again. Fails with a
+/// validation error if either count is off.
+#[tracing::instrument(name = "phase", skip_all, fields(phase = "copy_abort_test"))]
+pub async fn run_copy_abort_test(
+    client: &Client,
+    transactions: &[Transaction],
+    batch_size: usize,
+) -> Result<Vec<PhaseResult>> {
+    let batch = &transactions[..batch_size.max(1).min(transactions.len())];
+    if batch.len() < 2 {
+        println!("\nSkipping the COPY abort test, it needs at least two transactions");
+        return Ok(Vec::new());
+    }
+
+    let previous = schema::enter_scratch_schema(client, SCHEMA, &plain_transactions_ddl()).await?;
+    let sent = batch.len() / 2;
+    abort_copy(client, &batch[..sent]).await?;
+    let after_abort = count_rows(client).await?;
+
+    let start = Instant::now();
+    let copied = Transaction::copy_in(client, batch, false).await?;
+    let retry = start.elapsed();
+    let after_retry = count_rows(client).await?;
+    schema::leave_scratch_schema(client, SCHEMA, &previous).await?;
+
+    println!(
+        "\nCOPY Abort and Retry Test ({} transactions):",
+        batch.len()
+    );
+    println!("-------------------------");
+    println!(
+        "  Aborted COPY after {} rows: {} rows in the table",
+        sent, after_abort
+    );
+    println!(
+        "  Retried COPY of the batch in {:.2?}: {} rows reported, {} in the table",
+        retry, copied, after_retry
+    );
+    if after_abort != 0 || after_retry != batch.len() as i64 {
+        return Err(BenchmarkError::ValidationError(format!(
+            "COPY abort left {} partial rows and the retry {} rows, expected 0 and {}",
+            after_abort,
+            after_retry,
+            batch.len()
+        )));
+    }
+    println!("  The abort left the table consistent and the batch could be resent");
+    Ok(vec![PhaseResult::new(
+        "copy_abort_retry",
+        batch.len(),
+        retry,
+    )])
+}
+
+/// Writes `rows` into a binary COPY and returns without calling `finish()`, so
+/// the dropped sink aborts the COPY.
+async fn abort_copy(client: &Client, rows: &[Transaction]) -> Result<()> {
+    let sink = client.copy_in(&Transaction::SPEC.copy_statement()).await?;
+    let writer = BinaryCopyInWriter::new(sink, &Transaction::SPEC.types(false));
+    pin_mut!(writer);
+    for row in rows {
+        writer.as_mut().write(&row.params()).await?;
+    }
+    Ok(())
+}
+
+async fn count_rows(client: &Client) -> Result<i64> {
+    Ok(client
+        .query_one("SELECT COUNT(*) FROM transactions", &[])
+        .await?
+        .get(0))
+}
@@ -0,0 +1,137 @@
+use crate::cli::Args;
+use crate::error::{BenchmarkError, Result};
+use crate::models::{Block, Pool, Transaction, Transfer};
+use crate::schema::TABLES;
+use crate::stream;
+use serde::de::DeserializeOwned;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::PathBuf;
+
+/// The four tables' records, loaded from `<data-dir>/<table>.json`, or
+/// `<table>.ndjson` under `--ndjson`.
+pub struct Dataset {
+    pub blocks: Vec<Block>,
+    pub transactions: Vec<Transaction>,
+    pub transfers: Vec<Transfer>,
+    pub pools: Vec<Pool>,
+}
+
+impl Dataset {
+    #[tracing::instrument(name = "phase", skip_all, fields(phase = "load_json"))]
+    pub fn load(args: &Args) -> Result<Dataset> {
+        Ok(Dataset {
+            blocks: load_table(args, "blocks")?,
+            transactions: load_table(args, "transactions")?,
+            transfers: load_table(args, "transfers")?,
+            pools: load_table(args, "pools")?,
+        })
+    }
+
+    /// Record count per table, in `schema::TABLES` order.
+    pub fn table_lens(&self) -> [usize; 4] {
+        [
+            self.blocks.len(),
+            self.transactions.len(),
+            self.transfers.len(),
+            self.pools.len(),
+        ]
+    }
+}
+
+/// Where a table's JSON array or NDJSON records are read from.
+pub enum DataSource {
+    File(PathBuf),
+    /// Piped in with `--stdin-table`
+    Stdin,
+}
+
+impl DataSource {
+    /// `<data-dir>/<table>.json` (`.ndjson` under `--ndjson`), unless `table`
+    /// is the `--stdin-table`.
+    pub fn for_table(args: &Args, table: &str) -> DataSource {
+        if args.stdin_table.as_deref() == Some(table) {
+            DataSource::Stdin
+        } else {
+            let extension = if args.ndjson { "ndjson" } else { "json" };
+            DataSource::File(args.data_dir().join(format!("{}.{}", table, extension)))
+        }
+    }
+
+    pub fn open(&self) -> io::Result<Box<dyn BufRead + Send>> {
+        match self {
+            DataSource::File(path) => Ok(Box::new(BufReader::new(File::open(path)?))),
+            DataSource::Stdin => Ok(Box::new(BufReader::new(io::stdin()))),
+        }
+    }
+}
+
+impl fmt::Display for DataSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DataSource::File(path) => write!(f, "{}", path.display()),
+            DataSource::Stdin => f.write_str("stdin"),
+        }
+    }
+}
+
+/// Drops each selected table whose file is missing from the `--tables`
+/// selection, with a warning, so a data directory holding only some of the
+/// files benchmarks those tables rather than loading and reporting empty ones.
+/// A missing file is an error under `--require-all-tables`, as is having none
+/// at all.
+pub fn skip_missing_tables(args: &mut Args) -> Result<()> {
+    let mut present = Vec::with_capacity(TABLES.len());
+    for table in TABLES.into_iter().filter(|t| args.includes_table(t)) {
+        match DataSource::for_table(args, table) {
+            DataSource::File(path) if !path.exists() => {
+                if args.require_all_tables {
+                    return Err(BenchmarkError::ConfigError(format!(
+                        "{} is missing (--require-all-tables)",
+                        path.display()
+                    )));
+                }
+                eprintln!(
+                    "Warning: {} is missing, skipping the {} table",
+                    path.display(),
+                    table
+                );
+            }
+            _ => present.push(table.to_string()),
+        }
+    }
+    if present.is_empty() {
+        return Err(BenchmarkError::ConfigError(format!(
+            "none of the selected tables has a data file in {}",
+            args.data_dir().display()
+        )));
+    }
+    args.tables = Some(present);
+    Ok(())
+}
+
+/// Reads every record from `source`: a JSON array, or with `ndjson` one
+/// JSON object per line.
+pub fn read_records<T: DeserializeOwned>(source: &DataSource, ndjson: bool) -> Result<Vec<T>> {
+    let reader = source.open()?;
+    if !ndjson {
+        return Ok(serde_json::from_reader(reader)?);
+    }
+    let mut records = Vec::new();
+    stream::read_ndjson_batches(reader, usize::MAX, |batch| {
+        records.extend(batch);
+        true
+    })?;
+    Ok(records)
+}
+
+/// Loads `<data-dir>/<table>.json` (or stdin for `--stdin-table`), or nothing if the table isn't selected with `--tables`.
+/// Tables whose file is missing were already dropped from the selection by
+/// `skip_missing_tables`, so any error reading or parsing a file fails the run.
+fn load_table<T: DeserializeOwned>(args: &Args, table: &str) -> Result<Vec<T>> {
+    if !args.includes_table(table) {
+        return Ok(Vec::new());
+    }
+    read_records(&DataSource::for_table(args, table), args.ndjson)
+}
@@ -0,0 +1,191 @@
+use crate::error::{BenchmarkError, Result};
+use crate::notices;
+use crate::report;
+use futures::future::try_join_all;
+use futures::{stream, StreamExt};
+use native_tls::TlsConnector;
+use postgres_native_tls::MakeTlsConnector;
+use rand::Rng;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::{lookup_host, TcpSocket, TcpStream};
+use tokio_postgres::config::Host;
+use tokio_postgres::tls::MakeTlsConnect;
+use tokio_postgres::{AsyncMessage, Client, Config, Connection};
+
+/// Opens a TLS-capable connection, drives it in the background and checks it
+/// round-trips a simple query.
+pub async fn connect(database_url: &str) -> Result<Client> {
+    connect_with_send_buffer(database_url, None).await
+}
+
+/// `connect`, but with `send_buffer_size` opens the TCP socket itself to set
+/// its send buffer (SO_SNDBUF) to that many bytes before connecting. Only TCP
+/// hosts can be tuned, and only the first host and address are tried.
+pub async fn connect_with_send_buffer(
+    database_url: &str,
+    send_buffer_size: Option<u32>,
+) -> Result<Client> {
+    let mut postgres_tls_connector = tls_connector()?;
+
+    // Both URL (`postgres://user@%2Fvar%2Frun%2Fpostgresql/db`) and key/value
+    // (`host=/var/run/postgresql`) forms accept a socket directory as the host
+    let config: Config = database_url.parse()?;
+    let client = match send_buffer_size {
+        None => {
+            let (client, connection) = config.connect(postgres_tls_connector).await?;
+            tokio::spawn(drive(connection));
+            client
+        }
+        Some(size) => {
+            let (host, stream) = tuned_stream(&config, size).await?;
+            let tls =
+                MakeTlsConnect::<TcpStream>::make_tls_connect(&mut postgres_tls_connector, &host)?;
+            let (client, connection) = config.connect_raw(stream, tls).await?;
+            tokio::spawn(drive(connection));
+            client
+        }
+    };
+    println!(
+        "Connected to {} over {}",
+        config_label(&config),
+        transport(&config)
+    );
+    // Now we can execute a simple statement that just returns its parameter.
+    let rows = client.query("SELECT $1::TEXT", &[&"hello world"]).await?;
+
+    // And then check that we got back the same string we sent over.
+    let value: &str = rows[0].get(0);
+    assert_eq!(value, "hello world");
+
+    Ok(client)
+}
+
+/// Opens `n` worker connections concurrently, each starting after a random
+/// delay within `jitter` so they don't all hit the server's connection setup
+/// at the same instant, and prints how long they took to connect. Comparing
+/// runs with and without `--connection-jitter-ms` shows whether staggering
+/// smooths the spike of simultaneous connects.
+pub async fn connect_workers(
+    database_url: &str,
+    n: usize,
+    jitter: Duration,
+) -> Result<Vec<Client>> {
+    let delays: Vec<Duration> = {
+        let mut rng = rand::thread_rng();
+        (0..n)
+            .map(|_| jitter.mul_f64(rng.gen_range(0.0..=1.0)))
+            .collect()
+    };
+    let start = Instant::now();
+    let workers = try_join_all(delays.into_iter().map(|delay| async move {
+        tokio::time::sleep(delay).await;
+        let connect_start = Instant::now();
+        let client = connect(database_url).await?;
+        Ok::<_, BenchmarkError>((client, connect_start.elapsed()))
+    }))
+    .await?;
+    let (clients, mut latencies): (Vec<Client>, Vec<Duration>) = workers.into_iter().unzip();
+    let mean = latencies.iter().sum::<Duration>() / n.max(1) as u32;
+    println!(
+        "Opened {} worker connections in {:.2?} (jitter {:?}): connect mean {:.2?}, max {:.2?}",
+        n,
+        start.elapsed(),
+        jitter,
+        mean,
+        report::percentile(&mut latencies, 100.0).unwrap_or_default()
+    );
+    Ok(clients)
+}
+
+/// Drives a connection in the background until it closes, handing the
+/// notices the server sends to `notices::record`.
+async fn drive<S, T>(mut connection: Connection<S, T>)
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+    T: AsyncRead + AsyncWrite + Unpin,
+{
+    let mut messages = stream::poll_fn(move |cx| connection.poll_message(cx));
+    while let Some(message) = messages.next().await {
+        match message {
+            Ok(AsyncMessage::Notice(notice)) => notices::record(&notice),
+            Ok(_) => {}
+            Err(e) => {
+                eprintln!("Database connection error: {}", e);
+                break;
+            }
+        }
+    }
+}
+
+/// Connects a TCP socket to the config's first host with its send buffer set
+/// to `size` bytes, returning the host name for TLS alongside it.
+async fn tuned_stream(config: &Config, size: u32) -> Result<(String, TcpStream)> {
+    let Some(Host::Tcp(host)) = config.get_hosts().first() else {
+        return Err(BenchmarkError::ConfigError(
+            "--send-buffer-size needs a TCP host, not a Unix socket".to_string(),
+        ));
+    };
+    let port = config.get_ports().first().copied().unwrap_or(5432);
+    let Some(addr) = lookup_host((host.as_str(), port)).await?.next() else {
+        return Err(BenchmarkError::ConfigError(format!(
+            "{} did not resolve to any address",
+            host
+        )));
+    };
+    let socket = if addr.is_ipv4() {
+        TcpSocket::new_v4()?
+    } else {
+        TcpSocket::new_v6()?
+    };
+    socket.set_send_buffer_size(size)?;
+    // Linux doubles the request for bookkeeping and caps it at net.core.wmem_max
+    println!(
+        "Send buffer: requested {} bytes, kernel reports {}",
+        size,
+        socket.send_buffer_size()?
+    );
+    let stream = socket.connect(addr).await?;
+    // As tokio-postgres does for the sockets it opens itself
+    stream.set_nodelay(true)?;
+    Ok((host.clone(), stream))
+}
+
+/// The TLS connector used for every connection; whether TLS is actually
+/// negotiated depends on the URL's `sslmode` and the server.
+pub fn tls_connector() -> Result<MakeTlsConnector> {
+    let tls_connector = TlsConnector::builder().build()?;
+    Ok(MakeTlsConnector::new(tls_connector))
+}
+
+/// Short `host:port/dbname` name for a connection URL, without credentials.
+pub fn target_label(database_url: &str) -> Result<String> {
+    let config: Config = database_url.parse()?;
+    Ok(config_label(&config))
+}
+
+/// Whether the (first) host is reached over TCP or a Unix domain socket,
+/// which noticeably changes single-statement latency.
+fn transport(config: &Config) -> &'static str {
+    match config.get_hosts().first() {
+        #[cfg(unix)]
+        Some(Host::Unix(_)) => "a Unix socket",
+        _ => "TCP",
+    }
+}
+
+fn config_label(config: &Config) -> String {
+    let host = match config.get_hosts().first() {
+        Some(Host::Tcp(host)) => host.clone(),
+        #[cfg(unix)]
+        Some(Host::Unix(path)) => path.display().to_string(),
+        None => "localhost".to_string(),
+    };
+    let port = config.get_ports().first().copied().unwrap_or(5432);
+    format!(
+        "{}:{}/{}",
+        host,
+        port,
+        config.get_dbname().unwrap_or("postgres")
+    )
+}
@@ -0,0 +1,78 @@
+use crate::arrays::plain_transfers_ddl;
+use crate::bulk::copy_all;
+use crate::error::{BenchmarkError, Result};
+use crate::models::Transfer;
+use crate::report::PhaseResult;
+use crate::schema;
+use std::time::Duration;
+use tokio_postgres::Client;
+
+/// Client encodings the transfer load is timed under, UTF8 first as the baseline
+const ENCODINGS: [&str; 3] = ["UTF8", "LATIN1", "SQL_ASCII"];
+
+/// Switches the session's `client_encoding`, which sets the conversion the
+/// server applies to every text value sent, binary COPY included.
+///
+/// tokio-postgres decodes text it reads back as UTF-8, so other encodings are
+/// only safe while the data is ASCII, as the hex strings of the dataset are.
+pub async fn set_client_encoding(client: &Client, encoding: &str) -> Result<()> {
+    client
+        .batch_execute(&format!("SET client_encoding = '{}'", encoding))
+        .await?;
+    Ok(())
+}
+
+/// Times COPY of `transfers`, the most text-heavy table, into a scratch table
+/// under each of `ENCODINGS`, restoring the session's encoding afterwards.
+/// Encodings the server can't convert to are skipped.
+#[tracing::instrument(name = "phase", skip_all, fields(phase = "encoding_benchmark"))]
+pub async fn run_encoding_benchmark(
+    client: &Client,
+    transfers: &[Transfer],
+    batch_size: usize,
+) -> Result<Vec<PhaseResult>> {
+    let original: String = client.query_one("SHOW client_encoding", &[]).await?.get(0);
+    let previous =
+        schema::enter_scratch_schema(client, "encoding_bench", &plain_transfers_ddl()).await?;
+
+    let mut runs: Vec<(&str, Duration)> = Vec::new();
+    for encoding in ENCODINGS {
+        match set_client_encoding(client, encoding).await {
+            Ok(()) => {}
+            Err(BenchmarkError::DatabaseError(e)) if !e.is_closed() => {
+                println!("Skipping client_encoding {}: {}", encoding, e);
+                continue;
+            }
+            Err(e) => return Err(e),
+        }
+        client.batch_execute("TRUNCATE transfers").await?;
+        runs.push((encoding, copy_all(client, transfers, batch_size).await?));
+    }
+
+    set_client_encoding(client, &original).await?;
+    schema::leave_scratch_schema(client, "encoding_bench", &previous).await?;
+
+    println!(
+        "\nClient Encoding Benchmark ({} transfers):",
+        transfers.len()
+    );
+    println!("-------------------------");
+    let Some(&(baseline, baseline_duration)) = runs.first() else {
+        return Ok(Vec::new());
+    };
+    let mut results = Vec::new();
+    for (encoding, duration) in runs {
+        let rate = transfers.len() as f64 / duration.as_secs_f64();
+        let change = 100.0 * (baseline_duration.as_secs_f64() / duration.as_secs_f64() - 1.0);
+        println!(
+            "  {:<10} {:>12.2?}  {:>10.0} rows/sec  ({:+.1}% vs {})",
+            encoding, duration, rate, change, baseline
+        );
+        results.push(PhaseResult::new(
+            &format!("encoding_{}_copy", encoding.to_lowercase()),
+            transfers.len(),
+            duration,
+        ));
+    }
+    Ok(results)
+}
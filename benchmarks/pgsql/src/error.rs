@@ -1,9 +1,12 @@
-use tracing::{debug, error};
+use tokio_postgres::error::SqlState;
 
 #[derive(thiserror::Error, Debug)]
 pub enum BenchmarkError {
-    #[error("Environment variable not found: {0}")]
-    EnvVarError(#[from] std::env::VarError),
+    #[error("{var} is not usable ({source}); set it to the target's connection URL, pass --connection-url-file, or set PGHOST (with PGPORT, PGUSER, PGPASSWORD and PGDATABASE as needed)")]
+    EnvVarError {
+        var: &'static str,
+        source: std::env::VarError,
+    },
 
     #[error("Database error: {0}")]
     DatabaseError(#[from] tokio_postgres::Error),
@@ -11,14 +14,97 @@ pub enum BenchmarkError {
     #[error("JSON parsing error: {0}")]
     JsonError(#[from] serde_json::Error),
 
+    #[error("NDJSON parse error on line {line}: {source}")]
+    NdjsonError {
+        line: usize,
+        source: serde_json::Error,
+    },
+
+    #[error("Config file error: {0}")]
+    TomlError(#[from] toml::de::Error),
+
+    #[error("Invalid configuration: {0}")]
+    ConfigError(String),
+
+    #[error("Dataset validation failed: {0}")]
+    ValidationError(String),
+
     #[error("IO error: {0}")]
     IoError(#[from] std::io::Error),
 
-    #[error("SSL error: Could not create Tls ")]
-    TlsError(),
+    #[error("COPY into {table} failed at batch {batch}: wrote {written} rows but the server reported {copied}")]
+    CopyFailed {
+        table: String,
+        batch: usize,
+        written: usize,
+        copied: u64,
+    },
+
+    #[error("--inject-failure couldn't fail {table} batch {batch}: the server raised no error")]
+    InjectionFailed { table: String, batch: usize },
+
+    #[error("Metrics export error: {0}")]
+    MetricsError(#[from] prometheus::Error),
+
+    #[error("SSL error: could not create the TLS connector: {0}")]
+    TlsError(#[from] native_tls::Error),
 
-    #[error("Unknown error occurred")]
-    Unknown,
+    #[cfg(feature = "flamegraph")]
+    #[error("Profiler error: {0}")]
+    ProfilerError(#[from] pprof::Error),
 }
 
 pub type Result<T> = std::result::Result<T, BenchmarkError>;
+
+/// How a failed statement should be handled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorClass {
+    /// Transient: serialization failure, deadlock, lock timeout or a
+    /// connection exception the server reported (SQLSTATE class 08); the same
+    /// statement may succeed if retried. A closed client is `Fatal`, since
+    /// nothing reconnects it
+    Retryable,
+    /// An integrity constraint (unique, check, foreign key, not null) rejected
+    /// the row; retrying will fail the same way
+    Constraint,
+    /// Anything else
+    Fatal,
+}
+
+/// Classifies a statement error by its SQLSTATE.
+pub fn classify(e: &tokio_postgres::Error) -> ErrorClass {
+    if e.is_closed() {
+        return ErrorClass::Fatal;
+    }
+    let Some(code) = e.code() else {
+        return ErrorClass::Fatal;
+    };
+    if *code == SqlState::T_R_SERIALIZATION_FAILURE
+        || *code == SqlState::T_R_DEADLOCK_DETECTED
+        || *code == SqlState::LOCK_NOT_AVAILABLE
+        || code.code().starts_with("08")
+    {
+        ErrorClass::Retryable
+    } else if code.code().starts_with("23") {
+        // Class 23: integrity constraint violation
+        ErrorClass::Constraint
+    } else {
+        ErrorClass::Fatal
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tls_error_message_includes_the_cause() {
+        let Err(cause) = native_tls::Certificate::from_pem(b"not a certificate") else {
+            panic!("garbage PEM was accepted");
+        };
+        let detail = cause.to_string();
+        let message = BenchmarkError::from(cause).to_string();
+        assert!(!detail.is_empty());
+        assert!(message.ends_with(&detail), "{}", message);
+    }
+}
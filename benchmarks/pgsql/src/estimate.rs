@@ -0,0 +1,221 @@
+use crate::cli::Args;
+use crate::dataset::Dataset;
+use crate::error::Result;
+use crate::schema::TABLES;
+use crate::stats::try_stat;
+use std::ffi::CString;
+use std::path::Path;
+use tokio_postgres::Client;
+
+const PAGE_BYTES: usize = 8192;
+
+/// Page header, plus the B-tree special space on index pages
+const PAGE_HEADER_BYTES: usize = 24;
+const BTREE_SPECIAL_BYTES: usize = 16;
+
+/// Heap tuple header, with no NULLs to track
+const TUPLE_HEADER_BYTES: usize = 24;
+
+/// Line pointer per tuple on a page
+const LINE_POINTER_BYTES: usize = 4;
+
+/// Primary key index entry: index tuple header and INTEGER key, aligned
+const PK_ENTRY_BYTES: usize = 16;
+
+/// Share of each B-tree leaf page filled by default
+const BTREE_FILL: f64 = 0.9;
+
+/// Estimated on-disk size of one table once loaded.
+struct TableEstimate {
+    table: &'static str,
+    rows: usize,
+    avg_row_bytes: f64,
+    heap_bytes: u64,
+    index_bytes: u64,
+}
+
+/// On-disk size of a TEXT value: its bytes behind a 1-byte header, or a
+/// 4-byte one past 126 bytes.
+fn text_bytes(value: &str) -> usize {
+    value.len() + if value.len() <= 126 { 1 } else { 4 }
+}
+
+/// Tuple size on disk from the width of its columns, 8-byte aligned.
+fn tuple_bytes(data: usize) -> usize {
+    (TUPLE_HEADER_BYTES + data).div_ceil(8) * 8
+}
+
+/// Average tuple size of `records`, `row_data` giving each one's column bytes.
+fn avg_tuple_bytes<T>(records: &[T], row_data: impl Fn(&T) -> usize) -> f64 {
+    let total: usize = records.iter().map(|r| tuple_bytes(row_data(r))).sum();
+    total as f64 / records.len().max(1) as f64
+}
+
+/// Heap pages `rows` tuples of `avg_row_bytes` fill, in bytes.
+fn heap_bytes(rows: usize, avg_row_bytes: f64) -> u64 {
+    let per_page = ((PAGE_BYTES - PAGE_HEADER_BYTES) as f64
+        / (avg_row_bytes + LINE_POINTER_BYTES as f64))
+        .floor()
+        .max(1.0);
+    (rows as f64 / per_page).ceil() as u64 * PAGE_BYTES as u64
+}
+
+/// Leaf pages of the `id` primary key index over `rows` rows, in bytes.
+fn pk_index_bytes(rows: usize) -> u64 {
+    let per_page = ((PAGE_BYTES - PAGE_HEADER_BYTES - BTREE_SPECIAL_BYTES) as f64 * BTREE_FILL
+        / (PK_ENTRY_BYTES + LINE_POINTER_BYTES) as f64)
+        .floor();
+    // Plus the metapage and root
+    ((rows as f64 / per_page).ceil() as u64 + 2) * PAGE_BYTES as u64
+}
+
+/// Sizes each selected table will take on disk from its record count and the
+/// widths of its values, under the benchmark schema: heap pages at the
+/// default fillfactor and the `id` primary key index.
+fn estimate_tables(args: &Args, dataset: &Dataset) -> Vec<TableEstimate> {
+    let height = if args.bigint_blocks { 8 } else { 4 };
+    let widths = [
+        avg_tuple_bytes(&dataset.blocks, |b| {
+            4 + height
+                + text_bytes(&b.block_hash)
+                + text_bytes(&b.parent_hash)
+                + text_bytes(&b.block_timestamp)
+                + text_bytes(&b.created_at)
+                + text_bytes(&b.updated_at)
+        }),
+        avg_tuple_bytes(&dataset.transactions, |t| {
+            4 + height
+                + 4
+                + text_bytes(&t.timestamp)
+                + text_bytes(&t.hash)
+                + text_bytes(&t.from)
+                + text_bytes(&t.to)
+                + text_bytes(&t.value)
+        }),
+        avg_tuple_bytes(&dataset.transfers, |t| {
+            4 + text_bytes(&t.tx_hash)
+                + height
+                + text_bytes(&t.token)
+                + text_bytes(&t.from)
+                + text_bytes(&t.to)
+                + text_bytes(&t.amount)
+        }),
+        avg_tuple_bytes(&dataset.pools, |p| {
+            4 + text_bytes(&p.deployer)
+                + text_bytes(&p.address)
+                + text_bytes(&p.quote_token)
+                + text_bytes(&p.token)
+                + height
+                + 8
+        }),
+    ];
+    TABLES
+        .into_iter()
+        .zip(dataset.table_lens())
+        .zip(widths)
+        .filter(|((table, _), _)| args.includes_table(table))
+        .map(|((table, rows), avg_row_bytes)| TableEstimate {
+            table,
+            rows,
+            avg_row_bytes,
+            heap_bytes: heap_bytes(rows, avg_row_bytes),
+            index_bytes: pk_index_bytes(rows),
+        })
+        .collect()
+}
+
+/// Free bytes for unprivileged users on the filesystem holding `path`.
+#[cfg(unix)]
+fn available_bytes(path: &Path) -> Option<u64> {
+    use std::os::unix::ffi::OsStrExt;
+    let path = CString::new(path.as_os_str().as_bytes()).ok()?;
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    // SAFETY: `path` is NUL-terminated and `stat` is a valid statvfs to fill
+    if unsafe { libc::statvfs(path.as_ptr(), &mut stat) } != 0 {
+        return None;
+    }
+    Some(stat.f_bavail as u64 * stat.f_frsize as u64)
+}
+
+#[cfg(not(unix))]
+fn available_bytes(_path: &Path) -> Option<u64> {
+    None
+}
+
+/// Prints the disk space the load needs by table, estimated before anything
+/// is written, and warns if it exceeds the free space of the server's data
+/// directory. Free space can only be read when the server runs on this
+/// machine and this user may see `data_directory`; otherwise only the
+/// estimate and the database's current size are shown.
+pub async fn check_disk_space(client: &Client, args: &Args, dataset: &Dataset) -> Result<()> {
+    let estimates = estimate_tables(args, dataset);
+    let total: u64 = estimates.iter().map(|e| e.heap_bytes + e.index_bytes).sum();
+    let current: i64 = client
+        .query_one("SELECT pg_database_size(current_database())", &[])
+        .await?
+        .get(0);
+
+    println!("\nDisk Space Estimate:");
+    println!("-------------------------");
+    for estimate in &estimates {
+        println!(
+            "  {:<13} {:>9} rows  {:>6.0} B/row  heap {:>9.1} MiB  index {:>8.1} MiB",
+            estimate.table,
+            estimate.rows,
+            estimate.avg_row_bytes,
+            mib(estimate.heap_bytes),
+            mib(estimate.index_bytes)
+        );
+    }
+    println!(
+        "  Total: {:.1} MiB, on top of the database's current {:.1} MiB",
+        mib(total),
+        mib(current as u64)
+    );
+    println!("  Excludes TOAST, WAL and the single insert test's rows");
+
+    let free = match try_stat(client, "SHOW data_directory", &[]).await? {
+        Some(rows) => {
+            let directory: String = rows[0].get(0);
+            Path::new(&directory)
+                .exists()
+                .then(|| available_bytes(Path::new(&directory)))
+                .flatten()
+                .map(|free| (directory, free))
+        }
+        None => None,
+    };
+    match free {
+        Some((directory, free)) => {
+            println!("  Free space in {}: {:.1} MiB", directory, mib(free));
+            if total > free {
+                eprintln!(
+                    "Warning: the load needs an estimated {:.1} MiB but only {:.1} MiB is free in {}",
+                    mib(total),
+                    mib(free),
+                    directory
+                );
+            }
+        }
+        None => println!("  Free space unknown, the data directory isn't readable from here"),
+    }
+    Ok(())
+}
+
+fn mib(bytes: u64) -> f64 {
+    bytes as f64 / (1024.0 * 1024.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn heap_pages_round_up() {
+        assert_eq!(heap_bytes(0, 100.0), 0);
+        assert_eq!(heap_bytes(1, 100.0), PAGE_BYTES as u64);
+        // 78 tuples of 100 bytes and their line pointers fit a page
+        assert_eq!(heap_bytes(78, 100.0), PAGE_BYTES as u64);
+        assert_eq!(heap_bytes(79, 100.0), 2 * PAGE_BYTES as u64);
+    }
+}
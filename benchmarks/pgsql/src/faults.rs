@@ -0,0 +1,54 @@
+use crate::cli::Args;
+use crate::error::{BenchmarkError, Result};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tokio_postgres::Client;
+
+/// Load batches failed on purpose by `--inject-failure`
+static INJECTED: AtomicUsize = AtomicUsize::new(0);
+
+/// Injected failures whose batch was then written in full anyway
+static RECOVERED: AtomicUsize = AtomicUsize::new(0);
+
+/// Whether `--inject-failure every=N` fails load batch `batch`, counted from 1
+/// per table.
+pub fn injects(args: &Args, batch: usize) -> bool {
+    args.inject_failure
+        .is_some_and(|every| batch.is_multiple_of(every))
+}
+
+/// Fails a load batch as a server rejection would: the statement standing in
+/// for its COPY raises an error, so the batch's rows are never written and
+/// the load's usual error handling takes over. Should the server not raise
+/// it, the load fails instead of carrying on with the batch unwritten.
+pub async fn fail_batch(client: &Client, table: &str, batch: usize) -> Result<u64> {
+    INJECTED.fetch_add(1, Ordering::Relaxed);
+    client
+        .batch_execute(&format!(
+            "DO $$ BEGIN RAISE EXCEPTION 'injected failure: {} batch {}'; END $$",
+            table, batch
+        ))
+        .await?;
+    Err(BenchmarkError::InjectionFailed {
+        table: table.to_string(),
+        batch,
+    })
+}
+
+/// Records that an injected failure's batch was recovered.
+pub fn record_recovered() {
+    RECOVERED.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Prints how many failures were injected and how many of their batches were
+/// recovered, under `--inject-failure`.
+pub fn print_summary(args: &Args) {
+    if args.inject_failure.is_none() {
+        return;
+    }
+    let injected = INJECTED.load(Ordering::Relaxed);
+    let recovered = RECOVERED.load(Ordering::Relaxed);
+    println!(
+        "Injected failures (--inject-failure): {}, recovered: {}",
+        injected, recovered
+    );
+}
@@ -0,0 +1,51 @@
+use crate::error::Result;
+use std::path::Path;
+
+/// Stack samples taken per second while profiling
+#[cfg(feature = "flamegraph")]
+const FREQUENCY: i32 = 999;
+
+/// Samples this process' stacks for `--flamegraph` until written out.
+///
+/// Only CPU time is sampled, across every thread: serde, `ToSql` encoding and
+/// the connection task's socket writes show up, time spent waiting on the
+/// server doesn't, so a narrow graph over a long load points at the server.
+pub struct Profiler {
+    #[cfg(feature = "flamegraph")]
+    guard: pprof::ProfilerGuard<'static>,
+}
+
+impl Profiler {
+    pub fn start() -> Result<Self> {
+        #[cfg(feature = "flamegraph")]
+        {
+            let guard = pprof::ProfilerGuardBuilder::default()
+                .frequency(FREQUENCY)
+                .blocklist(&["libc", "libgcc", "pthread", "vdso"])
+                .build()?;
+            Ok(Self { guard })
+        }
+        // Args::validate rejects --flamegraph in builds without the flamegraph feature
+        #[cfg(not(feature = "flamegraph"))]
+        unreachable!("--flamegraph needs the flamegraph feature")
+    }
+
+    /// Stops sampling and writes the samples taken so far to `path` as an SVG flamegraph.
+    pub fn write(self, path: &Path) -> Result<()> {
+        #[cfg(feature = "flamegraph")]
+        {
+            let report = self.guard.report().build()?;
+            report.flamegraph(std::fs::File::create(path)?)?;
+            println!(
+                "Flamegraph of the bulk insert written to {}",
+                path.display()
+            );
+            Ok(())
+        }
+        #[cfg(not(feature = "flamegraph"))]
+        unreachable!(
+            "--flamegraph needs the flamegraph feature, not writing {}",
+            path.display()
+        )
+    }
+}
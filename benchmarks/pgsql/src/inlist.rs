@@ -0,0 +1,90 @@
+use crate::bulk::copy_all;
+use crate::error::Result;
+use crate::models::Transaction;
+use crate::report::{self, PhaseResult};
+use crate::schema;
+use crate::variants::plain_transactions_ddl;
+use rand::seq::SliceRandom;
+use std::time::{Duration, Instant};
+use tokio_postgres::Client;
+
+const HASH_INDEX_DDL: &str = "
+    CREATE INDEX ON transactions (hash);";
+
+/// Hashes per `= ANY($1)` array
+const LIST_SIZES: [usize; 3] = [10, 100, 1000];
+
+/// Queries timed per list size
+const QUERIES_PER_SIZE: usize = 100;
+
+/// Loads `transactions` into a scratch table indexed on `hash`, then times
+/// `SELECT * FROM transactions WHERE hash = ANY($1)` with arrays of random
+/// hashes from the dataset at each of `LIST_SIZES`, reporting latency per
+/// size and the rate at which rows come back.
+#[tracing::instrument(name = "phase", skip_all, fields(phase = "in_list_benchmark"))]
+pub async fn run_in_list_benchmark(
+    client: &Client,
+    transactions: &[Transaction],
+    batch_size: usize,
+) -> Result<Vec<PhaseResult>> {
+    if transactions.is_empty() {
+        println!("\nSkipping the IN-list benchmark, there are no transactions");
+        return Ok(Vec::new());
+    }
+    let previous = schema::enter_scratch_schema(
+        client,
+        "in_list_bench",
+        &(plain_transactions_ddl() + HASH_INDEX_DDL),
+    )
+    .await?;
+    copy_all(client, transactions, batch_size).await?;
+    client.execute("ANALYZE transactions", &[]).await?;
+    let statement = client
+        .prepare("SELECT * FROM transactions WHERE hash = ANY($1)")
+        .await?;
+
+    let mut rng = rand::thread_rng();
+    let mut runs = Vec::with_capacity(LIST_SIZES.len());
+    for size in LIST_SIZES {
+        let mut latencies = Vec::with_capacity(QUERIES_PER_SIZE);
+        let mut rows = 0;
+        for _ in 0..QUERIES_PER_SIZE {
+            let hashes: Vec<&str> = transactions
+                .choose_multiple(&mut rng, size)
+                .map(|tx| tx.hash.as_str())
+                .collect();
+            let start = Instant::now();
+            rows += client.query(&statement, &[&hashes]).await?.len();
+            latencies.push(start.elapsed());
+        }
+        runs.push((size, rows, latencies));
+    }
+    schema::leave_scratch_schema(client, "in_list_bench", &previous).await?;
+
+    println!(
+        "\nIN-List Query Benchmark ({} queries per list size):",
+        QUERIES_PER_SIZE
+    );
+    println!("-------------------------");
+    let mut results = Vec::new();
+    for (size, rows, mut latencies) in runs {
+        let duration: Duration = latencies.iter().sum();
+        let mean = duration / latencies.len().max(1) as u32;
+        let p99 = report::percentile(&mut latencies, 99.0);
+        println!(
+            "  {:>5} hashes  mean {:>9.2?}  p99 {:>9.2?}  {:>10.0} rows/sec",
+            size,
+            mean,
+            p99.unwrap_or_default(),
+            rows as f64 / duration.as_secs_f64()
+        );
+        results.push(PhaseResult::new(&format!("in_list_{}", size), rows, duration).with_p99(p99));
+    }
+    if transactions.len() < LIST_SIZES[LIST_SIZES.len() - 1] {
+        println!(
+            "  Note: only {} transactions, larger lists hold all of them",
+            transactions.len()
+        );
+    }
+    Ok(results)
+}
@@ -0,0 +1,100 @@
+use crate::error::Result;
+use crate::models::{Block, BlockHeight, Pool, Transaction, Transfer};
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+
+fn example_block() -> Block {
+    Block {
+        block_number: BlockHeight(17_000_000),
+        block_hash: "0x5c3e...".to_string(),
+        parent_hash: "0x9a1f...".to_string(),
+        block_timestamp: "2023-01-01T00:00:00".to_string(),
+        created_at: "2023-01-01T00:00:00".to_string(),
+        updated_at: "2023-01-01T00:00:00".to_string(),
+    }
+}
+
+fn example_transaction() -> Transaction {
+    Transaction {
+        block: BlockHeight(17_000_000),
+        index: 0,
+        timestamp: "2023-01-01T00:00:00".to_string(),
+        hash: "0x9735...".to_string(),
+        from: "0xa1b4...".to_string(),
+        to: "0x2394...".to_string(),
+        value: "988902439231429732".to_string(),
+    }
+}
+
+fn example_transfer() -> Transfer {
+    Transfer {
+        tx_hash: "0x9735...".to_string(),
+        block_number: BlockHeight(17_000_000),
+        token: "0xc02a...".to_string(),
+        from: "0xa1b4...".to_string(),
+        to: "0x2394...".to_string(),
+        amount: "1000000000000000000".to_string(),
+    }
+}
+
+fn example_pool() -> Pool {
+    Pool {
+        deployer: "0x1f98...".to_string(),
+        address: "0x88e6...".to_string(),
+        quote_token: "0xc02a...".to_string(),
+        token: "0xa0b8...".to_string(),
+        init_block: BlockHeight(17_000_000),
+        created_at: 1_672_531_200,
+    }
+}
+
+/// JSON Schema type name of a serialized field value.
+fn json_type(value: &Value) -> &'static str {
+    match value {
+        Value::Number(n) if n.is_f64() => "number",
+        Value::Number(_) => "integer",
+        Value::String(_) => "string",
+        Value::Bool(_) => "boolean",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+        Value::Null => "null",
+    }
+}
+
+/// A JSON Schema for a `<table>.json` file: an array of records shaped like
+/// `example` once serialized. Every field is required, since the models have
+/// no optional or defaulted fields.
+fn array_schema<T: Serialize>(table: &str, example: &T) -> Result<Value> {
+    let Value::Object(record) = serde_json::to_value(example)? else {
+        unreachable!("models serialize as JSON objects");
+    };
+    let properties: Map<String, Value> = record
+        .iter()
+        .map(|(field, value)| (field.clone(), json!({ "type": json_type(value) })))
+        .collect();
+    let required: Vec<&String> = record.keys().collect();
+    Ok(json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "title": format!("{}.json", table),
+        "type": "array",
+        "items": {
+            "type": "object",
+            "properties": properties,
+            "required": required,
+            "examples": [record],
+        },
+    }))
+}
+
+/// Prints the JSON Schema of `<table>.json`, derived from the record struct
+/// it is deserialized into so it can't fall out of step with the loader.
+pub fn print_json_schema(table: &str) -> Result<()> {
+    let schema = match table {
+        "blocks" => array_schema(table, &example_block())?,
+        "transactions" => array_schema(table, &example_transaction())?,
+        "transfers" => array_schema(table, &example_transfer())?,
+        _ => array_schema(table, &example_pool())?,
+    };
+    println!("{}", serde_json::to_string_pretty(&schema)?);
+    Ok(())
+}
@@ -0,0 +1,50 @@
+use crate::error::{BenchmarkError, Result};
+use tokio_postgres::Client;
+
+/// Counts blocks whose `parent_hash` doesn't match the `block_hash` of any loaded block.
+///
+/// The lowest loaded block always counts as missing its parent, so a gap-free
+/// chain reports exactly one.
+#[tracing::instrument(name = "phase", skip_all, fields(phase = "check_chain"))]
+pub async fn count_orphan_blocks(client: &Client) -> Result<i64> {
+    let row = client
+        .query_one(
+            "SELECT COUNT(*) FROM blocks b
+             LEFT JOIN blocks p ON p.block_hash = b.parent_hash
+             WHERE p.id IS NULL",
+            &[],
+        )
+        .await?;
+    Ok(row.get(0))
+}
+
+/// Fails with a per-table diff unless each table's row count is exactly the
+/// expected one, for `--expect-rows`.
+pub async fn check_row_counts(client: &Client, expected: &[(String, i64)]) -> Result<()> {
+    let mut mismatches = Vec::new();
+    for (table, expected) in expected {
+        let actual: i64 = client
+            .query_one(&format!("SELECT COUNT(*) FROM {}", table), &[])
+            .await?
+            .get(0);
+        if actual == *expected {
+            println!("  {}: {} rows as expected", table, actual);
+        } else {
+            println!(
+                "  {}: expected {} rows, found {} ({:+})",
+                table,
+                expected,
+                actual,
+                actual - expected
+            );
+            mismatches.push(format!(
+                "{} has {} rows, expected {}",
+                table, actual, expected
+            ));
+        }
+    }
+    if !mismatches.is_empty() {
+        return Err(BenchmarkError::ValidationError(mismatches.join("; ")));
+    }
+    Ok(())
+}
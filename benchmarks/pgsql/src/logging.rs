@@ -0,0 +1,43 @@
+use crate::cli::LogFormat;
+use tracing::level_filters::LevelFilter;
+use tracing_subscriber::fmt::format::FmtSpan;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::Layer;
+
+/// Installs a stderr subscriber for `--log-format`, logging when each phase
+/// span opens and closes (with its busy time) and each phase's result, all
+/// within a `run` span carrying the run id, and with `--profile` the
+/// `tokio-console` layer.
+///
+/// Without either option no subscriber is installed and only the usual report is printed.
+pub fn init(format: Option<LogFormat>, profile: bool) {
+    if format.is_none() && !profile {
+        return;
+    }
+
+    #[cfg(feature = "console")]
+    let console = profile.then(|| console_subscriber::ConsoleLayer::builder().spawn());
+    // Args::validate rejects --profile in builds without the console feature
+    #[cfg(not(feature = "console"))]
+    let console: Option<tracing_subscriber::layer::Identity> = None;
+
+    let fmt = format.map(|format| {
+        let layer = tracing_subscriber::fmt::layer()
+            .with_writer(std::io::stderr)
+            .with_span_events(FmtSpan::NEW | FmtSpan::CLOSE);
+        match format {
+            LogFormat::Text => layer.with_filter(LevelFilter::INFO).boxed(),
+            LogFormat::Json => layer
+                .json()
+                .with_span_list(true)
+                .with_filter(LevelFilter::INFO)
+                .boxed(),
+        }
+    });
+
+    tracing_subscriber::registry()
+        .with(console)
+        .with(fmt)
+        .init();
+}
@@ -1,285 +1,844 @@
 use dotenv::dotenv;
-use futures::pin_mut;
-use native_tls::TlsConnector;
-use postgres::types::ToSql;
-use postgres_native_tls::MakeTlsConnector;
-use serde::Deserialize;
-use serde_json;
-use std::env;
 use std::fs::File;
-use std::time::Instant;
-use tokio_postgres::binary_copy::BinaryCopyInWriter;
-use tokio_postgres::types::Type;
-use tokio_postgres::{Client, NoTls};
-
+use std::io::{BufWriter, Write};
+use std::process::{self, ExitCode};
+use std::time::{Duration, Instant};
+use tokio_postgres::Client;
+use tracing::Instrument;
+
+mod amounts;
+mod arrays;
+mod bloat;
+mod brin;
+mod bulk;
+mod catalog;
+mod cli;
+mod compression;
+mod config;
+mod connbench;
+mod copyabort;
+mod dataset;
+mod db;
+mod encoding;
 mod error;
+mod estimate;
+mod faults;
+mod flamegraph;
+mod inlist;
+mod input_schema;
+mod integrity;
+mod logging;
+mod matview;
+mod microbench;
+mod mixed;
 mod models;
+mod notices;
+mod parallel;
+mod partial;
+mod pkload;
+mod prefix;
+mod pushgateway;
+mod reads;
+mod replica;
+mod report;
+mod results;
+mod scaling;
 mod schema;
+mod sendbuf;
+mod signal;
+mod single;
+mod stats;
+mod status;
+mod stream;
+mod timestamps;
+mod toast;
+mod triggers;
+mod tui;
+mod validate;
+mod variants;
+mod wallevel;
+
+/// Runs the suite, but on SIGINT/SIGTERM reports the phases finished so far
+/// and exits instead of dying with no output.
+async fn run_until_signal(
+    client: &mut Client,
+    database_url: &str,
+    args: &cli::Args,
+    dataset: Option<&dataset::Dataset>,
+    results: &mut Vec<report::PhaseResult>,
+    timings: &mut report::PhaseTimings,
+) -> error::Result<()> {
+    let interrupted = tokio::select! {
+        outcome = run_suite(client, database_url, args, dataset, results, timings) => {
+            outcome?;
+            None
+        }
+        signal = signal::shutdown_signal() => Some(signal),
+    };
+    if let Some(signal) = interrupted {
+        report::log_results(results);
+        println!("\nReceived {}, stopping early", signal.name);
+        report::print_summary(results);
+        notices::print_summary();
+        timings.print_breakdown();
+        report::print_summary_lines(&db::target_label(database_url)?, results);
+        process::exit(signal.exit_code);
+    }
+    Ok(())
+}
 
-fn load_json_data<T>(file_path: &str) -> Result<Vec<T>, serde_json::Error>
-where
-    T: for<'a> Deserialize<'a>,
-{
-    let file = File::open(file_path).unwrap();
-    serde_json::from_reader(file)
+/// Prints the Markdown results table under `--output markdown` and sends each
+/// target's results to the structured log, the pushgateway and
+/// `--results-dir`, whichever are configured.
+async fn emit_results(
+    args: &cli::Args,
+    runs: &[(String, Vec<report::PhaseResult>)],
+) -> error::Result<()> {
+    if args.output == cli::OutputFormat::Markdown {
+        report::print_markdown(runs);
+    }
+    for (target, results) in runs {
+        report::log_results(results);
+        if let Some(url) = &args.pushgateway {
+            pushgateway::push_results(url, target, results).await?;
+        }
+    }
+    if let Some(dir) = &args.results_dir {
+        let run_dir = results::write_run(dir, args, runs)?;
+        println!("\nResults written to {}", run_dir.display());
+    }
+    Ok(())
 }
 
-#[tokio::main]
-async fn main() -> error::Result<()> {
-    dotenv().ok();
+/// Under `--output-on-error`, reports the phases that finished before `error`
+/// alongside any earlier `runs`, then hands the error back.
+async fn emit_partial_results(
+    args: &cli::Args,
+    mut runs: Vec<(String, Vec<report::PhaseResult>)>,
+    target: String,
+    results: Vec<report::PhaseResult>,
+    error: error::BenchmarkError,
+) -> error::Result<()> {
+    if args.output_on_error {
+        eprintln!("\nRun against {} failed: {}", target, error);
+        println!("\nPartial results ({} phases completed):", results.len());
+        report::print_summary(&results);
+        notices::print_summary();
+        runs.push((target, results));
+        emit_results(args, &runs).await?;
+        for (target, results) in &runs {
+            report::print_summary_lines(target, results);
+        }
+    }
+    Err(error)
+}
 
-    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+/// Under `--vacuum-between-phases`, runs `VACUUM (FULL, ANALYZE)` after the
+/// `after` phase and reports how long it took and the space it reclaimed.
+async fn vacuum_between_phases(
+    client: &Client,
+    args: &cli::Args,
+    after: &str,
+    results: &mut Vec<report::PhaseResult>,
+    timings: &mut report::PhaseTimings,
+) -> error::Result<()> {
+    if !args.vacuum_between_phases {
+        return Ok(());
+    }
+    let before = stats::total_table_bytes(client).await?;
+    let start = Instant::now();
+    schema::vacuum_full_tables(client).await?;
+    let duration = start.elapsed();
+    let after_bytes = stats::total_table_bytes(client).await?;
+    println!("\nVACUUM (FULL, ANALYZE) after {}:", after);
+    println!("  Duration: {:?}", duration);
+    println!(
+        "  Size: {:.1} MiB -> {:.1} MiB ({:.1} MiB reclaimed)",
+        before as f64 / (1024.0 * 1024.0),
+        after_bytes as f64 / (1024.0 * 1024.0),
+        (before - after_bytes) as f64 / (1024.0 * 1024.0)
+    );
+    timings.record("vacuum_full", duration);
+    results.push(report::PhaseResult::new(
+        &format!("vacuum_after_{}", after),
+        0,
+        duration,
+    ));
+    Ok(())
+}
 
-    let tls_connector = match TlsConnector::builder().build() {
-        Ok(connector) => connector,
-        Err(_) => return Err(error::BenchmarkError::TlsError()),
+/// Runs one of the benchmarks that load the in-memory dataset into scratch
+/// tables, recording its results and its time as `phase`, or skips it with a
+/// note naming it when the dataset is streamed.
+async fn run_dataset_bench(
+    name: &str,
+    phase: &str,
+    dataset: Option<&dataset::Dataset>,
+    results: &mut Vec<report::PhaseResult>,
+    timings: &mut report::PhaseTimings,
+    bench: impl AsyncFnOnce(&dataset::Dataset) -> error::Result<Vec<report::PhaseResult>>,
+) -> error::Result<()> {
+    let Some(dataset) = dataset else {
+        println!(
+            "\nSkipping the {}, it needs the in-memory dataset (no --stream)",
+            name
+        );
+        return Ok(());
     };
+    let start = Instant::now();
+    results.extend(bench(dataset).await?);
+    timings.record(phase, start.elapsed());
+    Ok(())
+}
 
-    let postgres_tls_connector = MakeTlsConnector::new(tls_connector);
-
-    let (mut client, connection) =
-        match tokio_postgres::connect(&database_url, postgres_tls_connector).await {
-            Ok((client, connection)) => (client, connection),
-            Err(e) => return Err(error::BenchmarkError::DatabaseError(e)),
-        };
-
-    // Spawn the connection future to drive the connection in the background
-    tokio::spawn(async move {
-        if let Err(e) = connection.await {
-            eprintln!("Database connection error: {}", e);
+/// Runs every benchmark phase against one database, appending to `results` as
+/// each phase completes.
+async fn run_suite(
+    client: &mut Client,
+    database_url: &str,
+    args: &cli::Args,
+    dataset: Option<&dataset::Dataset>,
+    results: &mut Vec<report::PhaseResult>,
+    timings: &mut report::PhaseTimings,
+) -> error::Result<()> {
+    if let Some(encoding) = &args.client_encoding {
+        encoding::set_client_encoding(client, encoding).await?;
+        println!("client_encoding set to {}", encoding);
+    }
+    if let Some(name) = &args.db_schema {
+        schema::use_schema(client, name, true).await?;
+        println!("Using schema {}", name);
+    }
+    if args.estimate_disk {
+        match dataset {
+            Some(dataset) => estimate::check_disk_space(client, args, dataset).await?,
+            None => println!(
+                "Skipping the disk space estimate, it needs the in-memory dataset (no --stream)"
+            ),
         }
-    });
-    // Now we can execute a simple statement that just returns its parameter.
-    let rows = client.query("SELECT $1::TEXT", &[&"hello world"]).await?;
-
-    // And then check that we got back the same string we sent over.
-    let value: &str = rows[0].get(0);
-    assert_eq!(value, "hello world");
+    }
 
     // Create tables if they don't exist
-    match schema::create_tables(&mut client).await {
+    let start = Instant::now();
+    match schema::create_tables(client, args.temp_tables, args.bigint_blocks).await {
         Ok(_) => println!("Tables created successfully"),
         Err(e) => return Err(e),
     }
+    timings.record("create_tables", start.elapsed());
 
-    let start = Instant::now();
-    // Load blocks from JSON file
-    // let prefix: &str = "../../data/S"; // remove the S to x100 the data size
-    let prefix: &str = "../../data/";
-    let blocks: Vec<models::Block> = match load_json_data(&format!("{}blocks.json", prefix)) {
-        Ok(data) => data,
-        Err(e) => {
-            eprintln!("Error loading blocks: {}", e);
-            Vec::new()
-        }
-    };
+    let server_timing = args.server_timing && stats::enable_statement_timing(client).await?;
+    let batch_size = args.batch_size.unwrap_or(bulk::DEFAULT_STREAM_BATCH_SIZE);
 
-    // Load pools from JSON file
-    let pools: Vec<models::Pool> = match load_json_data(&format!("{}pools.json", prefix)) {
-        Ok(data) => data,
-        Err(e) => {
-            eprintln!("Error loading pools: {}", e);
-            Vec::new()
+    // 1. Bulk Insert Test
+    let replica = match &args.replica_url {
+        Some(url) => {
+            let replica = db::connect(url).await?;
+            replica::check_standby(&replica).await?;
+            Some(replica)
         }
+        None => None,
     };
-
-    // Load transactions from JSON file
-    let transactions: Vec<models::Transaction> =
-        match load_json_data(&format!("{}transactions.json", prefix)) {
-            Ok(data) => data,
-            Err(e) => {
-                eprintln!("Error loading transactions: {}", e);
-                Vec::new()
+    let mut lag = replica::ReplicaLag::default();
+    let profiler = match args.flamegraph {
+        Some(_) => Some(flamegraph::Profiler::start()?),
+        None => None,
+    };
+    let wal_before = stats::wal_snapshot(client).await?;
+    let load = async {
+        let load = async {
+            match dataset {
+                Some(dataset) => bulk::run_bulk_insert(client, args, dataset).await,
+                None => bulk::run_streaming_insert(client, args).await,
             }
         };
+        match &replica {
+            Some(replica) => tokio::select! {
+                loaded = load => loaded,
+                Err(e) = replica::sample_lag(client, replica, &mut lag) => Err(e),
+            },
+            None => load.await,
+        }
+    };
+    let bulk_insert = match args.table_stats_interval {
+        Some(secs) => {
+            // Sample on the same connection between batches, racing the load
+            let path = args.table_stats_csv();
+            let mut csv = BufWriter::new(File::create(&path)?);
+            writeln!(csv, "elapsed_secs,table,bytes")?;
+            let started = Instant::now();
+            let interval = Duration::from_secs_f64(secs);
+            let bulk_insert = tokio::select! {
+                loaded = load => loaded?,
+                Err(e) = stats::sample_table_sizes(client, interval, started, &mut csv) => return Err(e),
+            };
+            stats::write_table_sizes(client, started, &mut csv).await?;
+            println!("Table sizes sampled to {}", path.display());
+            bulk_insert
+        }
+        None => load.await?,
+    };
+    timings.record("bulk_insert", bulk_insert.duration);
+    if let (Some(profiler), Some(path)) = (profiler, &args.flamegraph) {
+        profiler.write(path)?;
+    }
+    let wal_after = stats::wal_snapshot(client).await?;
+    if let (Some(before), Some(after)) = (wal_before, wal_after) {
+        stats::report_wal_delta(&before, &after, bulk_insert.records);
+    }
+    stats::report_table_sizes(client).await?;
+    let bulk_insert = bulk_insert.with_row_widths(&stats::avg_row_widths(client).await?);
+    report::print_table_throughput(&bulk_insert);
+    results.push(bulk_insert);
+    if let Some(replica) = &replica {
+        let catch_up = replica::wait_for_catch_up(client, replica).await?;
+        replica::report_lag(&lag, catch_up);
+        if let Some(duration) = catch_up {
+            results.push(report::PhaseResult::new("replica_catch_up", 0, duration));
+        }
+    }
+    if args.temp_tables {
+        println!("Note: loaded into TEMP tables, which skip WAL and use session-local buffers");
+        println!(
+            "      with no shared-buffer eviction pressure; expect different results on persistent tables"
+        );
+    }
 
-    // Load transfers from JSON file
-    let transfers: Vec<models::Transfer> =
-        match load_json_data(&format!("{}transfers.json", prefix)) {
-            Ok(data) => data,
-            Err(e) => {
-                eprintln!("Error loading transfers: {}", e);
-                Vec::new()
-            }
-        };
-    let duration = start.elapsed();
-    // Print the loaded data
-    println!("Loaded {} blocks", blocks.len());
-    println!("Loaded {} transactions", transactions.len());
-    println!("Loaded {} transfers", transfers.len());
-    println!("Loaded {} pools", pools.len());
-    println!("in {:?}", duration);
+    if let Some(expected) = &args.expect_rows {
+        println!("\nRow count assertion:");
+        integrity::check_row_counts(client, expected).await?;
+    }
 
-    // backtest
+    vacuum_between_phases(client, args, "bulk_insert", results, timings).await?;
+
+    // Refresh planner statistics so the read tests don't run against the empty-table estimates
+    if args.skip_analyze {
+        println!("\nSkipping ANALYZE (--skip-analyze), planner statistics may be stale");
+    } else {
+        let start = Instant::now();
+        schema::analyze_tables(client).await?;
+        let duration = start.elapsed();
+        println!("\nANALYZE completed in {:?}", duration);
+        timings.record("analyze", duration);
+        results.push(report::PhaseResult::new("analyze", 0, duration));
+    }
+    stats::report_stats_freshness(client).await?;
+
+    if args.check_chain {
+        let start = Instant::now();
+        let orphans = integrity::count_orphan_blocks(client).await?;
+        let duration = start.elapsed();
+        println!("\nChain integrity check:");
+        println!("  Blocks with a missing parent: {}", orphans);
+        println!("  Query duration: {:?}", duration);
+        timings.record("check_chain", duration);
+        results.push(report::PhaseResult::new("check_chain", 0, duration));
+    }
 
-    // 1. Bulk Insert Test
-    let start = Instant::now();
-    let total_records = blocks.len();
-    let mut batch_size = total_records / 100;
-    if batch_size == 0 {
-        batch_size = 1;
-    }
-    let num_batches = (total_records + batch_size - 1) / batch_size;
-
-    println!("\nStarting Bulk Insert Tests:");
-    println!("Total batches: {}", num_batches);
-    println!("Batch size: {}", batch_size);
-
-    for i in 0..num_batches {
-        let start_index = i * batch_size;
-        let end_index = std::cmp::min((i + 1) * batch_size, total_records);
-
-        // Get batches for each type
-        let block_batch = &blocks[start_index..end_index];
-        let transaction_batch =
-            &transactions[start_index..std::cmp::min(end_index, transactions.len())];
-        let transfer_batch = &transfers[start_index..std::cmp::min(end_index, transfers.len())];
-        let pool_batch = &pools[start_index..std::cmp::min(end_index, pools.len())];
-
-        // 1. Bulk insert blocks
-        let sink = client
-            .copy_in("COPY blocks (block_number, block_hash, parent_hash, block_timestamp, created_at, updated_at) FROM STDIN BINARY")
-            .await?;
-        let types = &[
-            Type::INT4,
-            Type::TEXT,
-            Type::TEXT,
-            Type::TEXT,
-            Type::TEXT,
-            Type::TEXT,
-        ];
-        let writer = BinaryCopyInWriter::new(sink, types);
-        pin_mut!(writer);
-
-        for block in block_batch {
-            writer
-                .as_mut()
-                .write(&[
-                    &block.block_number as &(dyn ToSql + Sync),
-                    &block.block_hash.as_str() as &(dyn ToSql + Sync),
-                    &block.parent_hash.as_str() as &(dyn ToSql + Sync),
-                    &block.block_timestamp.as_str() as &(dyn ToSql + Sync),
-                    &block.created_at.as_str() as &(dyn ToSql + Sync),
-                    &block.updated_at.as_str() as &(dyn ToSql + Sync),
-                ])
-                .await?;
-        }
-        writer.as_mut().finish().await?;
+    if let Some(pad_bytes) = args.toast_pad_bytes {
+        run_dataset_bench(
+            "TOAST benchmark",
+            "toast_benchmark",
+            dataset,
+            results,
+            timings,
+            async |dataset| {
+                toast::run_toast_benchmark(
+                    client,
+                    &dataset.transactions,
+                    pad_bytes,
+                    args.bigint_blocks,
+                )
+                .await
+            },
+        )
+        .await?;
+    }
 
-        // 2. Bulk insert transactions
-        let sink = client
-            .copy_in("COPY transactions (block, index, timestamp, hash, from_address, to_address, value) FROM STDIN BINARY")
-            .await?;
-        let types = &[
-            Type::INT4,
-            Type::INT4,
-            Type::TEXT,
-            Type::TEXT,
-            Type::TEXT,
-            Type::TEXT,
-            Type::TEXT,
-        ];
-        let writer = BinaryCopyInWriter::new(sink, types);
-        pin_mut!(writer);
-
-        for tx in transaction_batch {
-            writer
-                .as_mut()
-                .write(&[
-                    &tx.block as &(dyn ToSql + Sync),
-                    &tx.index as &(dyn ToSql + Sync),
-                    &tx.timestamp.as_str() as &(dyn ToSql + Sync),
-                    &tx.hash.as_str() as &(dyn ToSql + Sync),
-                    &tx.from.as_str() as &(dyn ToSql + Sync),
-                    &tx.to.as_str() as &(dyn ToSql + Sync),
-                    &tx.value.as_str() as &(dyn ToSql + Sync),
-                ])
-                .await?;
-        }
-        writer.as_mut().finish().await?;
+    if args.key_bench {
+        run_dataset_bench(
+            "surrogate key benchmark",
+            "key_benchmark",
+            dataset,
+            results,
+            timings,
+            async |dataset| {
+                variants::run_surrogate_key_benchmark(client, dataset, batch_size).await
+            },
+        )
+        .await?;
+    }
 
-        // 3. Bulk insert transfers
-        let sink = client
-            .copy_in("COPY transfers (tx_hash, block_number, token, from_address, to_address, amount) FROM STDIN BINARY")
-            .await?;
-        let types = &[
-            Type::TEXT,
-            Type::INT4,
-            Type::TEXT,
-            Type::TEXT,
-            Type::TEXT,
-            Type::TEXT,
-        ];
-        let writer = BinaryCopyInWriter::new(sink, types);
-        pin_mut!(writer);
-
-        for transfer in transfer_batch {
-            writer
-                .as_mut()
-                .write(&[
-                    &transfer.tx_hash.as_str() as &(dyn ToSql + Sync),
-                    &transfer.block_number as &(dyn ToSql + Sync),
-                    &transfer.token.as_str() as &(dyn ToSql + Sync),
-                    &transfer.from.as_str() as &(dyn ToSql + Sync),
-                    &transfer.to.as_str() as &(dyn ToSql + Sync),
-                    &transfer.amount.as_str() as &(dyn ToSql + Sync),
-                ])
-                .await?;
+    if args.reverse {
+        run_dataset_bench(
+            "insert order benchmark",
+            "insert_order_benchmark",
+            dataset,
+            results,
+            timings,
+            async |dataset| {
+                variants::run_insert_order_benchmark(client, &dataset.blocks, batch_size).await
+            },
+        )
+        .await?;
+    }
+
+    if args.deferred_fk_bench {
+        run_dataset_bench(
+            "deferred foreign key benchmark",
+            "deferred_fk_benchmark",
+            dataset,
+            results,
+            timings,
+            async |dataset| variants::run_deferred_fk_benchmark(client, dataset, batch_size).await,
+        )
+        .await?;
+    }
+
+    if args.copy_freeze {
+        run_dataset_bench(
+            "COPY FREEZE benchmark",
+            "copy_freeze_benchmark",
+            dataset,
+            results,
+            timings,
+            async |dataset| variants::run_copy_freeze_benchmark(client, dataset, batch_size).await,
+        )
+        .await?;
+    }
+
+    if args.send_buffer_bench {
+        run_dataset_bench(
+            "send buffer benchmark",
+            "send_buffer_benchmark",
+            dataset,
+            results,
+            timings,
+            async |dataset| {
+                sendbuf::run_send_buffer_benchmark(database_url, &dataset.transactions, batch_size)
+                    .await
+            },
+        )
+        .await?;
+    }
+
+    if args.encoding_bench {
+        run_dataset_bench(
+            "client encoding benchmark",
+            "encoding_benchmark",
+            dataset,
+            results,
+            timings,
+            async |dataset| {
+                encoding::run_encoding_benchmark(client, &dataset.transfers, batch_size).await
+            },
+        )
+        .await?;
+    }
+
+    if args.array_bench {
+        run_dataset_bench(
+            "array column benchmark",
+            "array_column_benchmark",
+            dataset,
+            results,
+            timings,
+            async |dataset| {
+                arrays::run_array_column_benchmark(client, &dataset.transfers, batch_size).await
+            },
+        )
+        .await?;
+    }
+
+    if args.timestamp_bench {
+        run_dataset_bench(
+            "timestamp benchmark",
+            "timestamp_benchmark",
+            dataset,
+            results,
+            timings,
+            async |dataset| {
+                timestamps::run_timestamp_benchmark(
+                    client,
+                    &dataset.transactions,
+                    args.timestamp_format.as_deref(),
+                    batch_size,
+                )
+                .await
+            },
+        )
+        .await?;
+    }
+
+    if let Some(numeric_scale) = args.numeric_scale {
+        run_dataset_bench(
+            "NUMERIC amount benchmark",
+            "numeric_amount_benchmark",
+            dataset,
+            results,
+            timings,
+            async |dataset| {
+                amounts::run_numeric_amount_benchmark(
+                    client,
+                    &dataset.transfers,
+                    numeric_scale,
+                    args.strict_numeric_scale,
+                    batch_size,
+                )
+                .await
+            },
+        )
+        .await?;
+    }
+
+    if let Some(updates) = args.index_bloat_bench {
+        run_dataset_bench(
+            "index bloat benchmark",
+            "index_bloat_benchmark",
+            dataset,
+            results,
+            timings,
+            async |dataset| {
+                bloat::run_index_bloat_benchmark(client, &dataset.transactions, updates, batch_size)
+                    .await
+            },
+        )
+        .await?;
+    }
+
+    if args.upsert_bench {
+        run_dataset_bench(
+            "upsert benchmark",
+            "upsert_update_benchmark",
+            dataset,
+            results,
+            timings,
+            async |dataset| {
+                variants::run_upsert_update_benchmark(
+                    client,
+                    &dataset.blocks,
+                    args.conflict_ratio(),
+                    batch_size,
+                )
+                .await
+            },
+        )
+        .await?;
+    }
+
+    if args.merge_bench {
+        run_dataset_bench(
+            "MERGE benchmark",
+            "merge_benchmark",
+            dataset,
+            results,
+            timings,
+            async |dataset| {
+                variants::run_merge_benchmark(
+                    client,
+                    &dataset.blocks,
+                    args.conflict_ratio(),
+                    batch_size,
+                )
+                .await
+            },
+        )
+        .await?;
+    }
+
+    if args.generated_column_bench {
+        run_dataset_bench(
+            "generated column benchmark",
+            "generated_column_benchmark",
+            dataset,
+            results,
+            timings,
+            async |dataset| {
+                variants::run_generated_column_benchmark(client, dataset, batch_size).await
+            },
+        )
+        .await?;
+    }
+
+    if args.compression_bench {
+        run_dataset_bench(
+            "column compression benchmark",
+            "compression_benchmark",
+            dataset,
+            results,
+            timings,
+            async |dataset| {
+                compression::run_compression_benchmark(client, dataset, batch_size).await
+            },
+        )
+        .await?;
+    }
+
+    if args.copy_abort_test {
+        run_dataset_bench(
+            "COPY abort test",
+            "copy_abort_test",
+            dataset,
+            results,
+            timings,
+            async |dataset| {
+                copyabort::run_copy_abort_test(client, &dataset.transactions, batch_size).await
+            },
+        )
+        .await?;
+    }
+
+    if args.in_list_bench {
+        run_dataset_bench(
+            "IN-list benchmark",
+            "in_list_benchmark",
+            dataset,
+            results,
+            timings,
+            async |dataset| {
+                inlist::run_in_list_benchmark(client, &dataset.transactions, batch_size).await
+            },
+        )
+        .await?;
+    }
+
+    if args.scale_sweep {
+        run_dataset_bench(
+            "scaling sweep",
+            "scale_sweep",
+            dataset,
+            results,
+            timings,
+            async |dataset| {
+                scaling::run_scale_sweep(client, &dataset.transactions, batch_size).await
+            },
+        )
+        .await?;
+    }
+
+    if let Some(connections) = args.parallel_copy {
+        run_dataset_bench(
+            "parallel COPY benchmark",
+            "parallel_copy_benchmark",
+            dataset,
+            results,
+            timings,
+            async |dataset| {
+                parallel::run_parallel_copy_benchmark(
+                    client,
+                    database_url,
+                    &dataset.transactions,
+                    connections,
+                    args.connection_jitter(),
+                    batch_size,
+                )
+                .await
+            },
+        )
+        .await?;
+    }
+
+    if args.partial_index_bench {
+        run_dataset_bench(
+            "partial index benchmark",
+            "partial_index_benchmark",
+            dataset,
+            results,
+            timings,
+            async |dataset| {
+                partial::run_partial_index_benchmark(client, &dataset.transactions, batch_size)
+                    .await
+            },
+        )
+        .await?;
+    }
+
+    if args.prefix_search_bench {
+        run_dataset_bench(
+            "prefix search benchmark",
+            "prefix_search_benchmark",
+            dataset,
+            results,
+            timings,
+            async |dataset| {
+                prefix::run_prefix_search_benchmark(client, &dataset.transactions, batch_size).await
+            },
+        )
+        .await?;
+    }
+
+    if args.brin_bench {
+        run_dataset_bench(
+            "BRIN benchmark",
+            "brin_benchmark",
+            dataset,
+            results,
+            timings,
+            async |dataset| {
+                brin::run_brin_benchmark(client, &dataset.transactions, batch_size).await
+            },
+        )
+        .await?;
+    }
+
+    if args.pk_after_load_bench {
+        run_dataset_bench(
+            "primary key timing benchmark",
+            "pk_after_load_benchmark",
+            dataset,
+            results,
+            timings,
+            async |dataset| {
+                pkload::run_pk_after_load_benchmark(client, &dataset.transactions, batch_size).await
+            },
+        )
+        .await?;
+    }
+
+    if args.matview_bench {
+        run_dataset_bench(
+            "materialized view benchmark",
+            "matview_benchmark",
+            dataset,
+            results,
+            timings,
+            async |dataset| {
+                matview::run_matview_benchmark(client, &dataset.transfers, batch_size).await
+            },
+        )
+        .await?;
+    }
+
+    if args.wal_level_bench {
+        run_dataset_bench(
+            "WAL level benchmark",
+            "wal_level_benchmark",
+            dataset,
+            results,
+            timings,
+            async |dataset| {
+                wallevel::run_wal_level_benchmark(client, &dataset.transactions, batch_size).await
+            },
+        )
+        .await?;
+    }
+
+    if args.trigger_bench {
+        run_dataset_bench(
+            "trigger benchmark",
+            "trigger_benchmark",
+            dataset,
+            results,
+            timings,
+            async |dataset| {
+                triggers::run_trigger_benchmark(client, &dataset.transactions, batch_size).await
+            },
+        )
+        .await?;
+    }
+
+    // 2. Single Record Insert Test
+    // Opt-in, since it adds rows to the loaded transactions table
+    if let Some(single_rows) = args.single_insert_rows.filter(|&rows| rows > 0) {
+        match dataset {
+            Some(dataset) => {
+                let rows = &dataset.transactions[..single_rows.min(dataset.transactions.len())];
+                let single_insert = single::run_single_insert(client, args, rows).await?;
+                timings.record("single_insert", single_insert.duration);
+                results.push(single_insert);
+                vacuum_between_phases(client, args, "single_insert", results, timings).await?;
+            }
+            None => println!(
+                "\nSkipping the single record insert test, it needs the in-memory dataset (no --stream)"
+            ),
         }
-        writer.as_mut().finish().await?;
+    }
 
-        // 4. Bulk insert pools
-        let sink = client
-            .copy_in("COPY pools (deployer, address, quote_token, token, init_block, created_at) FROM STDIN BINARY")
+    if args.returning_bench {
+        let single_rows = args.single_insert_rows();
+        if single_rows == 0 {
+            println!("\nSkipping the RETURNING benchmark, --single-insert-rows is 0");
+        } else {
+            run_dataset_bench(
+                "RETURNING benchmark",
+                "returning_benchmark",
+                dataset,
+                results,
+                timings,
+                async |dataset| {
+                    let rows = &dataset.transactions[..single_rows.min(dataset.transactions.len())];
+                    single::run_returning_benchmark(client, rows).await
+                },
+            )
             .await?;
-        let types = &[
-            Type::TEXT,
-            Type::TEXT,
-            Type::TEXT,
-            Type::TEXT,
-            Type::INT4,
-            Type::INT8,
-        ];
-        let writer = BinaryCopyInWriter::new(sink, types);
-        pin_mut!(writer);
-
-        for pool in pool_batch {
-            writer
-                .as_mut()
-                .write(&[
-                    &pool.deployer.as_str() as &(dyn ToSql + Sync),
-                    &pool.address.as_str() as &(dyn ToSql + Sync),
-                    &pool.quote_token.as_str() as &(dyn ToSql + Sync),
-                    &pool.token.as_str() as &(dyn ToSql + Sync),
-                    &pool.init_block as &(dyn ToSql + Sync),
-                    &pool.created_at as &(dyn ToSql + Sync),
-                ])
-                .await?;
         }
-        writer.as_mut().finish().await?;
+    }
 
-        if i % 10 == 0 || i == num_batches - 1 {
-            println!("Processed batch {}/{}", i + 1, num_batches);
-        }
+    if args.multirow_bench {
+        run_dataset_bench(
+            "multi-row insert benchmark",
+            "multirow_insert",
+            dataset,
+            results,
+            timings,
+            async |dataset| {
+                let mut runs = Vec::new();
+                for rows_per_stmt in args.rows_per_stmt() {
+                    runs.push(
+                        variants::run_multirow_insert(client, &dataset.transactions, rows_per_stmt)
+                            .await?,
+                    );
+                }
+                Ok(runs)
+            },
+        )
+        .await?;
     }
 
-    let bulk_insert_duration = start.elapsed();
-    println!("\nBulk Insert Test Results:");
-    println!("-------------------------");
-    println!("Total records processed:");
-    println!("  Blocks: {}", blocks.len());
-    println!("  Transactions: {}", transactions.len());
-    println!("  Transfers: {}", transfers.len());
-    println!("  Pools: {}", pools.len());
-    println!("Total duration: {:?}", bulk_insert_duration);
-    println!(
-        "Average insertion rate: {} records/sec",
-        (blocks.len() + transactions.len() + transfers.len() + pools.len()) as f64
-            / bulk_insert_duration.as_secs_f64()
-    );
+    if args.cold_cache {
+        let start = Instant::now();
+        results.extend(reads::run_cold_cache_benchmark(client).await?);
+        timings.record("cold_cache_benchmark", start.elapsed());
+    }
 
-    // 2. Single Record Insert Test
-    // ...
+    if let Some(readers) = args.mixed_readers {
+        run_dataset_bench(
+            "mixed workload benchmark",
+            "mixed_workload",
+            dataset,
+            results,
+            timings,
+            async |dataset| {
+                mixed::run_mixed_workload(
+                    client,
+                    database_url,
+                    &dataset.transactions,
+                    readers,
+                    args.connection_jitter(),
+                    batch_size,
+                )
+                .await
+            },
+        )
+        .await?;
+    }
+
+    if let Some(page_size) = args.pagination_page_size {
+        let start = Instant::now();
+        results.extend(reads::run_pagination_benchmark(client, page_size).await?);
+        timings.record("pagination_benchmark", start.elapsed());
+    }
+
+    if let Some(n) = args.pk_lookups {
+        let start = Instant::now();
+        results.extend(reads::run_pk_lookup_benchmark(client, n).await?);
+        timings.record("pk_lookup_benchmark", start.elapsed());
+    }
+
+    if let Some(settings) = &args.work_mem_sweep {
+        let start = Instant::now();
+        results.extend(reads::run_work_mem_sweep(client, settings).await?);
+        timings.record("work_mem_sweep", start.elapsed());
+    }
 
     // 3. Read-Write Mixed Workload Test
     // ...
@@ -287,5 +846,174 @@ async fn main() -> error::Result<()> {
     // 4. Time-Range Query Test
     // ...
 
+    if server_timing {
+        stats::report_statement_timing(client).await?;
+    }
+
+    Ok(())
+}
+
+/// Prints a failed run's error by its message rather than returning it from
+/// `main`, which would print its `Debug` form.
+#[tokio::main]
+async fn main() -> ExitCode {
+    match run().await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn run() -> error::Result<()> {
+    let timings = report::PhaseTimings::start();
+    dotenv().ok();
+    let args = cli::Args::load()?;
+    logging::init(args.log_format, args.profile);
+    if args.list_benchmarks {
+        catalog::print_benchmarks();
+        return Ok(());
+    }
+    if args.status {
+        return status::print_status(&args).await;
+    }
+    if let Some(table) = &args.json_schema {
+        input_schema::print_json_schema(table)?;
+        return Ok(());
+    }
+    if let Some(runs) = &args.diff_results {
+        results::print_diff(&runs[0], &runs[1])?;
+        return Ok(());
+    }
+    if args.drop {
+        let client = db::connect(&args.database_url()?).await?;
+        return schema::drop_tables(&client, args.db_schema.as_deref()).await;
+    }
+
+    println!("Run ID: {}", results::run_id());
+    let span = tracing::info_span!("run", run_id = results::run_id());
+    run_benchmarks(args, timings).instrument(span).await
+}
+
+/// Runs the selected benchmarks, within the `run` span carrying the run id.
+async fn run_benchmarks(
+    mut args: cli::Args,
+    mut timings: report::PhaseTimings,
+) -> error::Result<()> {
+    if args.copy_microbench {
+        let database_url = args.database_url()?;
+        let client = db::connect(&database_url).await?;
+        let start = Instant::now();
+        let results = microbench::run_copy_microbench(&client, args.microbench_rows()).await?;
+        timings.record("copy_microbench", start.elapsed());
+        let runs = [(db::target_label(&database_url)?, results)];
+        emit_results(&args, &runs).await?;
+        timings.print_breakdown();
+        let [(target, results)] = &runs;
+        report::print_summary_lines(target, results);
+        return Ok(());
+    }
+
+    if let Some(n) = args.connect_bench {
+        let database_url = args.database_url()?;
+        let start = Instant::now();
+        let results = connbench::run_connect_benchmark(&database_url, n, args.no_tls).await?;
+        timings.record("connect_bench", start.elapsed());
+        report::print_summary(&results);
+        timings.print_breakdown();
+        report::print_summary_lines(&db::target_label(&database_url)?, &results);
+        return Ok(());
+    }
+
+    dataset::skip_missing_tables(&mut args)?;
+    if args.check_block_range {
+        validate::check_block_ranges(&args)?;
+    }
+    if args.validate && args.stream {
+        println!("Skipping --validate, it needs the in-memory dataset (no --stream)");
+    }
+
+    // Streaming mode reads the files during the load instead
+    let dataset = if args.stream {
+        None
+    } else {
+        let start = Instant::now();
+        let dataset = dataset::Dataset::load(&args)?;
+        let duration = start.elapsed();
+        // Print the loaded data
+        for (table, len) in schema::TABLES.into_iter().zip(dataset.table_lens()) {
+            if args.includes_table(table) {
+                println!("Loaded {} {}", len, table);
+            }
+        }
+        println!("in {:?}", duration);
+        timings.record("load_json", duration);
+        if args.validate {
+            validate::check_empty_fields(&dataset, args.strict)?;
+        }
+        Some(dataset)
+    };
+
+    // Run the identical suite against each target and line the results up
+    if !args.compare.is_empty() {
+        let mut runs = Vec::new();
+        for database_url in &args.compare {
+            let target = db::target_label(database_url)?;
+            println!("\n=== {} ===", target);
+            let start = Instant::now();
+            let mut client =
+                db::connect_with_send_buffer(database_url, args.send_buffer_size).await?;
+            timings.record("connect", start.elapsed());
+            let mut results = Vec::new();
+            let outcome = run_until_signal(
+                &mut client,
+                database_url,
+                &args,
+                dataset.as_ref(),
+                &mut results,
+                &mut timings,
+            )
+            .await;
+            if let Err(e) = outcome {
+                return emit_partial_results(&args, runs, target, results, e).await;
+            }
+            runs.push((target, results));
+        }
+        report::print_comparison(&runs);
+        emit_results(&args, &runs).await?;
+        notices::print_summary();
+        timings.print_breakdown();
+        for (target, results) in &runs {
+            report::print_summary_lines(target, results);
+        }
+        return Ok(());
+    }
+
+    let database_url = args.database_url()?;
+    let target = db::target_label(&database_url)?;
+    let start = Instant::now();
+    let mut client = db::connect_with_send_buffer(&database_url, args.send_buffer_size).await?;
+    timings.record("connect", start.elapsed());
+    let mut results = Vec::new();
+    let outcome = run_until_signal(
+        &mut client,
+        &database_url,
+        &args,
+        dataset.as_ref(),
+        &mut results,
+        &mut timings,
+    )
+    .await;
+    if let Err(e) = outcome {
+        return emit_partial_results(&args, Vec::new(), target, results, e).await;
+    }
+    let runs = [(target, results)];
+    emit_results(&args, &runs).await?;
+    notices::print_summary();
+    timings.print_breakdown();
+    let [(target, results)] = &runs;
+    report::print_summary_lines(target, results);
+
     Ok(())
 }
@@ -0,0 +1,130 @@
+use crate::arrays::plain_transfers_ddl;
+use crate::bulk::copy_all;
+use crate::error::Result;
+use crate::models::Transfer;
+use crate::report::{self, PhaseResult};
+use crate::schema;
+use rand::seq::SliceRandom;
+use std::time::{Duration, Instant};
+use tokio_postgres::Client;
+
+const SCHEMA: &str = "matview_bench";
+
+/// Token lookups timed against the view and the base table each
+const LOOKUPS: usize = 500;
+
+/// A per-token aggregate over the transfers, left unpopulated so the first
+/// refresh is timed too. The unique index lets it refresh concurrently.
+const MATVIEW_DDL: &str = "
+    CREATE MATERIALIZED VIEW token_totals AS
+        SELECT token, COUNT(*) AS transfers, SUM(amount::NUMERIC) AS volume
+        FROM transfers GROUP BY token
+        WITH NO DATA;
+    CREATE UNIQUE INDEX ON token_totals (token);";
+
+/// Models serving a dashboard from a precomputed aggregate: loads the
+/// transfers into a scratch table, times populating a materialized view of
+/// transfer count and volume per token, then a `REFRESH ... CONCURRENTLY`,
+/// and compares per-token reads from the view against aggregating the base
+/// table on every read.
+#[tracing::instrument(name = "phase", skip_all, fields(phase = "matview_benchmark"))]
+pub async fn run_matview_benchmark(
+    client: &Client,
+    transfers: &[Transfer],
+    batch_size: usize,
+) -> Result<Vec<PhaseResult>> {
+    if transfers.is_empty() {
+        println!("\nSkipping the materialized view benchmark, there are no transfers");
+        return Ok(Vec::new());
+    }
+    let mut rng = rand::thread_rng();
+    let tokens: Vec<&str> = (0..LOOKUPS)
+        .filter_map(|_| transfers.choose(&mut rng))
+        .map(|t| t.token.as_str())
+        .collect();
+
+    let previous =
+        schema::enter_scratch_schema(client, SCHEMA, &(plain_transfers_ddl() + MATVIEW_DDL))
+            .await?;
+    copy_all(client, transfers, batch_size).await?;
+    client.execute("ANALYZE transfers", &[]).await?;
+
+    let start = Instant::now();
+    client
+        .batch_execute("REFRESH MATERIALIZED VIEW token_totals")
+        .await?;
+    let refresh = start.elapsed();
+    let start = Instant::now();
+    client
+        .batch_execute("REFRESH MATERIALIZED VIEW CONCURRENTLY token_totals")
+        .await?;
+    let refresh_concurrently = start.elapsed();
+    let rows: i64 = client
+        .query_one("SELECT COUNT(*) FROM token_totals", &[])
+        .await?
+        .get(0);
+
+    let view = time_lookups(
+        client,
+        "SELECT transfers, volume FROM token_totals WHERE token = $1",
+        &tokens,
+    )
+    .await?;
+    let base = time_lookups(
+        client,
+        "SELECT COUNT(*), SUM(amount::NUMERIC) FROM transfers WHERE token = $1",
+        &tokens,
+    )
+    .await?;
+    schema::leave_scratch_schema(client, SCHEMA, &previous).await?;
+
+    println!(
+        "\nMaterialized View Benchmark ({} transfers, {} tokens, {} lookups):",
+        transfers.len(),
+        rows,
+        LOOKUPS
+    );
+    println!("-------------------------");
+    println!("  REFRESH               {:>10.2?}", refresh);
+    println!("  REFRESH CONCURRENTLY  {:>10.2?}", refresh_concurrently);
+    let mut results = vec![
+        PhaseResult::new("matview_refresh", transfers.len(), refresh),
+        PhaseResult::new(
+            "matview_refresh_concurrently",
+            transfers.len(),
+            refresh_concurrently,
+        ),
+    ];
+    for (label, mut latencies) in [("view", view), ("base_table", base)] {
+        let duration: Duration = latencies.iter().sum();
+        let mean = duration / latencies.len().max(1) as u32;
+        let p99 = report::percentile(&mut latencies, 99.0);
+        println!(
+            "  read {:<16} mean {:>9.2?}  p99 {:>9.2?}",
+            label,
+            mean,
+            p99.unwrap_or_default()
+        );
+        results.push(
+            PhaseResult::new(
+                &format!("matview_read_{}", label),
+                latencies.len(),
+                duration,
+            )
+            .with_p99(p99),
+        );
+    }
+    Ok(results)
+}
+
+/// Times `query` once per token, its only parameter.
+async fn time_lookups(client: &Client, query: &str, tokens: &[&str]) -> Result<Vec<Duration>> {
+    let statement = client.prepare(query).await?;
+    let mut latencies = Vec::with_capacity(tokens.len());
+    for token in tokens {
+        let start = Instant::now();
+        client.query(&statement, &[token]).await?;
+        latencies.push(start.elapsed());
+    }
+    Ok(latencies)
+}
@@ -0,0 +1,103 @@
+use crate::error::Result;
+use crate::report::PhaseResult;
+use chrono::{DateTime, Duration as ChronoDuration, TimeZone, Utc};
+use futures::pin_mut;
+use postgres::types::ToSql;
+use rand::Rng;
+use rust_decimal::Decimal;
+use std::time::{Duration, Instant};
+use tokio_postgres::binary_copy::BinaryCopyInWriter;
+use tokio_postgres::types::Type;
+use tokio_postgres::Client;
+
+/// COPYs the same number of rows into single-column INT4, TEXT, TIMESTAMPTZ and
+/// NUMERIC tables and reports the throughput of each column type, returning a
+/// `copy_microbench_<type>` phase per type.
+#[tracing::instrument(name = "phase", skip_all, fields(phase = "copy_microbench"))]
+pub async fn run_copy_microbench(client: &Client, rows: usize) -> Result<Vec<PhaseResult>> {
+    let mut rng = rand::thread_rng();
+    let start_time = Utc.with_ymd_and_hms(2023, 1, 1, 0, 0, 0).unwrap();
+
+    // Values are generated up front so only the COPY itself is timed
+    let ints: Vec<i32> = (0..rows).map(|i| i as i32).collect();
+    let texts: Vec<String> = (0..rows)
+        .map(|_| rng.gen_range(0..=1_000_000_000_000_000_000u64).to_string())
+        .collect();
+    let timestamps: Vec<DateTime<Utc>> = (0..rows)
+        .map(|i| start_time + ChronoDuration::seconds(i as i64 * 15))
+        .collect();
+    let numerics: Vec<Decimal> = (0..rows)
+        .map(|_| Decimal::from(rng.gen_range(0..=1_000_000_000_000_000_000u64)))
+        .collect();
+
+    println!(
+        "\nCOPY Column Type Microbenchmark ({} rows per type):",
+        rows
+    );
+    println!("-------------------------------------------------");
+    let results = [
+        (
+            "INT4",
+            copy_column(client, "INT4", Type::INT4, &ints).await?,
+        ),
+        (
+            "TEXT",
+            copy_column(client, "TEXT", Type::TEXT, &texts).await?,
+        ),
+        (
+            "TIMESTAMPTZ",
+            copy_column(client, "TIMESTAMPTZ", Type::TIMESTAMPTZ, &timestamps).await?,
+        ),
+        (
+            "NUMERIC",
+            copy_column(client, "NUMERIC", Type::NUMERIC, &numerics).await?,
+        ),
+    ];
+    let mut phases = Vec::with_capacity(results.len());
+    for (name, duration) in results {
+        println!(
+            "  {:<12} {:>12?}  {:.0} rows/sec",
+            name,
+            duration,
+            rows as f64 / duration.as_secs_f64()
+        );
+        phases.push(PhaseResult::new(
+            &format!("copy_microbench_{}", name.to_lowercase()),
+            rows,
+            duration,
+        ));
+    }
+    Ok(phases)
+}
+
+async fn copy_column<T>(client: &Client, sql_type: &str, ty: Type, values: &[T]) -> Result<Duration>
+where
+    T: ToSql + Sync,
+{
+    let table = format!("copy_bench_{}", sql_type.to_lowercase());
+    client
+        .batch_execute(&format!(
+            "DROP TABLE IF EXISTS {table}; CREATE TABLE {table} (v {sql_type} NOT NULL)"
+        ))
+        .await?;
+
+    let start = Instant::now();
+    let sink = client
+        .copy_in(&format!("COPY {} (v) FROM STDIN BINARY", table))
+        .await?;
+    let writer = BinaryCopyInWriter::new(sink, &[ty]);
+    pin_mut!(writer);
+    for value in values {
+        writer
+            .as_mut()
+            .write(&[value as &(dyn ToSql + Sync)])
+            .await?;
+    }
+    writer.as_mut().finish().await?;
+    let duration = start.elapsed();
+
+    client
+        .execute(&format!("DROP TABLE {}", table), &[])
+        .await?;
+    Ok(duration)
+}
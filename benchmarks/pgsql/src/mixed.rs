@@ -0,0 +1,178 @@
+use crate::bulk::copy_all;
+use crate::db;
+use crate::error::Result;
+use crate::models::{BlockHeight, Transaction};
+use crate::report::{self, PhaseResult};
+use crate::schema;
+use crate::variants::plain_transactions_ddl;
+use futures::future::{try_join, try_join_all};
+use rand::Rng;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use tokio_postgres::Client;
+
+/// Scratch schema the mixed workload writes into; readers name it explicitly
+/// since only the writer's search_path points at it
+const SCHEMA: &str = "mixed_bench";
+
+const BLOCK_INDEX_DDL: &str = "
+    CREATE INDEX ON transactions (block);";
+
+/// Blocks covered by each range query, as in the `block_range` read query
+const RANGE_BLOCKS: i64 = 100;
+
+/// Queries each reader runs against the idle table for the baseline
+const BASELINE_QUERIES: usize = 200;
+
+/// Preloads half of `transactions` into a scratch table, times block-range
+/// queries from `readers` extra connections against it while idle, then again
+/// while this connection COPYs the other half, and reports how much the
+/// concurrent write load degrades read latency. Readers connect at once, or
+/// staggered within `jitter`.
+///
+/// Readers only query block ranges present before the write starts, so both
+/// runs read the same rows.
+#[tracing::instrument(name = "phase", skip_all, fields(phase = "mixed_workload"))]
+pub async fn run_mixed_workload(
+    client: &Client,
+    database_url: &str,
+    transactions: &[Transaction],
+    readers: usize,
+    jitter: Duration,
+    batch_size: usize,
+) -> Result<Vec<PhaseResult>> {
+    let (preload, load) = transactions.split_at(transactions.len() / 2);
+    let Some((low, high)) = block_span(preload) else {
+        println!("\nSkipping the mixed workload benchmark, it needs at least two transactions");
+        return Ok(Vec::new());
+    };
+
+    let previous = schema::enter_scratch_schema(
+        client,
+        SCHEMA,
+        &(plain_transactions_ddl() + BLOCK_INDEX_DDL),
+    )
+    .await?;
+    copy_all(client, preload, batch_size).await?;
+    client.execute("ANALYZE transactions", &[]).await?;
+
+    let reader_clients = db::connect_workers(database_url, readers, jitter).await?;
+
+    let baseline = try_join_all(
+        reader_clients
+            .iter()
+            .map(|reader| read_ranges(reader, low, high, Some(BASELINE_QUERIES), None)),
+    )
+    .await?;
+    let baseline_duration = baseline.iter().map(|(_, d)| *d).max().unwrap_or_default();
+    let mut baseline: Vec<Duration> = baseline.into_iter().flat_map(|(l, _)| l).collect();
+
+    let done = AtomicBool::new(false);
+    let write = async {
+        let duration = copy_all(client, load, batch_size).await;
+        done.store(true, Ordering::Relaxed);
+        duration
+    };
+    let reads = try_join_all(
+        reader_clients
+            .iter()
+            .map(|reader| read_ranges(reader, low, high, None, Some(&done))),
+    );
+    let (write_duration, loaded) = try_join(write, reads).await?;
+    let loaded_duration = loaded.iter().map(|(_, d)| *d).max().unwrap_or_default();
+    let mut loaded: Vec<Duration> = loaded.into_iter().flat_map(|(l, _)| l).collect();
+    schema::leave_scratch_schema(client, SCHEMA, &previous).await?;
+
+    let idle = summarize("mixed_read_idle", &mut baseline, baseline_duration);
+    let under_load = summarize("mixed_read_under_load", &mut loaded, loaded_duration);
+    let write = PhaseResult::new("mixed_write", load.len(), write_duration);
+
+    println!(
+        "\nMixed Read/Write Workload ({} readers, {} transactions written):",
+        readers,
+        load.len()
+    );
+    println!("-------------------------");
+    println!(
+        "  write      {:>12.2?}  {:>10.0} rows/sec",
+        write_duration,
+        write.records_per_sec()
+    );
+    for (label, summary) in [("idle", &idle), ("under load", &under_load)] {
+        println!(
+            "  {:<10} {:>6} queries  mean {:>9.2?}  p50 {:>9.2?}  p99 {:>9.2?}",
+            label, summary.queries, summary.mean, summary.p50, summary.p99
+        );
+    }
+    println!(
+        "  Read latency under load: mean {:.1}x, p99 {:.1}x the idle baseline",
+        ratio(under_load.mean, idle.mean),
+        ratio(under_load.p99, idle.p99)
+    );
+
+    Ok(vec![idle.result, under_load.result, write])
+}
+
+/// Lowest and highest block height in `rows`, if they span a range at all.
+fn block_span(rows: &[Transaction]) -> Option<(i64, i64)> {
+    let low = rows.iter().map(|t| t.block.0).min()?;
+    let high = rows.iter().map(|t| t.block.0).max()?;
+    (rows.len() > 1).then_some((low, high))
+}
+
+/// Runs random block-range queries within `low..=high`, either `count` of them
+/// or until `done` is set, returning each query's latency and the time taken.
+async fn read_ranges(
+    client: &Client,
+    low: i64,
+    high: i64,
+    count: Option<usize>,
+    done: Option<&AtomicBool>,
+) -> Result<(Vec<Duration>, Duration)> {
+    let statement = client
+        .prepare(&format!(
+            "SELECT COUNT(*), SUM(length(value)) FROM {SCHEMA}.transactions
+             WHERE block BETWEEN $1 AND $1 + {RANGE_BLOCKS}"
+        ))
+        .await?;
+    let mut latencies = Vec::new();
+    let start = Instant::now();
+    loop {
+        if count.is_some_and(|count| latencies.len() >= count)
+            || done.is_some_and(|done| done.load(Ordering::Relaxed))
+        {
+            break;
+        }
+        let from = BlockHeight(rand::thread_rng().gen_range(low..=high));
+        let query_start = Instant::now();
+        client.query_one(&statement, &[&from]).await?;
+        latencies.push(query_start.elapsed());
+    }
+    Ok((latencies, start.elapsed()))
+}
+
+struct ReadSummary {
+    queries: usize,
+    mean: Duration,
+    p50: Duration,
+    p99: Duration,
+    result: PhaseResult,
+}
+
+fn summarize(phase: &str, latencies: &mut [Duration], duration: Duration) -> ReadSummary {
+    let queries = latencies.len();
+    let mean = latencies.iter().sum::<Duration>() / queries.max(1) as u32;
+    let p50 = report::percentile(latencies, 50.0).unwrap_or_default();
+    let p99 = report::percentile(latencies, 99.0);
+    ReadSummary {
+        queries,
+        mean,
+        p50,
+        p99: p99.unwrap_or_default(),
+        result: PhaseResult::new(phase, queries, duration).with_p99(p99),
+    }
+}
+
+fn ratio(loaded: Duration, idle: Duration) -> f64 {
+    loaded.as_secs_f64() / idle.as_secs_f64()
+}
@@ -1,9 +1,44 @@
-use chrono::{DateTime, Utc};
+use bytes::BytesMut;
 use serde::{Deserialize, Serialize};
+use std::error::Error;
+use tokio_postgres::types::{to_sql_checked, IsNull, ToSql, Type};
+
+/// A block number, bound as whichever of INTEGER or BIGINT the target column
+/// is (see `--bigint-blocks`). Binding a height past `i32::MAX` to an INTEGER
+/// column fails instead of wrapping.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct BlockHeight(pub i64);
+
+impl ToSql for BlockHeight {
+    fn to_sql(
+        &self,
+        ty: &Type,
+        out: &mut BytesMut,
+    ) -> Result<IsNull, Box<dyn Error + Sync + Send>> {
+        if *ty == Type::INT4 {
+            let height = i32::try_from(self.0).map_err(|_| {
+                format!(
+                    "block height {} doesn't fit an INTEGER column, use --bigint-blocks",
+                    self.0
+                )
+            })?;
+            height.to_sql(ty, out)
+        } else {
+            self.0.to_sql(ty, out)
+        }
+    }
+
+    fn accepts(ty: &Type) -> bool {
+        matches!(*ty, Type::INT4 | Type::INT8)
+    }
+
+    to_sql_checked!();
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Block {
-    pub block_number: i32,
+    pub block_number: BlockHeight,
     pub block_hash: String,
     pub parent_hash: String,
     pub block_timestamp: String,
@@ -13,7 +48,7 @@ pub struct Block {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Transaction {
-    pub block: i32,
+    pub block: BlockHeight,
     pub index: i32,
     pub timestamp: String,
     pub hash: String,
@@ -25,7 +60,7 @@ pub struct Transaction {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Transfer {
     pub tx_hash: String,
-    pub block_number: i32,
+    pub block_number: BlockHeight,
     pub token: String,
     pub from: String,
     pub to: String,
@@ -38,6 +73,6 @@ pub struct Pool {
     pub address: String,
     pub quote_token: String,
     pub token: String,
-    pub init_block: i32,
+    pub init_block: BlockHeight,
     pub created_at: i64,
 }
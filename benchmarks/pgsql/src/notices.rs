@@ -0,0 +1,44 @@
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use tokio_postgres::error::DbError;
+
+/// Distinct messages kept per severity as samples
+const SAMPLES: usize = 3;
+
+/// NOTICE, WARNING and other non-error messages the server sent on any
+/// connection, by severity.
+static NOTICES: Mutex<BTreeMap<String, Severity>> = Mutex::new(BTreeMap::new());
+
+#[derive(Default)]
+struct Severity {
+    count: usize,
+    samples: Vec<String>,
+}
+
+/// Records one notice delivered on a connection.
+pub fn record(notice: &DbError) {
+    let mut notices = NOTICES.lock().unwrap_or_else(|e| e.into_inner());
+    let severity = notices.entry(notice.severity().to_string()).or_default();
+    severity.count += 1;
+    let message = notice.message();
+    if severity.samples.len() < SAMPLES && !severity.samples.iter().any(|s| s == message) {
+        severity.samples.push(message.to_string());
+    }
+}
+
+/// Prints how many notices of each severity the server sent, with a few
+/// distinct messages each, if it sent any. These are otherwise dropped, and
+/// can point at things like implicit casts or skipped `IF NOT EXISTS` DDL.
+pub fn print_summary() {
+    let notices = NOTICES.lock().unwrap_or_else(|e| e.into_inner());
+    if notices.is_empty() {
+        return;
+    }
+    println!("\nServer Notices:");
+    for (severity, notice) in notices.iter() {
+        println!("  {}: {}", severity, notice.count);
+        for sample in &notice.samples {
+            println!("    {}", sample);
+        }
+    }
+}
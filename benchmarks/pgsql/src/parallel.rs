@@ -0,0 +1,102 @@
+use crate::bulk::copy_all;
+use crate::db;
+use crate::error::{BenchmarkError, Result};
+use crate::models::Transaction;
+use crate::report::PhaseResult;
+use crate::schema;
+use crate::variants::plain_transactions_ddl;
+use futures::future::try_join_all;
+use std::time::{Duration, Instant};
+use tokio_postgres::Client;
+
+const SCHEMA: &str = "parallel_copy_bench";
+
+/// COPYs `transactions` into a scratch table over this connection, then into a
+/// fresh one split evenly across `connections` connections COPYing at once,
+/// and reports the aggregate throughput of each. Concurrent appends to one
+/// table contend on its relation extension lock and primary key index, so
+/// the speedup shows whether parallel COPY pays off on this server. The
+/// writers connect at once, or staggered within `jitter`, before the clock
+/// starts.
+#[tracing::instrument(name = "phase", skip_all, fields(phase = "parallel_copy_benchmark"))]
+pub async fn run_parallel_copy_benchmark(
+    client: &Client,
+    database_url: &str,
+    transactions: &[Transaction],
+    connections: usize,
+    jitter: Duration,
+    batch_size: usize,
+) -> Result<Vec<PhaseResult>> {
+    if transactions.is_empty() {
+        println!("\nSkipping the parallel COPY benchmark, there are no transactions");
+        return Ok(Vec::new());
+    }
+
+    let previous = schema::enter_scratch_schema(client, SCHEMA, &plain_transactions_ddl()).await?;
+    let single = copy_all(client, transactions, batch_size).await?;
+    schema::leave_scratch_schema(client, SCHEMA, &previous).await?;
+
+    let writers = db::connect_workers(database_url, connections, jitter).await?;
+    for writer in &writers {
+        writer
+            .batch_execute(&format!("SET search_path = {SCHEMA}"))
+            .await?;
+    }
+    let previous = schema::enter_scratch_schema(client, SCHEMA, &plain_transactions_ddl()).await?;
+    let chunk = transactions.len().div_ceil(connections);
+    let start = Instant::now();
+    try_join_all(
+        writers
+            .iter()
+            .zip(transactions.chunks(chunk))
+            .map(|(writer, rows)| copy_all(writer, rows, batch_size)),
+    )
+    .await?;
+    let parallel = start.elapsed();
+    let copied: i64 = client
+        .query_one("SELECT COUNT(*) FROM transactions", &[])
+        .await?
+        .get(0);
+    schema::leave_scratch_schema(client, SCHEMA, &previous).await?;
+    if copied != transactions.len() as i64 {
+        return Err(BenchmarkError::ValidationError(format!(
+            "parallel COPY wrote {} transactions, expected {}",
+            copied,
+            transactions.len()
+        )));
+    }
+
+    let results = vec![
+        PhaseResult::new("copy_single_stream", transactions.len(), single),
+        PhaseResult::new("copy_parallel", transactions.len(), parallel),
+    ];
+    println!(
+        "\nParallel COPY Benchmark ({} transactions into one table):",
+        transactions.len()
+    );
+    println!("-------------------------");
+    for (label, result) in [
+        "1 connection".to_string(),
+        format!("{} connections", connections),
+    ]
+    .iter()
+    .zip(&results)
+    {
+        println!(
+            "  {:<16} {:>10.2?}  {:>10.0} rows/sec",
+            label,
+            result.duration,
+            result.records_per_sec()
+        );
+    }
+    println!(
+        "  Speedup: {:.2}x with {} connections",
+        ratio(single, parallel),
+        connections
+    );
+    Ok(results)
+}
+
+fn ratio(single: Duration, parallel: Duration) -> f64 {
+    single.as_secs_f64() / parallel.as_secs_f64()
+}
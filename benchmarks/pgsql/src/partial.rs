@@ -0,0 +1,151 @@
+use crate::bulk::copy_all;
+use crate::error::Result;
+use crate::models::Transaction;
+use crate::report::{self, PhaseResult};
+use crate::schema;
+use crate::variants::plain_transactions_ddl;
+use rand::seq::SliceRandom;
+use std::time::{Duration, Instant};
+use tokio_postgres::Client;
+
+/// Queries timed per index variant
+const LOOKUPS: usize = 500;
+
+/// Transactions with an index on `to_address`, with the index's WHERE clause
+/// `predicate`, if any
+fn partial_index_ddl(predicate: &str) -> String {
+    format!(
+        "{}\n    CREATE INDEX transactions_to_address_idx ON transactions (to_address) {};",
+        plain_transactions_ddl(),
+        predicate
+    )
+}
+
+/// Rows the partial index covers: the dataset stores the missing `to` of a
+/// contract creation as an empty string rather than NULL
+const PREDICATE: &str = "WHERE to_address <> ''";
+
+/// COPY and lookup timings of one index variant.
+struct IndexRun {
+    copy: Duration,
+    index_bytes: i64,
+    lookups: Vec<Duration>,
+}
+
+/// Compares a full index on `to_address` against a partial one skipping the
+/// transactions without a recipient: COPY throughput with each index
+/// maintained, index size, and the latency of lookups by recipient whose
+/// query repeats the predicate, as the planner needs to use the partial index.
+#[tracing::instrument(name = "phase", skip_all, fields(phase = "partial_index_benchmark"))]
+pub async fn run_partial_index_benchmark(
+    client: &Client,
+    transactions: &[Transaction],
+    batch_size: usize,
+) -> Result<Vec<PhaseResult>> {
+    if transactions.is_empty() {
+        println!("\nSkipping the partial index benchmark, there are no transactions");
+        return Ok(Vec::new());
+    }
+    let mut rng = rand::thread_rng();
+    let recipients: Vec<&str> = (0..LOOKUPS)
+        .filter_map(|_| transactions.choose(&mut rng))
+        .map(|tx| tx.to.as_str())
+        .collect();
+    let covered = transactions.iter().filter(|tx| !tx.to.is_empty()).count();
+
+    let full = index_run(
+        client,
+        "partial_index_full",
+        "",
+        transactions,
+        &recipients,
+        batch_size,
+    )
+    .await?;
+    let partial = index_run(
+        client,
+        "partial_index_partial",
+        PREDICATE,
+        transactions,
+        &recipients,
+        batch_size,
+    )
+    .await?;
+
+    println!(
+        "\nPartial vs Full Index Benchmark ({} transactions, {} with a recipient):",
+        transactions.len(),
+        covered
+    );
+    println!("-------------------------");
+    let mut results = Vec::new();
+    for (label, run) in [("full", full), ("partial", partial)] {
+        let mut lookups = run.lookups;
+        let mean = lookups.iter().sum::<Duration>() / lookups.len().max(1) as u32;
+        let p99 = report::percentile(&mut lookups, 99.0);
+        println!(
+            "  {:<8} COPY {:>10.0} rows/sec  index {:>8.1} KiB  lookup mean {:>9.2?}  p99 {:>9.2?}",
+            label,
+            transactions.len() as f64 / run.copy.as_secs_f64(),
+            run.index_bytes as f64 / 1024.0,
+            mean,
+            p99.unwrap_or_default()
+        );
+        results.push(PhaseResult::new(
+            &format!("{}_index_copy", label),
+            transactions.len(),
+            run.copy,
+        ));
+        results.push(
+            PhaseResult::new(
+                &format!("{}_index_lookup", label),
+                lookups.len(),
+                lookups.iter().sum(),
+            )
+            .with_p99(p99),
+        );
+    }
+    if covered == transactions.len() {
+        println!("  Every transaction has a recipient, so both indexes cover the same rows");
+    }
+    Ok(results)
+}
+
+/// Loads `transactions` into the scratch schema `name` with the `to_address`
+/// index limited by `predicate`, then times lookups of `recipients`.
+async fn index_run(
+    client: &Client,
+    name: &str,
+    predicate: &str,
+    transactions: &[Transaction],
+    recipients: &[&str],
+    batch_size: usize,
+) -> Result<IndexRun> {
+    let ddl = partial_index_ddl(predicate);
+    let previous = schema::enter_scratch_schema(client, name, &ddl).await?;
+    let copy = copy_all(client, transactions, batch_size).await?;
+    client.execute("ANALYZE transactions", &[]).await?;
+    let index_bytes: i64 = client
+        .query_one(
+            "SELECT pg_relation_size('transactions_to_address_idx')",
+            &[],
+        )
+        .await?
+        .get(0);
+
+    let statement = client
+        .prepare("SELECT COUNT(*) FROM transactions WHERE to_address = $1 AND to_address <> ''")
+        .await?;
+    let mut lookups = Vec::with_capacity(recipients.len());
+    for recipient in recipients {
+        let start = Instant::now();
+        client.query_one(&statement, &[recipient]).await?;
+        lookups.push(start.elapsed());
+    }
+    schema::leave_scratch_schema(client, name, &previous).await?;
+    Ok(IndexRun {
+        copy,
+        index_bytes,
+        lookups,
+    })
+}
@@ -0,0 +1,82 @@
+use crate::bulk::copy_all;
+use crate::error::Result;
+use crate::models::Transaction;
+use crate::report::PhaseResult;
+use crate::schema;
+use crate::variants::plain_transactions_ddl;
+use std::time::Instant;
+use tokio_postgres::Client;
+
+const SCHEMA: &str = "pk_after_load_bench";
+
+/// `plain_transactions_ddl` with the `id` primary key left for after the load
+fn no_pk_transactions_ddl() -> String {
+    schema::TRANSACTIONS
+        .scratch_table()
+        .id(Some("id SERIAL"))
+        .ddl()
+}
+
+/// Compares COPYing the transactions into a scratch table that already has
+/// its `id` primary key against the usual fast-load recipe: COPY into a
+/// table without one, then `ALTER TABLE ... ADD PRIMARY KEY`, which builds
+/// the index in one sorted pass instead of inserting into it row by row.
+/// Reports each step and the end-to-end difference.
+#[tracing::instrument(name = "phase", skip_all, fields(phase = "pk_after_load_benchmark"))]
+pub async fn run_pk_after_load_benchmark(
+    client: &Client,
+    transactions: &[Transaction],
+    batch_size: usize,
+) -> Result<Vec<PhaseResult>> {
+    if transactions.is_empty() {
+        println!("\nSkipping the primary key timing benchmark, there are no transactions");
+        return Ok(Vec::new());
+    }
+
+    let previous = schema::enter_scratch_schema(client, SCHEMA, &plain_transactions_ddl()).await?;
+    let with_pk = copy_all(client, transactions, batch_size).await?;
+    schema::leave_scratch_schema(client, SCHEMA, &previous).await?;
+
+    let previous = schema::enter_scratch_schema(client, SCHEMA, &no_pk_transactions_ddl()).await?;
+    let without_pk = copy_all(client, transactions, batch_size).await?;
+    let start = Instant::now();
+    client
+        .batch_execute("ALTER TABLE transactions ADD PRIMARY KEY (id)")
+        .await?;
+    let add_pk = start.elapsed();
+    schema::leave_scratch_schema(client, SCHEMA, &previous).await?;
+    let after = without_pk + add_pk;
+
+    println!(
+        "\nPrimary Key Before vs After Load Benchmark ({} transactions):",
+        transactions.len()
+    );
+    println!("-------------------------");
+    println!(
+        "  PK before load  COPY {:>10.2?}                       total {:>10.2?}",
+        with_pk, with_pk
+    );
+    println!(
+        "  PK after load   COPY {:>10.2?}  ADD PRIMARY KEY {:>10.2?}  total {:>10.2?}",
+        without_pk, add_pk, after
+    );
+    if after < with_pk {
+        println!(
+            "  Adding the primary key after the load is {:.2?} ({:.2}x) faster end to end",
+            with_pk - after,
+            with_pk.as_secs_f64() / after.as_secs_f64()
+        );
+    } else {
+        println!(
+            "  Adding the primary key after the load is {:.2?} slower end to end",
+            after - with_pk
+        );
+    }
+
+    Ok(vec![
+        PhaseResult::new("load_with_pk", transactions.len(), with_pk),
+        PhaseResult::new("load_without_pk", transactions.len(), without_pk),
+        PhaseResult::new("add_primary_key", transactions.len(), add_pk),
+        PhaseResult::new("load_then_add_pk", transactions.len(), after),
+    ])
+}
@@ -0,0 +1,183 @@
+use crate::bulk::copy_all;
+use crate::error::Result;
+use crate::models::Transaction;
+use crate::report::{self, PhaseResult};
+use crate::schema;
+use crate::stats::try_stat;
+use crate::variants::plain_transactions_ddl;
+use rand::seq::SliceRandom;
+use std::time::{Duration, Instant};
+use tokio_postgres::Client;
+
+const SCHEMA: &str = "prefix_search_bench";
+
+/// Queries timed per index variant
+const SEARCHES: usize = 200;
+
+/// Characters of an address searched for, `0x` and four hex digits
+const PREFIX_LEN: usize = 6;
+
+/// Index variants on `from_address`, by label and DDL. No index leaves the
+/// search to a sequential scan, as the baseline.
+const VARIANTS: [(&str, Option<&str>); 3] = [
+    ("no_index", None),
+    (
+        "btree_pattern_ops",
+        Some("CREATE INDEX from_address_idx ON transactions (from_address text_pattern_ops)"),
+    ),
+    (
+        "gin_trgm",
+        Some("CREATE INDEX from_address_idx ON transactions USING gin (from_address gin_trgm_ops)"),
+    ),
+];
+
+/// Index build and search timings of one variant.
+struct SearchRun {
+    label: &'static str,
+    build: Duration,
+    index_bytes: i64,
+    used_index: bool,
+    rows: usize,
+    searches: Vec<Duration>,
+}
+
+/// Compares `LIKE '<prefix>%'` searches on `from_address` without an index,
+/// with a `text_pattern_ops` BTREE index and with a `pg_trgm` GIN index, each
+/// over a scratch copy of the transactions: index build time and size, whether
+/// the planner picked the index, and search latency. The GIN variant is
+/// skipped with a warning if the `pg_trgm` extension can't be created.
+#[tracing::instrument(name = "phase", skip_all, fields(phase = "prefix_search_benchmark"))]
+pub async fn run_prefix_search_benchmark(
+    client: &Client,
+    transactions: &[Transaction],
+    batch_size: usize,
+) -> Result<Vec<PhaseResult>> {
+    if transactions.is_empty() {
+        println!("\nSkipping the prefix search benchmark, there are no transactions");
+        return Ok(Vec::new());
+    }
+    let mut rng = rand::thread_rng();
+    let prefixes: Vec<String> = (0..SEARCHES)
+        .filter_map(|_| transactions.choose(&mut rng))
+        .map(|tx| like_prefix(&tx.from))
+        .collect();
+    // Created ahead of the scratch schema, which would otherwise own it and
+    // drop it on the way out
+    let trigrams = try_stat(client, "CREATE EXTENSION IF NOT EXISTS pg_trgm", &[])
+        .await?
+        .is_some();
+
+    let mut runs = Vec::with_capacity(VARIANTS.len());
+    for (label, index) in VARIANTS {
+        if label == "gin_trgm" && !trigrams {
+            eprintln!("Warning: pg_trgm is unavailable, skipping the GIN trigram variant");
+            continue;
+        }
+        runs.push(search_run(client, label, index, transactions, &prefixes, batch_size).await?);
+    }
+
+    println!(
+        "\nPrefix Search Benchmark ({} transactions, {} LIKE searches on from_address):",
+        transactions.len(),
+        SEARCHES
+    );
+    println!("-------------------------");
+    let mut results = Vec::new();
+    for run in runs {
+        let mut searches = run.searches;
+        let duration: Duration = searches.iter().sum();
+        let mean = duration / searches.len().max(1) as u32;
+        let p99 = report::percentile(&mut searches, 99.0);
+        println!(
+            "  {:<18} build {:>9.2?}  index {:>8.1} KiB  search mean {:>9.2?}  p99 {:>9.2?}  {}",
+            run.label,
+            run.build,
+            run.index_bytes as f64 / 1024.0,
+            mean,
+            p99.unwrap_or_default(),
+            if run.used_index {
+                "index scan"
+            } else {
+                "sequential scan"
+            }
+        );
+        results.push(
+            PhaseResult::new(&format!("prefix_search_{}", run.label), run.rows, duration)
+                .with_p99(p99),
+        );
+    }
+    Ok(results)
+}
+
+/// Loads `transactions` into a scratch table, builds `index` if any and times
+/// a search for each of `prefixes`.
+async fn search_run(
+    client: &Client,
+    label: &'static str,
+    index: Option<&str>,
+    transactions: &[Transaction],
+    prefixes: &[String],
+    batch_size: usize,
+) -> Result<SearchRun> {
+    let previous = schema::enter_scratch_schema(client, SCHEMA, &plain_transactions_ddl()).await?;
+    copy_all(client, transactions, batch_size).await?;
+    let mut build = Duration::ZERO;
+    let mut index_bytes = 0;
+    if let Some(index) = index {
+        let start = Instant::now();
+        client.batch_execute(index).await?;
+        build = start.elapsed();
+        index_bytes = client
+            .query_one("SELECT pg_relation_size('from_address_idx')", &[])
+            .await?
+            .get(0);
+    }
+    client.execute("ANALYZE transactions", &[]).await?;
+
+    // The pattern is inlined as a literal, since the planner only turns a
+    // LIKE prefix into a BTREE range for a constant
+    let query = |prefix: &str| {
+        format!(
+            "SELECT COUNT(*) FROM transactions WHERE from_address LIKE '{}%'",
+            prefix
+        )
+    };
+    let plan = client
+        .query(&format!("EXPLAIN {}", query(&prefixes[0])), &[])
+        .await?;
+    let used_index = plan
+        .iter()
+        .any(|line| line.get::<_, &str>(0).contains("from_address_idx"));
+
+    let mut rows = 0;
+    let mut searches = Vec::with_capacity(prefixes.len());
+    for prefix in prefixes {
+        let start = Instant::now();
+        let count: i64 = client.query_one(&query(prefix), &[]).await?.get(0);
+        searches.push(start.elapsed());
+        rows += count as usize;
+    }
+    schema::leave_scratch_schema(client, SCHEMA, &previous).await?;
+    Ok(SearchRun {
+        label,
+        build,
+        index_bytes,
+        used_index,
+        rows,
+        searches,
+    })
+}
+
+/// The first `PREFIX_LEN` characters of `address`, with LIKE wildcards and
+/// quotes escaped for use inside a string literal pattern.
+fn like_prefix(address: &str) -> String {
+    address
+        .chars()
+        .take(PREFIX_LEN)
+        .map(|c| match c {
+            '%' | '_' | '\\' => format!("\\{}", c),
+            '\'' => "''".to_string(),
+            c => c.to_string(),
+        })
+        .collect()
+}
@@ -0,0 +1,60 @@
+use crate::error::Result;
+use crate::report::PhaseResult;
+use prometheus::{GaugeVec, Opts, Registry};
+use std::collections::HashMap;
+use std::io;
+
+/// Job label the metrics are grouped under on the pushgateway
+const JOB: &str = "db_bench";
+
+/// Pushes each phase's throughput (overall and per table) and batch p99
+/// latency to a Prometheus pushgateway, grouped by target database.
+///
+/// Pushing replaces the previous run's metrics for the same target.
+pub async fn push_results(url: &str, target: &str, results: &[PhaseResult]) -> Result<()> {
+    let registry = Registry::new();
+    let records_per_sec = GaugeVec::new(
+        Opts::new(
+            "db_bench_records_per_sec",
+            "Records written or read per second",
+        ),
+        &["phase", "table"],
+    )?;
+    let p99_ms = GaugeVec::new(
+        Opts::new(
+            "db_bench_p99_ms",
+            "99th percentile batch latency in milliseconds",
+        ),
+        &["phase"],
+    )?;
+    registry.register(Box::new(records_per_sec.clone()))?;
+    registry.register(Box::new(p99_ms.clone()))?;
+
+    for result in results.iter().filter(|r| r.records > 0) {
+        records_per_sec
+            .with_label_values(&[&result.phase, "all"])
+            .set(result.records_per_sec());
+        for table in result.tables.iter().filter(|t| t.records > 0) {
+            records_per_sec
+                .with_label_values(&[&result.phase, table.table])
+                .set(table.records_per_sec());
+        }
+        if let Some(p99) = result.p99 {
+            p99_ms
+                .with_label_values(&[&result.phase])
+                .set(p99.as_secs_f64() * 1000.0);
+        }
+    }
+
+    // The push client is blocking, keep it off the runtime threads
+    let push_url = url.to_string();
+    // Grouping label values end up in the URL path, so they can't contain '/'
+    let grouping = HashMap::from([("instance".to_string(), target.replace('/', "_"))]);
+    tokio::task::spawn_blocking(move || {
+        prometheus::push_metrics(JOB, grouping, &push_url, registry.gather(), None)
+    })
+    .await
+    .map_err(io::Error::other)??;
+    println!("Pushed results for {} to {}", target, url);
+    Ok(())
+}
@@ -0,0 +1,338 @@
+use crate::error::Result;
+use crate::report::{self, PhaseResult};
+use crate::schema::TABLES;
+use crate::stats::{self, try_stat};
+use rand::Rng;
+use std::time::{Duration, Instant};
+use tokio_postgres::Client;
+
+/// Read queries timed by the read benchmarks, by name
+const READ_QUERIES: [(&str, &str); 3] = [
+    (
+        "tx_scan",
+        "SELECT COUNT(*), MAX(length(value)) FROM transactions",
+    ),
+    (
+        "block_range",
+        "SELECT COUNT(*) FROM transactions
+         WHERE block BETWEEN (SELECT MIN(block_number) FROM blocks)
+                         AND (SELECT MIN(block_number) + 100 FROM blocks)",
+    ),
+    (
+        "transfers_join",
+        "SELECT COUNT(*) FROM transfers t JOIN transactions x ON x.hash = t.tx_hash",
+    ),
+];
+
+/// Per-sender aggregation whose hash or sort is sized by work_mem
+const GROUP_BY_QUERY: &str = "SELECT from_address, COUNT(*), SUM(length(value))
+     FROM transactions GROUP BY from_address ORDER BY 2 DESC";
+
+/// Timed runs of the aggregation per work_mem setting
+const WORK_MEM_RUNS: usize = 5;
+
+/// OFFSET pagination re-reads every earlier row per page, so its walk stops here
+const MAX_OFFSET_PAGES: usize = 2_000;
+
+/// Scratch table scanned to push the benchmark tables out of shared buffers
+const FLUSH_TABLE: &str = "cache_flush_scratch";
+
+/// Times each read query with the tables in shared buffers, then again after
+/// evicting them, and reports cold against warm latency.
+///
+/// Warming uses `pg_prewarm` when available, otherwise a priming run of each
+/// query. Eviction uses `pg_buffercache_evict` (Postgres 17+) when available,
+/// otherwise an index scan over a scratch table the size of shared_buffers,
+/// which unlike a sequential scan doesn't stay within a small ring buffer.
+/// Neither reaches the OS page cache, so "cold" means a shared-buffer miss,
+/// not necessarily a disk read.
+#[tracing::instrument(name = "phase", skip_all, fields(phase = "cold_cache_benchmark"))]
+pub async fn run_cold_cache_benchmark(client: &Client) -> Result<Vec<PhaseResult>> {
+    warm_tables(client).await?;
+    let warm = time_queries(client).await?;
+    let method = evict_tables(client).await?;
+    let cold = time_queries(client).await?;
+
+    println!(
+        "\nCold vs Warm Cache Read Benchmark (evicted via {}):",
+        method
+    );
+    println!("-------------------------");
+    let mut results = Vec::new();
+    for ((name, warm), (_, cold)) in warm.into_iter().zip(cold) {
+        println!(
+            "  {:<16} warm {:>10.2?}  cold {:>10.2?}  ({:.1}x)",
+            name,
+            warm,
+            cold,
+            cold.as_secs_f64() / warm.as_secs_f64()
+        );
+        results.push(PhaseResult::new(&format!("{}_warm", name), 0, warm));
+        results.push(PhaseResult::new(&format!("{}_cold", name), 0, cold));
+    }
+    println!("  Note: the OS page cache is not cleared, cold reads may not reach disk");
+    Ok(results)
+}
+
+async fn time_queries(client: &Client) -> Result<Vec<(&'static str, Duration)>> {
+    let mut timings = Vec::with_capacity(READ_QUERIES.len());
+    for (name, query) in READ_QUERIES {
+        let start = Instant::now();
+        client.query(query, &[]).await?;
+        timings.push((name, start.elapsed()));
+    }
+    Ok(timings)
+}
+
+/// Loads the tables into shared buffers, with `pg_prewarm` if it can be used.
+async fn warm_tables(client: &Client) -> Result<()> {
+    if try_stat(client, "CREATE EXTENSION IF NOT EXISTS pg_prewarm", &[])
+        .await?
+        .is_some()
+    {
+        for table in TABLES {
+            client
+                .execute("SELECT pg_prewarm($1::TEXT::regclass)", &[&table])
+                .await?;
+        }
+    } else {
+        time_queries(client).await?;
+    }
+    Ok(())
+}
+
+/// Evicts the tables from shared buffers, returning how.
+async fn evict_tables(client: &Client) -> Result<&'static str> {
+    if stats::server_version(client).await? >= 170000
+        && try_stat(client, "CREATE EXTENSION IF NOT EXISTS pg_buffercache", &[])
+            .await?
+            .is_some()
+    {
+        let tables: Vec<&str> = TABLES.to_vec();
+        let evicted = try_stat(
+            client,
+            "SELECT pg_buffercache_evict(b.bufferid)
+             FROM pg_buffercache b JOIN pg_class c ON b.relfilenode = pg_relation_filenode(c.oid)
+             WHERE c.oid IN (SELECT to_regclass(t) FROM unnest($1::TEXT[]) AS t)",
+            &[&tables],
+        )
+        .await?;
+        if evicted.is_some() {
+            return Ok("pg_buffercache_evict");
+        }
+    }
+
+    // Two uncompressed 4 KB rows per 8 KB page, a fifth more pages than there are shared buffers
+    let buffers: i64 = client
+        .query_one(
+            "SELECT setting::BIGINT FROM pg_settings WHERE name = 'shared_buffers'",
+            &[],
+        )
+        .await?
+        .get(0);
+    client
+        .batch_execute(&format!(
+            "DROP TABLE IF EXISTS {FLUSH_TABLE};
+             CREATE UNLOGGED TABLE {FLUSH_TABLE} (id INTEGER, pad TEXT);
+             ALTER TABLE {FLUSH_TABLE} ALTER COLUMN pad SET STORAGE PLAIN;
+             INSERT INTO {FLUSH_TABLE} SELECT g, repeat('x', 4000) FROM generate_series(1, {rows}) g;
+             CREATE INDEX ON {FLUSH_TABLE} (id);
+             SET enable_seqscan = off;
+             SET enable_bitmapscan = off;
+             SELECT SUM(length(pad)) FROM {FLUSH_TABLE} WHERE id > 0;
+             RESET enable_seqscan;
+             RESET enable_bitmapscan;
+             DROP TABLE {FLUSH_TABLE}",
+            rows = buffers * 12 / 10 * 2
+        ))
+        .await?;
+    Ok("a competing index scan")
+}
+
+/// Walks the transactions table page by page, first with keyset pagination
+/// over the whole table and then with OFFSET (up to `MAX_OFFSET_PAGES`),
+/// reporting throughput and per-page latency. Comparing the first and last
+/// tenth of the pages shows OFFSET slowing down with depth while keyset stays flat.
+#[tracing::instrument(name = "phase", skip_all, fields(phase = "pagination_benchmark"))]
+pub async fn run_pagination_benchmark(
+    client: &Client,
+    page_size: usize,
+) -> Result<Vec<PhaseResult>> {
+    let limit = page_size.max(1) as i64;
+    println!("\nPagination Benchmark ({} rows per page):", limit);
+    println!("-------------------------");
+
+    let keyset = client
+        .prepare("SELECT id, hash FROM transactions WHERE id > $1 ORDER BY id LIMIT $2")
+        .await?;
+    let mut latencies = Vec::new();
+    let mut rows = 0;
+    let mut last_id = 0i32;
+    let start = Instant::now();
+    loop {
+        let page_start = Instant::now();
+        let page = client.query(&keyset, &[&last_id, &limit]).await?;
+        latencies.push(page_start.elapsed());
+        let Some(last) = page.last() else { break };
+        last_id = last.get(0);
+        rows += page.len();
+    }
+    let keyset_result = report_pages("keyset", rows, start.elapsed(), latencies);
+
+    let offset = client
+        .prepare("SELECT id, hash FROM transactions ORDER BY id LIMIT $1 OFFSET $2")
+        .await?;
+    let mut latencies = Vec::new();
+    let mut rows = 0;
+    let start = Instant::now();
+    while latencies.len() < MAX_OFFSET_PAGES {
+        let page_start = Instant::now();
+        let page = client.query(&offset, &[&limit, &(rows as i64)]).await?;
+        latencies.push(page_start.elapsed());
+        if page.is_empty() {
+            break;
+        }
+        rows += page.len();
+    }
+    if latencies.len() == MAX_OFFSET_PAGES {
+        println!("  (OFFSET walk stopped after {} pages)", MAX_OFFSET_PAGES);
+    }
+    let offset_result = report_pages("offset", rows, start.elapsed(), latencies);
+
+    Ok(vec![keyset_result, offset_result])
+}
+
+/// Prints one pagination strategy's throughput and page latencies, including
+/// the mean latency of the first and last tenth of the pages.
+fn report_pages(
+    strategy: &str,
+    rows: usize,
+    duration: Duration,
+    mut latencies: Vec<Duration>,
+) -> PhaseResult {
+    let pages = latencies.len();
+    let tenth = (pages / 10).max(1);
+    let mean = |pages: &[Duration]| pages.iter().sum::<Duration>() / pages.len().max(1) as u32;
+    let first = mean(&latencies[..tenth.min(pages)]);
+    let last = mean(&latencies[pages.saturating_sub(tenth)..]);
+    let overall = mean(&latencies);
+    let p99 = report::percentile(&mut latencies, 99.0);
+    println!(
+        "  {:<7} {:>6} pages  {:>10.0} rows/sec  mean {:>9.2?}  p99 {:>9.2?}  first 10% {:>9.2?}  last 10% {:>9.2?}",
+        strategy,
+        pages,
+        rows as f64 / duration.as_secs_f64(),
+        overall,
+        p99.unwrap_or_default(),
+        first,
+        last
+    );
+    PhaseResult::new(&format!("paginate_{}", strategy), rows, duration).with_p99(p99)
+}
+
+/// Runs `n` `SELECT * FROM blocks WHERE id = $1` lookups of random ids within
+/// the table's id range and reports latency percentiles and lookups per second.
+/// Ids left unused by rolled back or failed inserts return no row but are
+/// still timed, as a miss costs the same index descent.
+#[tracing::instrument(name = "phase", skip_all, fields(phase = "pk_lookup_benchmark"))]
+pub async fn run_pk_lookup_benchmark(client: &Client, n: usize) -> Result<Vec<PhaseResult>> {
+    let row = client
+        .query_one("SELECT MIN(id), MAX(id) FROM blocks", &[])
+        .await?;
+    let (Some(low), Some(high)) = (row.get::<_, Option<i32>>(0), row.get::<_, Option<i32>>(1))
+    else {
+        println!("\nSkipping the primary key lookup benchmark, the blocks table is empty");
+        return Ok(Vec::new());
+    };
+
+    let statement = client.prepare("SELECT * FROM blocks WHERE id = $1").await?;
+    let mut rng = rand::thread_rng();
+    let mut latencies = Vec::with_capacity(n);
+    let mut found = 0;
+    let start = Instant::now();
+    for _ in 0..n {
+        let id = rng.gen_range(low..=high);
+        let lookup_start = Instant::now();
+        found += client.query_opt(&statement, &[&id]).await?.is_some() as usize;
+        latencies.push(lookup_start.elapsed());
+    }
+    let duration = start.elapsed();
+
+    let mean = latencies.iter().sum::<Duration>() / n.max(1) as u32;
+    let p50 = report::percentile(&mut latencies, 50.0);
+    let p95 = report::percentile(&mut latencies, 95.0);
+    let p99 = report::percentile(&mut latencies, 99.0);
+    println!(
+        "\nPrimary Key Lookup Benchmark ({} lookups over ids {}..={}):",
+        n, low, high
+    );
+    println!("-------------------------");
+    println!(
+        "  {:>10.0} lookups/sec  mean {:>9.2?}  p50 {:>9.2?}  p95 {:>9.2?}  p99 {:>9.2?}",
+        n as f64 / duration.as_secs_f64(),
+        mean,
+        p50.unwrap_or_default(),
+        p95.unwrap_or_default(),
+        p99.unwrap_or_default()
+    );
+    if found < n {
+        println!(
+            "  {} lookups hit an unused id and returned no row",
+            n - found
+        );
+    }
+    Ok(vec![
+        PhaseResult::new("pk_lookup", n, duration).with_p99(p99)
+    ])
+}
+
+/// Runs the per-sender GROUP BY aggregation over transactions under each
+/// session `work_mem` setting (e.g. `64kB`, `4MB`) and reports its latency
+/// and whether the plan spilled to disk, as a guide to sizing work_mem.
+/// work_mem is reset afterwards.
+#[tracing::instrument(name = "phase", skip_all, fields(phase = "work_mem_sweep"))]
+pub async fn run_work_mem_sweep(client: &Client, settings: &[String]) -> Result<Vec<PhaseResult>> {
+    println!(
+        "\nwork_mem Sweep of the GROUP BY Aggregation ({} runs per setting):",
+        WORK_MEM_RUNS
+    );
+    println!("-------------------------");
+    let mut results = Vec::with_capacity(settings.len());
+    for setting in settings {
+        client
+            .batch_execute(&format!("SET work_mem = '{}'", setting))
+            .await?;
+        let plan = client
+            .query(&format!("EXPLAIN (ANALYZE) {}", GROUP_BY_QUERY), &[])
+            .await?;
+        let spilled = plan
+            .iter()
+            .any(|line| line.get::<_, &str>(0).contains("Disk"));
+
+        let mut latencies = Vec::with_capacity(WORK_MEM_RUNS);
+        for _ in 0..WORK_MEM_RUNS {
+            let start = Instant::now();
+            client.query(GROUP_BY_QUERY, &[]).await?;
+            latencies.push(start.elapsed());
+        }
+        let duration: Duration = latencies.iter().sum();
+        let mean = duration / WORK_MEM_RUNS as u32;
+        let p50 = report::percentile(&mut latencies, 50.0);
+        println!(
+            "  {:>8}  mean {:>10.2?}  median {:>10.2?}  {}",
+            setting,
+            mean,
+            p50.unwrap_or_default(),
+            if spilled {
+                "spilled to disk"
+            } else {
+                "in memory"
+            }
+        );
+        results.push(
+            PhaseResult::new(&format!("group_by_work_mem_{}", setting), 0, duration).with_p50(p50),
+        );
+    }
+    client.batch_execute("RESET work_mem").await?;
+    Ok(results)
+}
@@ -0,0 +1,108 @@
+use crate::error::{BenchmarkError, Result};
+use std::convert::Infallible;
+use std::time::{Duration, Instant};
+use tokio_postgres::Client;
+
+/// Time between replication lag samples during the load
+const SAMPLE_INTERVAL: Duration = Duration::from_millis(500);
+
+/// How long to wait after the load for the replica to replay everything
+const CATCH_UP_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Peak replication lag seen while sampling.
+#[derive(Default)]
+pub struct ReplicaLag {
+    pub samples: usize,
+    pub peak_bytes: i64,
+    pub peak_secs: f64,
+}
+
+/// Fails unless `replica` is a standby in recovery, so a primary URL passed as
+/// `--replica-url` by mistake doesn't report zero lag.
+pub async fn check_standby(replica: &Client) -> Result<()> {
+    let in_recovery: bool = replica
+        .query_one("SELECT pg_is_in_recovery()", &[])
+        .await?
+        .get(0);
+    if !in_recovery {
+        return Err(BenchmarkError::ConfigError(
+            "--replica-url points at a server that isn't a standby in recovery".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// WAL the replica has yet to replay relative to the primary's current
+/// position, in bytes, and the age of the last transaction it replayed.
+async fn lag_sample(primary: &Client, replica: &Client) -> Result<(Option<i64>, Option<f64>)> {
+    let lsn: String = primary
+        .query_one("SELECT pg_current_wal_lsn()::TEXT", &[])
+        .await?
+        .get(0);
+    let row = replica
+        .query_one(
+            "SELECT pg_wal_lsn_diff($1::TEXT::pg_lsn, pg_last_wal_replay_lsn())::BIGINT,
+                    EXTRACT(EPOCH FROM now() - pg_last_xact_replay_timestamp())::FLOAT8",
+            &[&lsn],
+        )
+        .await?;
+    Ok((row.get(0), row.get(1)))
+}
+
+/// Samples the replica's replay lag into `lag` every `SAMPLE_INTERVAL` until
+/// dropped, so it can be raced against a load. Only returns on error.
+pub async fn sample_lag(
+    primary: &Client,
+    replica: &Client,
+    lag: &mut ReplicaLag,
+) -> Result<Infallible> {
+    let mut ticks = tokio::time::interval(SAMPLE_INTERVAL);
+    loop {
+        ticks.tick().await;
+        let (bytes, secs) = lag_sample(primary, replica).await?;
+        lag.samples += 1;
+        lag.peak_bytes = lag.peak_bytes.max(bytes.unwrap_or(0));
+        lag.peak_secs = lag.peak_secs.max(secs.unwrap_or(0.0));
+    }
+}
+
+/// Waits for the replica to replay the primary's WAL up to now, returning how
+/// long that took, or `None` if it is still behind after `CATCH_UP_TIMEOUT`.
+pub async fn wait_for_catch_up(primary: &Client, replica: &Client) -> Result<Option<Duration>> {
+    let target: String = primary
+        .query_one("SELECT pg_current_wal_lsn()::TEXT", &[])
+        .await?
+        .get(0);
+    let start = Instant::now();
+    while start.elapsed() < CATCH_UP_TIMEOUT {
+        let behind: Option<i64> = replica
+            .query_one(
+                "SELECT pg_wal_lsn_diff($1::TEXT::pg_lsn, pg_last_wal_replay_lsn())::BIGINT",
+                &[&target],
+            )
+            .await?
+            .get(0);
+        if behind.is_some_and(|behind| behind <= 0) {
+            return Ok(Some(start.elapsed()));
+        }
+        tokio::time::sleep(SAMPLE_INTERVAL / 5).await;
+    }
+    Ok(None)
+}
+
+/// Prints the peak lag seen during the load and the replica's catch-up time.
+pub fn report_lag(lag: &ReplicaLag, catch_up: Option<Duration>) {
+    println!("\nReplication Lag During Load ({} samples):", lag.samples);
+    println!(
+        "  Peak replay lag: {:.1} MiB of WAL, {:.2}s behind the primary",
+        lag.peak_bytes as f64 / (1024.0 * 1024.0),
+        lag.peak_secs
+    );
+    match catch_up {
+        Some(duration) => println!("  Replica caught up {:.2?} after the load", duration),
+        None => println!(
+            "  Replica still behind {:?} after the load",
+            CATCH_UP_TIMEOUT
+        ),
+    }
+}
@@ -0,0 +1,370 @@
+use crate::results;
+use std::time::{Duration, Instant};
+
+/// Outcome of one benchmark phase.
+#[derive(Debug, Clone)]
+pub struct PhaseResult {
+    pub phase: String,
+    pub records: usize,
+    pub duration: Duration,
+    /// Median latency of the phase's batches or statements, where measured
+    pub p50: Option<Duration>,
+    /// 99th percentile latency of the phase's batches or statements, where measured
+    pub p99: Option<Duration>,
+    /// Records and time spent per table, for phases that load several tables
+    pub tables: Vec<TableResult>,
+}
+
+/// Records and time spent on one table within a phase.
+#[derive(Debug, Clone)]
+pub struct TableResult {
+    pub table: &'static str,
+    pub records: usize,
+    pub duration: Duration,
+    /// Average width of the table's rows as stored, sampled after the phase
+    pub avg_row_bytes: Option<f64>,
+}
+
+impl TableResult {
+    pub fn records_per_sec(&self) -> f64 {
+        self.records as f64 / self.duration.as_secs_f64()
+    }
+
+    /// Throughput normalized by row width, comparable between narrow and wide tables.
+    pub fn bytes_per_sec(&self) -> Option<f64> {
+        self.avg_row_bytes
+            .map(|width| width * self.records_per_sec())
+    }
+}
+
+impl PhaseResult {
+    pub fn new(phase: &str, records: usize, duration: Duration) -> Self {
+        PhaseResult {
+            phase: phase.to_string(),
+            records,
+            duration,
+            p50: None,
+            p99: None,
+            tables: Vec::new(),
+        }
+    }
+
+    pub fn with_p50(mut self, p50: Option<Duration>) -> Self {
+        self.p50 = p50;
+        self
+    }
+
+    pub fn with_p99(mut self, p99: Option<Duration>) -> Self {
+        self.p99 = p99;
+        self
+    }
+
+    pub fn with_tables(mut self, tables: Vec<(&'static str, usize, Duration)>) -> Self {
+        self.tables = tables
+            .into_iter()
+            .map(|(table, records, duration)| TableResult {
+                table,
+                records,
+                duration,
+                avg_row_bytes: None,
+            })
+            .collect();
+        self
+    }
+
+    /// Attaches the sampled average row width of each of `tables`.
+    pub fn with_row_widths(mut self, widths: &[(&str, Option<f64>)]) -> Self {
+        for table in &mut self.tables {
+            table.avg_row_bytes = widths
+                .iter()
+                .find(|(name, _)| *name == table.table)
+                .and_then(|(_, width)| *width);
+        }
+        self
+    }
+
+    pub fn records_per_sec(&self) -> f64 {
+        self.records as f64 / self.duration.as_secs_f64()
+    }
+}
+
+/// The `pct` percentile (0-100) of `samples` by nearest rank, or `None` if empty.
+pub fn percentile(samples: &mut [Duration], pct: f64) -> Option<Duration> {
+    if samples.is_empty() {
+        return None;
+    }
+    samples.sort_unstable();
+    let rank = ((pct / 100.0) * samples.len() as f64).ceil() as usize;
+    Some(samples[rank.clamp(1, samples.len()) - 1])
+}
+
+/// Prints each phase as a row with one column per target, showing records/sec
+/// for phases that process records and the duration for the others.
+pub fn print_comparison(runs: &[(String, Vec<PhaseResult>)]) {
+    const PHASE_WIDTH: usize = 16;
+    let width = runs
+        .iter()
+        .map(|(target, _)| target.len())
+        .max()
+        .unwrap_or(0)
+        .max(18);
+
+    println!("\nBackend Comparison:");
+    print!("{:<PHASE_WIDTH$}", "phase");
+    for (target, _) in runs {
+        print!("  {:>width$}", target);
+    }
+    println!();
+
+    let phases = runs
+        .first()
+        .map(|(_, results)| results.as_slice())
+        .unwrap_or(&[]);
+    for phase in phases {
+        print!("{:<PHASE_WIDTH$}", phase.phase);
+        for (_, results) in runs {
+            let cell = match results.iter().find(|r| r.phase == phase.phase) {
+                Some(r) if r.records > 0 => format!("{:.0} rec/s", r.records_per_sec()),
+                Some(r) => format!("{:.2?}", r.duration),
+                None => "-".to_string(),
+            };
+            print!("  {:>width$}", cell);
+        }
+        println!();
+    }
+}
+
+/// Emits one structured event per completed phase, for `--log-format`.
+pub fn log_results(results: &[PhaseResult]) {
+    for r in results {
+        tracing::info!(
+            phase = %r.phase,
+            records = r.records,
+            duration_ms = r.duration.as_secs_f64() * 1000.0,
+            records_per_sec = r.records_per_sec(),
+            p99_ms = r.p99.map(|p99| p99.as_secs_f64() * 1000.0),
+            "phase result"
+        );
+        for t in &r.tables {
+            tracing::info!(
+                phase = %r.phase,
+                table = t.table,
+                records = t.records,
+                records_per_sec = t.records_per_sec(),
+                avg_row_bytes = t.avg_row_bytes,
+                bytes_per_sec = t.bytes_per_sec(),
+                "table result"
+            );
+        }
+    }
+}
+
+/// Prints one line per completed phase.
+pub fn print_summary(results: &[PhaseResult]) {
+    println!("\nCompleted phases:");
+    if results.is_empty() {
+        println!("  (none)");
+    }
+    for r in results {
+        if r.records > 0 {
+            println!(
+                "  {:<20} {:>10} records  {:>12.2?}  {:.0} records/sec",
+                r.phase,
+                r.records,
+                r.duration,
+                r.records_per_sec()
+            );
+        } else {
+            println!(
+                "  {:<20} {:>10}          {:>12.2?}",
+                r.phase, "", r.duration
+            );
+        }
+    }
+}
+
+/// Prints one `SUMMARY phase=... records=...` line per phase against `target`
+/// and tagged with the run id, a stable format to grep out of CI logs whatever
+/// the output mode.
+pub fn print_summary_lines(target: &str, results: &[PhaseResult]) {
+    for r in results {
+        println!("{}", summary_line(target, r));
+    }
+}
+
+/// `p99_ms` is left out for phases that don't measure it.
+fn summary_line(target: &str, r: &PhaseResult) -> String {
+    let mut line = format!(
+        "SUMMARY phase={} records={} duration_ms={:.0} rps={:.1}",
+        r.phase,
+        r.records,
+        r.duration.as_secs_f64() * 1000.0,
+        if r.records > 0 {
+            r.records_per_sec()
+        } else {
+            0.0
+        }
+    );
+    if let Some(p99) = r.p99 {
+        line.push_str(&format!(" p99_ms={:.1}", p99.as_secs_f64() * 1000.0));
+    }
+    line.push_str(&format!(" target={} run_id={}", target, results::run_id()));
+    line
+}
+
+/// Prints each target's phases as a GitHub-flavored Markdown table, for
+/// `--output markdown`, headed by the target's name when there are several.
+pub fn print_markdown(runs: &[(String, Vec<PhaseResult>)]) {
+    for (target, results) in runs {
+        println!();
+        if runs.len() > 1 {
+            println!("**{}**\n", target);
+        }
+        for line in markdown_table(results) {
+            println!("{}", line);
+        }
+    }
+}
+
+fn markdown_table(results: &[PhaseResult]) -> Vec<String> {
+    let ms = |d: Option<Duration>| {
+        d.map(|d| format!("{:.2}", d.as_secs_f64() * 1000.0))
+            .unwrap_or_else(|| "-".to_string())
+    };
+    let mut lines = vec![
+        "| phase | records | duration (ms) | records/sec | p50 (ms) | p99 (ms) |".to_string(),
+        "|---|--:|--:|--:|--:|--:|".to_string(),
+    ];
+    for r in results {
+        let rps = if r.records > 0 {
+            format!("{:.0}", r.records_per_sec())
+        } else {
+            "-".to_string()
+        };
+        lines.push(format!(
+            "| {} | {} | {} | {} | {} | {} |",
+            r.phase,
+            r.records,
+            ms(Some(r.duration)),
+            rps,
+            ms(r.p50),
+            ms(r.p99)
+        ));
+    }
+    lines
+}
+
+/// Prints each table's load rate in rows/sec and, where its row width was
+/// sampled, in bytes/sec, which compares narrow and wide tables fairly.
+pub fn print_table_throughput(result: &PhaseResult) {
+    let tables: Vec<&TableResult> = result.tables.iter().filter(|t| t.records > 0).collect();
+    if tables.is_empty() {
+        return;
+    }
+    println!("\nThroughput by table:");
+    for t in tables {
+        match (t.avg_row_bytes, t.bytes_per_sec()) {
+            (Some(width), Some(bytes_per_sec)) => println!(
+                "  {:<13} {:>10.0} rows/sec  {:>6.0} B/row  {:>8.2} MiB/sec",
+                t.table,
+                t.records_per_sec(),
+                width,
+                bytes_per_sec / (1024.0 * 1024.0)
+            ),
+            _ => println!("  {:<13} {:>10.0} rows/sec", t.table, t.records_per_sec()),
+        }
+    }
+}
+
+/// Wall time spent in each part of the program, accumulated by phase name.
+pub struct PhaseTimings {
+    started: Instant,
+    phases: Vec<(String, Duration)>,
+}
+
+impl PhaseTimings {
+    /// Starts the clock for the whole program run.
+    pub fn start() -> Self {
+        PhaseTimings {
+            started: Instant::now(),
+            phases: Vec::new(),
+        }
+    }
+
+    /// Adds `duration` to `phase`, so repeated phases (e.g. per `--compare` target) sum up.
+    pub fn record(&mut self, phase: &str, duration: Duration) {
+        match self.phases.iter_mut().find(|(name, _)| name == phase) {
+            Some((_, total)) => *total += duration,
+            None => self.phases.push((phase.to_string(), duration)),
+        }
+    }
+
+    /// Prints each phase's share of the total runtime. Time outside any recorded
+    /// phase is reported as "other", so the shares add up to 100%.
+    pub fn print_breakdown(&self) {
+        let total = self.started.elapsed();
+        let recorded: Duration = self.phases.iter().map(|(_, d)| *d).sum();
+        let other = total.saturating_sub(recorded);
+        let percent = |d: Duration| 100.0 * d.as_secs_f64() / total.as_secs_f64();
+
+        println!("\nRuntime Breakdown:");
+        println!("-------------------------");
+        for (phase, duration) in &self.phases {
+            println!(
+                "  {:<20} {:>12.2?}  {:>5.1}%",
+                phase,
+                duration,
+                percent(*duration)
+            );
+        }
+        println!(
+            "  {:<20} {:>12.2?}  {:>5.1}%",
+            "other",
+            other,
+            percent(other)
+        );
+        println!("  {:<20} {:>12.2?}  100.0%", "total", total);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn markdown_rows_leave_unmeasured_cells_blank() {
+        let results = [
+            PhaseResult::new("bulk_insert", 2000, Duration::from_millis(500))
+                .with_p50(Some(Duration::from_millis(2)))
+                .with_p99(Some(Duration::from_millis(5))),
+            PhaseResult::new("analyze", 0, Duration::from_millis(12)),
+        ];
+        let table = markdown_table(&results);
+        assert_eq!(
+            table[2],
+            "| bulk_insert | 2000 | 500.00 | 4000 | 2.00 | 5.00 |"
+        );
+        assert_eq!(table[3], "| analyze | 0 | 12.00 | - | - | - |");
+    }
+
+    #[test]
+    fn summary_line_is_stable() {
+        let result = PhaseResult::new("bulk_insert", 12345, Duration::from_millis(6789))
+            .with_p99(Some(Duration::from_micros(4200)));
+        assert_eq!(
+            summary_line("localhost:5432/postgres", &result),
+            format!(
+                "SUMMARY phase=bulk_insert records=12345 duration_ms=6789 rps=1818.4 p99_ms=4.2 target=localhost:5432/postgres run_id={}",
+                results::run_id()
+            )
+        );
+        let analyze = PhaseResult::new("analyze", 0, Duration::from_millis(12));
+        assert_eq!(
+            summary_line("db", &analyze),
+            format!(
+                "SUMMARY phase=analyze records=0 duration_ms=12 rps=0.0 target=db run_id={}",
+                results::run_id()
+            )
+        );
+    }
+}
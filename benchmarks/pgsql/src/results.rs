@@ -0,0 +1,334 @@
+use crate::cli::Args;
+use crate::error::Result;
+use crate::report::PhaseResult;
+use chrono::{SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use uuid::Uuid;
+
+/// One phase of one target as written to `results.json`.
+#[derive(Serialize, Deserialize)]
+struct PhaseRecord {
+    phase: String,
+    records: usize,
+    duration_ms: f64,
+    records_per_sec: Option<f64>,
+    p99_ms: Option<f64>,
+    /// Per-table rates, missing from runs written before they were recorded
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    tables: Vec<TableRecord>,
+}
+
+/// One table's share of a phase, with its rate normalized by row width.
+#[derive(Serialize, Deserialize)]
+struct TableRecord {
+    table: String,
+    records: usize,
+    duration_ms: f64,
+    records_per_sec: Option<f64>,
+    avg_row_bytes: Option<f64>,
+    bytes_per_sec: Option<f64>,
+}
+
+impl From<&PhaseResult> for PhaseRecord {
+    fn from(r: &PhaseResult) -> Self {
+        PhaseRecord {
+            phase: r.phase.clone(),
+            records: r.records,
+            duration_ms: r.duration.as_secs_f64() * 1000.0,
+            records_per_sec: (r.records > 0).then(|| r.records_per_sec()),
+            p99_ms: r.p99.map(|p99| p99.as_secs_f64() * 1000.0),
+            tables: r
+                .tables
+                .iter()
+                .map(|t| TableRecord {
+                    table: t.table.to_string(),
+                    records: t.records,
+                    duration_ms: t.duration.as_secs_f64() * 1000.0,
+                    records_per_sec: (t.records > 0).then(|| t.records_per_sec()),
+                    avg_row_bytes: t.avg_row_bytes,
+                    bytes_per_sec: t.bytes_per_sec(),
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct TargetRecord {
+    target: String,
+    phases: Vec<PhaseRecord>,
+}
+
+/// A whole run as written to `results.json`. `batch_size` and `data_dir` are
+/// missing from runs written before they were recorded.
+#[derive(Serialize, Deserialize)]
+struct RunRecord {
+    /// Missing from runs written before runs had an id
+    #[serde(default)]
+    run_id: Option<String>,
+    timestamp: String,
+    /// Commit the tool was built from
+    commit: String,
+    /// `--batch-size`, `None` for the default
+    batch_size: Option<usize>,
+    data_dir: Option<String>,
+    targets: Vec<TargetRecord>,
+}
+
+/// One entry of `index.json`, listing a run directory.
+#[derive(Serialize, Deserialize)]
+struct IndexEntry {
+    #[serde(default)]
+    run_id: Option<String>,
+    dir: String,
+    timestamp: String,
+    commit: String,
+    targets: Vec<String>,
+}
+
+/// Short hash of the commit this binary was built from, baked in by build.rs.
+const COMMIT: &str = env!("GIT_COMMIT");
+
+/// A UUID generated once per invocation, tagging its output, log spans and
+/// results files so they can be matched up afterwards.
+pub fn run_id() -> &'static str {
+    static RUN_ID: OnceLock<String> = OnceLock::new();
+    RUN_ID.get_or_init(|| Uuid::new_v4().to_string())
+}
+
+/// Writes the run's results as `results.json` and `results.csv` into a new
+/// `<timestamp>-<commit>-<run id prefix>` directory under `results_dir`, and
+/// appends the run to `results_dir/index.json`. Returns the run directory.
+pub fn write_run(
+    results_dir: &Path,
+    args: &Args,
+    runs: &[(String, Vec<PhaseResult>)],
+) -> Result<PathBuf> {
+    let now = Utc::now();
+    let timestamp = now.to_rfc3339_opts(SecondsFormat::Secs, true);
+    let commit = COMMIT.to_string();
+    // No ':' in the directory name, for filesystems that reject it. The run id
+    // keeps runs started in the same second apart, and create_dir fails
+    // rather than overwriting another run's files should they still collide.
+    let dir_name = format!(
+        "{}-{}-{}",
+        now.format("%Y-%m-%dT%H-%M-%SZ"),
+        commit,
+        &run_id()[..8]
+    );
+    let run_dir = results_dir.join(&dir_name);
+    fs::create_dir_all(results_dir)?;
+    fs::create_dir(&run_dir)?;
+
+    let record = RunRecord {
+        run_id: Some(run_id().to_string()),
+        timestamp: timestamp.clone(),
+        commit: commit.clone(),
+        batch_size: args.batch_size,
+        data_dir: Some(args.data_dir().display().to_string()),
+        targets: runs
+            .iter()
+            .map(|(target, results)| TargetRecord {
+                target: target.clone(),
+                phases: results.iter().map(PhaseRecord::from).collect(),
+            })
+            .collect(),
+    };
+    let json = BufWriter::new(File::create(run_dir.join("results.json"))?);
+    serde_json::to_writer_pretty(json, &record)?;
+
+    let mut csv = BufWriter::new(File::create(run_dir.join("results.csv"))?);
+    writeln!(
+        csv,
+        "run_id,target,phase,records,duration_ms,records_per_sec,p99_ms"
+    )?;
+    for target in &record.targets {
+        for phase in &target.phases {
+            writeln!(
+                csv,
+                "{},{},{},{},{:.3},{},{}",
+                run_id(),
+                target.target,
+                phase.phase,
+                phase.records,
+                phase.duration_ms,
+                phase
+                    .records_per_sec
+                    .map(|r| format!("{:.1}", r))
+                    .unwrap_or_default(),
+                phase
+                    .p99_ms
+                    .map(|p| format!("{:.3}", p))
+                    .unwrap_or_default()
+            )?;
+        }
+    }
+    csv.flush()?;
+
+    let index_path = results_dir.join("index.json");
+    let mut index: Vec<IndexEntry> = match fs::read(&index_path) {
+        Ok(bytes) => serde_json::from_slice(&bytes)?,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+        Err(e) => return Err(e.into()),
+    };
+    index.push(IndexEntry {
+        run_id: Some(run_id().to_string()),
+        dir: dir_name,
+        timestamp,
+        commit,
+        targets: runs.iter().map(|(target, _)| target.clone()).collect(),
+    });
+    serde_json::to_writer_pretty(BufWriter::new(File::create(&index_path)?), &index)?;
+
+    Ok(run_dir)
+}
+
+/// Reads a run's `results.json`, given either the run directory or the file.
+fn read_run(path: &Path) -> Result<RunRecord> {
+    let file = if path.is_dir() {
+        path.join("results.json")
+    } else {
+        path.to_path_buf()
+    };
+    Ok(serde_json::from_slice(&fs::read(file)?)?)
+}
+
+/// Records the first target loaded in its bulk_insert phase.
+fn loaded_records(run: &RunRecord) -> Option<usize> {
+    let target = run.targets.first()?;
+    let phase = target.phases.iter().find(|p| p.phase == "bulk_insert")?;
+    Some(phase.records)
+}
+
+/// Settings that differ between two runs in a way that makes their numbers
+/// not directly comparable, as human-readable notes.
+fn setting_differences(a: &RunRecord, b: &RunRecord) -> Vec<String> {
+    let batch_size = |run: &RunRecord| {
+        run.batch_size
+            .map(|size| size.to_string())
+            .unwrap_or_else(|| "default".to_string())
+    };
+    let mut notes = Vec::new();
+    if a.batch_size != b.batch_size {
+        notes.push(format!(
+            "batch sizes differ ({} vs {})",
+            batch_size(a),
+            batch_size(b)
+        ));
+    }
+    if let (Some(a_dir), Some(b_dir)) = (&a.data_dir, &b.data_dir) {
+        if a_dir != b_dir {
+            notes.push(format!("data directories differ ({} vs {})", a_dir, b_dir));
+        }
+    }
+    if let (Some(a_records), Some(b_records)) = (loaded_records(a), loaded_records(b)) {
+        if a_records != b_records {
+            notes.push(format!(
+                "datasets differ ({} vs {} records loaded)",
+                a_records, b_records
+            ));
+        }
+    }
+    notes
+}
+
+/// Prints each phase of run `a` next to the same phase of run `b` with the
+/// change between them, headed by the commits that produced each run and a
+/// note for any setting that invalidates the comparison.
+///
+/// Phases that process records compare records/sec, the others duration.
+pub fn print_diff(a: &Path, b: &Path) -> Result<()> {
+    let (a, b) = (read_run(a)?, read_run(b)?);
+    println!("\nResults Diff:");
+    println!("  A: commit {} at {}", a.commit, a.timestamp);
+    println!("  B: commit {} at {}", b.commit, b.timestamp);
+    if a.commit == b.commit {
+        println!("  Both runs used the same build of the tool");
+    }
+    for note in setting_differences(&a, &b) {
+        println!("  Note: {}, so the numbers aren't like for like", note);
+    }
+
+    for target in &a.targets {
+        // A single target on each side lines up even if its address changed
+        let other = b
+            .targets
+            .iter()
+            .find(|t| t.target == target.target)
+            .or_else(|| (a.targets.len() == 1 && b.targets.len() == 1).then(|| &b.targets[0]));
+        let Some(other) = other else {
+            println!("\n{}: not in B", target.target);
+            continue;
+        };
+        println!(
+            "\n{} ({}) vs {} ({}):",
+            target.target, a.commit, other.target, b.commit
+        );
+        println!(
+            "  {:<24} {:>16}  {:>16}  {:>8}",
+            "phase", "A", "B", "change"
+        );
+        for phase in &target.phases {
+            let Some(theirs) = other.phases.iter().find(|p| p.phase == phase.phase) else {
+                println!("  {:<24} {:>16}  {:>16}", phase.phase, "", "-");
+                continue;
+            };
+            let (ours, theirs, unit) = match (phase.records_per_sec, theirs.records_per_sec) {
+                (Some(ours), Some(theirs)) => (ours, theirs, "rec/s"),
+                _ => (phase.duration_ms, theirs.duration_ms, "ms"),
+            };
+            println!(
+                "  {:<24} {:>10.1} {:<5}  {:>10.1} {:<5}  {:>+7.1}%",
+                phase.phase,
+                ours,
+                unit,
+                theirs,
+                unit,
+                100.0 * (theirs - ours) / ours
+            );
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(batch_size: Option<usize>, records: usize) -> RunRecord {
+        RunRecord {
+            run_id: None,
+            timestamp: "2024-01-01T00:00:00Z".to_string(),
+            commit: "abc1234".to_string(),
+            batch_size,
+            data_dir: Some("../../data/".to_string()),
+            targets: vec![TargetRecord {
+                target: "localhost:5432/postgres".to_string(),
+                phases: vec![PhaseRecord {
+                    phase: "bulk_insert".to_string(),
+                    records,
+                    duration_ms: 1000.0,
+                    records_per_sec: Some(records as f64),
+                    p99_ms: None,
+                    tables: Vec::new(),
+                }],
+            }],
+        }
+    }
+
+    #[test]
+    fn differing_batch_size_and_dataset_are_noted() {
+        assert!(setting_differences(&run(Some(100), 10), &run(Some(100), 10)).is_empty());
+        assert_eq!(
+            setting_differences(&run(Some(100), 10), &run(None, 20)),
+            [
+                "batch sizes differ (100 vs default)",
+                "datasets differ (10 vs 20 records loaded)"
+            ]
+        );
+    }
+}
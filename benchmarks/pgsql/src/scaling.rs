@@ -0,0 +1,58 @@
+use crate::bulk::copy_all;
+use crate::error::Result;
+use crate::models::Transaction;
+use crate::report::PhaseResult;
+use crate::schema;
+use crate::variants::plain_transactions_ddl;
+use tokio_postgres::Client;
+
+/// Shares of the transactions loaded at each point of the sweep
+const SCALE_POINTS: [f64; 3] = [0.1, 0.5, 1.0];
+
+/// COPYs the first 10%, 50% and 100% of `transactions` into a fresh scratch
+/// table each time and reports the throughput at each scale, relative to the
+/// smallest. A falling rate means inserts slow down as the table and its
+/// primary key index grow, which a single full-size load averages away.
+#[tracing::instrument(name = "phase", skip_all, fields(phase = "scale_sweep"))]
+pub async fn run_scale_sweep(
+    client: &Client,
+    transactions: &[Transaction],
+    batch_size: usize,
+) -> Result<Vec<PhaseResult>> {
+    if transactions.len() < SCALE_POINTS.len() * 10 {
+        println!("\nSkipping the scaling sweep, there are too few transactions to scale");
+        return Ok(Vec::new());
+    }
+    let mut results = Vec::with_capacity(SCALE_POINTS.len());
+    for share in SCALE_POINTS {
+        let rows = &transactions[..(transactions.len() as f64 * share).round() as usize];
+        let previous =
+            schema::enter_scratch_schema(client, "scale_sweep", &plain_transactions_ddl()).await?;
+        let duration = copy_all(client, rows, batch_size).await?;
+        schema::leave_scratch_schema(client, "scale_sweep", &previous).await?;
+        results.push(PhaseResult::new(
+            &format!("scale_{:.0}pct", share * 100.0),
+            rows.len(),
+            duration,
+        ));
+    }
+
+    println!(
+        "\nData Scaling Sweep ({} transactions at 100%):",
+        transactions.len()
+    );
+    println!("-------------------------");
+    let baseline = results[0].records_per_sec();
+    for (share, result) in SCALE_POINTS.iter().zip(&results) {
+        println!(
+            "  {:>4.0}%  {:>9} rows  {:>10.2?}  {:>10.0} rows/sec  ({:.2}x the {:.0}% rate)",
+            share * 100.0,
+            result.records,
+            result.duration,
+            result.records_per_sec(),
+            result.records_per_sec() / baseline,
+            SCALE_POINTS[0] * 100.0
+        );
+    }
+    Ok(results)
+}
@@ -1,68 +1,544 @@
-use crate::error::Result;
+use crate::error::{BenchmarkError, Result};
+use tokio_postgres::error::SqlState;
+use tokio_postgres::types::Type;
 use tokio_postgres::Client;
 
-pub async fn create_tables(client: &mut Client) -> Result<()> {
+pub const TABLES: [&str; 4] = ["blocks", "transactions", "transfers", "pools"];
+
+/// One column of a table's COPY, as it is bound.
+pub struct Column {
+    pub name: &'static str,
+    pub ty: Type,
+    /// A block height, bound as BIGINT instead of `ty` under `--bigint-blocks`
+    pub height: bool,
+}
+
+pub(crate) const fn column(name: &'static str, ty: Type) -> Column {
+    Column {
+        name,
+        ty,
+        height: false,
+    }
+}
+
+pub(crate) const fn height(name: &'static str) -> Column {
+    Column {
+        name,
+        ty: Type::INT4,
+        height: true,
+    }
+}
+
+/// A table's COPY columns in bind order, the single source of both the COPY
+/// column list and the binary `types` array so the two can't disagree.
+pub struct TableSpec {
+    pub table: &'static str,
+    pub columns: &'static [Column],
+}
+
+impl TableSpec {
+    /// `COPY <table> (<columns>) FROM STDIN BINARY`.
+    pub fn copy_statement(&self) -> String {
+        self.copy_into(self.table)
+    }
+
+    /// The COPY statement aimed at `table` instead, e.g. a scratch copy of this table.
+    pub fn copy_into(&self, table: &str) -> String {
+        let columns: Vec<&str> = self.columns.iter().map(|c| c.name).collect();
+        format!("COPY {} ({}) FROM STDIN BINARY", table, columns.join(", "))
+    }
+
+    /// `copy_statement` writing the rows already frozen, which Postgres only
+    /// allows if the table was created or truncated in the current transaction.
+    pub fn copy_freeze_statement(&self) -> String {
+        let columns: Vec<&str> = self.columns.iter().map(|c| c.name).collect();
+        format!(
+            "COPY {} ({}) FROM STDIN (FORMAT binary, FREEZE)",
+            self.table,
+            columns.join(", ")
+        )
+    }
+
+    /// The binary COPY types in column order, with block heights as BIGINT
+    /// when `bigint_blocks` is set.
+    pub fn types(&self, bigint_blocks: bool) -> Vec<Type> {
+        self.columns
+            .iter()
+            .map(|c| {
+                if c.height && bigint_blocks {
+                    Type::INT8
+                } else {
+                    c.ty.clone()
+                }
+            })
+            .collect()
+    }
+
+    /// A scratch copy of this table for `enter_scratch_schema`, as
+    /// `create_tables` makes it: a SERIAL `id` primary key, then the COPY
+    /// columns NOT NULL with block heights as INTEGER.
+    pub fn scratch_table(&self) -> ScratchTable {
+        ScratchTable {
+            table: self.table,
+            columns: self.columns,
+            id: Some("id SERIAL PRIMARY KEY"),
+            definitions: Vec::new(),
+            extra_columns: Vec::new(),
+            options: None,
+            bigint_blocks: false,
+        }
+    }
+}
+
+/// `CREATE TABLE` DDL of a scratch copy of a table, derived from its
+/// `TableSpec` so a column added there reaches every benchmark's copy.
+pub struct ScratchTable {
+    table: &'static str,
+    columns: &'static [Column],
+    id: Option<&'static str>,
+    /// COPY columns defined other than as `<type> NOT NULL`
+    definitions: Vec<(&'static str, String)>,
+    extra_columns: Vec<String>,
+    options: Option<&'static str>,
+    bigint_blocks: bool,
+}
+
+impl ScratchTable {
+    /// Defines the leading `id` column as `definition` instead, or leaves it
+    /// out with `None`.
+    pub fn id(mut self, definition: Option<&'static str>) -> Self {
+        self.id = definition;
+        self
+    }
+
+    /// Defines COPY column `name` by `definition`, its type and constraints,
+    /// instead of `<type> NOT NULL`. Panics if the table has no such column.
+    pub fn column(mut self, name: &'static str, definition: impl Into<String>) -> Self {
+        if !self.columns.iter().any(|c| c.name == name) {
+            panic!("{} has no column {:?}", self.table, name);
+        }
+        self.definitions.push((name, definition.into()));
+        self
+    }
+
+    /// Adds a column after the COPY columns, e.g. a generated one.
+    pub fn extra_column(mut self, definition: impl Into<String>) -> Self {
+        self.extra_columns.push(definition.into());
+        self
+    }
+
+    /// Declares block heights BIGINT instead, matching rows bound under
+    /// `--bigint-blocks`.
+    pub fn bigint_blocks(mut self, bigint_blocks: bool) -> Self {
+        self.bigint_blocks = bigint_blocks;
+        self
+    }
+
+    /// Sets the table's storage parameters, its `WITH (...)` clause.
+    pub fn with(mut self, options: &'static str) -> Self {
+        self.options = Some(options);
+        self
+    }
+
+    /// The `CREATE TABLE` statement, ending in a semicolon.
+    pub fn ddl(&self) -> String {
+        let columns = self.columns.iter().map(|c| {
+            match self.definitions.iter().find(|(name, _)| *name == c.name) {
+                Some((_, definition)) => format!("{} {}", c.name, definition),
+                None if c.height && self.bigint_blocks => format!("{} BIGINT NOT NULL", c.name),
+                None => format!("{} {} NOT NULL", c.name, sql_type(&c.ty)),
+            }
+        });
+        let definitions: Vec<String> = self
+            .id
+            .map(str::to_string)
+            .into_iter()
+            .chain(columns)
+            .chain(self.extra_columns.iter().cloned())
+            .collect();
+        let options = self
+            .options
+            .map(|options| format!(" WITH ({})", options))
+            .unwrap_or_default();
+        format!(
+            "\n    CREATE TABLE {} (\n        {}\n    ){};",
+            self.table,
+            definitions.join(",\n        "),
+            options
+        )
+    }
+}
+
+/// The SQL type a COPY column of type `ty` is declared as.
+fn sql_type(ty: &Type) -> String {
+    if *ty == Type::INT4 {
+        "INTEGER".to_string()
+    } else if *ty == Type::INT8 {
+        "BIGINT".to_string()
+    } else {
+        ty.name().to_uppercase()
+    }
+}
+
+pub const BLOCKS: TableSpec = TableSpec {
+    table: "blocks",
+    columns: &[
+        height("block_number"),
+        column("block_hash", Type::TEXT),
+        column("parent_hash", Type::TEXT),
+        column("block_timestamp", Type::TEXT),
+        column("created_at", Type::TEXT),
+        column("updated_at", Type::TEXT),
+    ],
+};
+
+pub const TRANSACTIONS: TableSpec = TableSpec {
+    table: "transactions",
+    columns: &[
+        height("block"),
+        column("index", Type::INT4),
+        column("timestamp", Type::TEXT),
+        column("hash", Type::TEXT),
+        column("from_address", Type::TEXT),
+        column("to_address", Type::TEXT),
+        column("value", Type::TEXT),
+    ],
+};
+
+pub const TRANSFERS: TableSpec = TableSpec {
+    table: "transfers",
+    columns: &[
+        column("tx_hash", Type::TEXT),
+        height("block_number"),
+        column("token", Type::TEXT),
+        column("from_address", Type::TEXT),
+        column("to_address", Type::TEXT),
+        column("amount", Type::TEXT),
+    ],
+};
+
+pub const POOLS: TableSpec = TableSpec {
+    table: "pools",
+    columns: &[
+        column("deployer", Type::TEXT),
+        column("address", Type::TEXT),
+        column("quote_token", Type::TEXT),
+        column("token", Type::TEXT),
+        height("init_block"),
+        column("created_at", Type::INT8),
+    ],
+};
+
+pub const BLOCKS_INSERT: &str = "INSERT INTO blocks (block_number, block_hash, parent_hash, block_timestamp, created_at, updated_at) VALUES ($1, $2, $3, $4, $5, $6)";
+pub const TRANSACTIONS_INSERT: &str = "INSERT INTO transactions (block, index, timestamp, hash, from_address, to_address, value) VALUES ($1, $2, $3, $4, $5, $6, $7)";
+pub const TRANSFERS_INSERT: &str = "INSERT INTO transfers (tx_hash, block_number, token, from_address, to_address, amount) VALUES ($1, $2, $3, $4, $5, $6)";
+pub const POOLS_INSERT: &str = "INSERT INTO pools (deployer, address, quote_token, token, init_block, created_at) VALUES ($1, $2, $3, $4, $5, $6)";
+
+pub const BLOCKS_UNNEST: &str = "INSERT INTO blocks (block_number, block_hash, parent_hash, block_timestamp, created_at, updated_at)
+    SELECT * FROM unnest($1::INT8[], $2::TEXT[], $3::TEXT[], $4::TEXT[], $5::TEXT[], $6::TEXT[])";
+/// `BLOCKS_UNNEST` refreshing `updated_at` of blocks that already exist
+pub const BLOCKS_UPSERT: &str = "INSERT INTO blocks (block_number, block_hash, parent_hash, block_timestamp, created_at, updated_at)
+    SELECT * FROM unnest($1::INT8[], $2::TEXT[], $3::TEXT[], $4::TEXT[], $5::TEXT[], $6::TEXT[])
+    ON CONFLICT (block_number) DO UPDATE SET updated_at = EXCLUDED.updated_at";
+/// `BLOCKS_UPSERT` as a `MERGE` (Postgres 15+)
+pub const BLOCKS_MERGE: &str = "MERGE INTO blocks b
+    USING unnest($1::INT8[], $2::TEXT[], $3::TEXT[], $4::TEXT[], $5::TEXT[], $6::TEXT[])
+        AS s (block_number, block_hash, parent_hash, block_timestamp, created_at, updated_at)
+    ON b.block_number = s.block_number
+    WHEN MATCHED THEN UPDATE SET updated_at = s.updated_at
+    WHEN NOT MATCHED THEN INSERT (block_number, block_hash, parent_hash, block_timestamp, created_at, updated_at)
+        VALUES (s.block_number, s.block_hash, s.parent_hash, s.block_timestamp, s.created_at, s.updated_at)";
+pub const TRANSACTIONS_UNNEST: &str = "INSERT INTO transactions (block, index, timestamp, hash, from_address, to_address, value)
+    SELECT * FROM unnest($1::INT8[], $2::INT4[], $3::TEXT[], $4::TEXT[], $5::TEXT[], $6::TEXT[], $7::TEXT[])";
+pub const TRANSFERS_UNNEST: &str =
+    "INSERT INTO transfers (tx_hash, block_number, token, from_address, to_address, amount)
+    SELECT * FROM unnest($1::TEXT[], $2::INT8[], $3::TEXT[], $4::TEXT[], $5::TEXT[], $6::TEXT[])";
+pub const POOLS_UNNEST: &str =
+    "INSERT INTO pools (deployer, address, quote_token, token, init_block, created_at)
+    SELECT * FROM unnest($1::TEXT[], $2::TEXT[], $3::TEXT[], $4::TEXT[], $5::INT8[], $6::INT8[])";
+
+/// Creates the four benchmark tables, as session-local `TEMP` tables when `temp` is set
+/// and with BIGINT block heights when `bigint_blocks` is.
+#[tracing::instrument(name = "phase", skip_all, fields(phase = "create_tables"))]
+pub async fn create_tables(client: &mut Client, temp: bool, bigint_blocks: bool) -> Result<()> {
     println!("creating tables ... ");
+    let kind = if temp { "TEMP TABLE" } else { "TABLE" };
+    let height = if bigint_blocks { "BIGINT" } else { "INTEGER" };
     client
         .execute(
-            "CREATE TABLE IF NOT EXISTS blocks (
+            &format!(
+                "CREATE {kind} IF NOT EXISTS blocks (
             id SERIAL PRIMARY KEY,
-            block_number INTEGER NOT NULL,
+            block_number {height} NOT NULL,
             block_hash TEXT NOT NULL,
             parent_hash TEXT NOT NULL,
             block_timestamp TEXT NOT NULL,
             created_at TEXT NOT NULL,
             updated_at TEXT NOT NULL
-        )",
+        )"
+            ),
             &[],
         )
         .await?;
 
     client
         .execute(
-            "CREATE TABLE IF NOT EXISTS transactions (
+            &format!(
+                "CREATE {kind} IF NOT EXISTS transactions (
             id SERIAL PRIMARY KEY,
-            block INTEGER NOT NULL,
+            block {height} NOT NULL,
             index INTEGER NOT NULL,
             timestamp TEXT NOT NULL,
             hash TEXT NOT NULL,
             from_address TEXT NOT NULL,
             to_address TEXT NOT NULL,
             value TEXT NOT NULL
-        )",
+        )"
+            ),
             &[],
         )
         .await?;
 
     client
         .execute(
-            "CREATE TABLE IF NOT EXISTS transfers (
+            &format!(
+                "CREATE {kind} IF NOT EXISTS transfers (
             id SERIAL PRIMARY KEY,
             tx_hash TEXT NOT NULL,
-            block_number INTEGER NOT NULL,
+            block_number {height} NOT NULL,
             token TEXT NOT NULL,
             from_address TEXT NOT NULL,
             to_address TEXT NOT NULL,
             amount TEXT NOT NULL
-        )",
+        )"
+            ),
             &[],
         )
         .await?;
 
     client
         .execute(
-            "CREATE TABLE IF NOT EXISTS pools (
+            &format!(
+                "CREATE {kind} IF NOT EXISTS pools (
             id SERIAL PRIMARY KEY,
             deployer TEXT NOT NULL,
             address TEXT NOT NULL,
             quote_token TEXT NOT NULL,
             token TEXT NOT NULL,
-            init_block INTEGER NOT NULL,
+            init_block {height} NOT NULL,
             created_at BIGINT NOT NULL
-        )",
+        )"
+            ),
             &[],
         )
         .await?;
 
+    check_block_height_type(client, height).await
+}
+
+#[tracing::instrument(name = "phase", skip_all, fields(phase = "analyze"))]
+pub async fn analyze_tables(client: &Client) -> Result<()> {
+    for table in TABLES {
+        client.execute(&format!("ANALYZE {}", table), &[]).await?;
+    }
+    Ok(())
+}
+
+/// Rewrites every table and its indexes with `VACUUM (FULL, ANALYZE)`, which
+/// returns dead space to the operating system but locks each table meanwhile.
+#[tracing::instrument(name = "phase", skip_all, fields(phase = "vacuum_full"))]
+pub async fn vacuum_full_tables(client: &Client) -> Result<()> {
+    for table in TABLES {
+        client
+            .execute(&format!("VACUUM (FULL, ANALYZE) {}", table), &[])
+            .await?;
+    }
+    Ok(())
+}
+
+/// Fails if tables left by an earlier run declare their block heights with a
+/// different type than this run binds, which would otherwise break COPY mid-load.
+async fn check_block_height_type(client: &Client, expected: &str) -> Result<()> {
+    let rows = client
+        .query(
+            "SELECT c.relname::TEXT, upper(format_type(a.atttypid, a.atttypmod))
+             FROM pg_attribute a JOIN pg_class c ON c.oid = a.attrelid
+             WHERE (a.attrelid, a.attname) IN ((to_regclass('blocks'), 'block_number'),
+                (to_regclass('transactions'), 'block'),
+                (to_regclass('transfers'), 'block_number'),
+                (to_regclass('pools'), 'init_block'))",
+            &[],
+        )
+        .await?;
+    for row in rows {
+        let (table, actual): (String, String) = (row.get(0), row.get(1));
+        if actual != expected {
+            return Err(BenchmarkError::ConfigError(format!(
+                "{} already exists with {} block heights but this run uses {}; drop the tables first",
+                table, actual, expected
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Puts the schema `name` first on the search_path, so the benchmark tables are
+/// created and read there under `--db-schema`, creating it first if `create` is
+/// set. The previous search_path stays behind it for extensions and scratch
+/// schemas still go in front of it.
+pub async fn use_schema(client: &Client, name: &str, create: bool) -> Result<()> {
+    let previous: String = client.query_one("SHOW search_path", &[]).await?.get(0);
+    if create {
+        client
+            .batch_execute(&format!("CREATE SCHEMA IF NOT EXISTS {name}"))
+            .await?;
+    }
+    client
+        .batch_execute(&format!("SET search_path = {name}, {previous}"))
+        .await?;
     Ok(())
 }
+
+/// Drops the four benchmark tables, taking their indexes and `id` sequences
+/// with them, from `db_schema` or else wherever the search_path finds them.
+/// A `db_schema` is dropped too once nothing else is left in it; if it still
+/// holds other objects it is kept and said so.
+pub async fn drop_tables(client: &Client, db_schema: Option<&str>) -> Result<()> {
+    for table in TABLES {
+        let name = match db_schema {
+            Some(schema) => format!("{schema}.{table}"),
+            None => table.to_string(),
+        };
+        let exists: bool = client
+            .query_one("SELECT to_regclass($1) IS NOT NULL", &[&name])
+            .await?
+            .get(0);
+        if exists {
+            client.batch_execute(&format!("DROP TABLE {name}")).await?;
+            println!("Dropped {}", name);
+        } else {
+            println!("No {} to drop", name);
+        }
+    }
+    let Some(schema) = db_schema else {
+        return Ok(());
+    };
+    let exists: bool = client
+        .query_one("SELECT to_regnamespace($1) IS NOT NULL", &[&schema])
+        .await?
+        .get(0);
+    if exists {
+        match client.batch_execute(&format!("DROP SCHEMA {schema}")).await {
+            Ok(()) => println!("Dropped schema {}", schema),
+            Err(e) if e.code() == Some(&SqlState::DEPENDENT_OBJECTS_STILL_EXIST) => {
+                println!("Kept schema {}, it still holds other objects", schema)
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Ok(())
+}
+
+/// Creates the scratch schema `name` from `ddl` and puts it first on the
+/// search_path, so the unqualified table names in the standard COPY and INSERT
+/// statements resolve to its variant tables. Returns the previous search_path
+/// for `leave_scratch_schema`.
+pub async fn enter_scratch_schema(client: &Client, name: &str, ddl: &str) -> Result<String> {
+    let previous: String = client.query_one("SHOW search_path", &[]).await?.get(0);
+    client
+        .batch_execute(&format!(
+            "DROP SCHEMA IF EXISTS {name} CASCADE;
+             CREATE SCHEMA {name};
+             SET search_path = {name}, {previous};
+             {ddl}"
+        ))
+        .await?;
+    Ok(previous)
+}
+
+/// Drops a scratch schema and restores the search_path saved by `enter_scratch_schema`.
+pub async fn leave_scratch_schema(client: &Client, name: &str, previous: &str) -> Result<()> {
+    client
+        .batch_execute(&format!(
+            "SET search_path = {previous}; DROP SCHEMA {name} CASCADE"
+        ))
+        .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scratch_tables_follow_the_spec() {
+        assert_eq!(
+            TRANSACTIONS.scratch_table().ddl(),
+            "
+    CREATE TABLE transactions (
+        id SERIAL PRIMARY KEY,
+        block INTEGER NOT NULL,
+        index INTEGER NOT NULL,
+        timestamp TEXT NOT NULL,
+        hash TEXT NOT NULL,
+        from_address TEXT NOT NULL,
+        to_address TEXT NOT NULL,
+        value TEXT NOT NULL
+    );"
+        );
+        assert_eq!(
+            POOLS
+                .scratch_table()
+                .id(None)
+                .column("address", "TEXT PRIMARY KEY")
+                .extra_column("note TEXT")
+                .with("fillfactor = 90")
+                .ddl(),
+            "
+    CREATE TABLE pools (
+        deployer TEXT NOT NULL,
+        address TEXT PRIMARY KEY,
+        quote_token TEXT NOT NULL,
+        token TEXT NOT NULL,
+        init_block INTEGER NOT NULL,
+        created_at BIGINT NOT NULL,
+        note TEXT
+    ) WITH (fillfactor = 90);"
+        );
+        assert!(BLOCKS
+            .scratch_table()
+            .bigint_blocks(true)
+            .ddl()
+            .contains("block_number BIGINT NOT NULL"));
+    }
+
+    #[test]
+    fn copy_statement_and_types_follow_the_columns() {
+        assert_eq!(
+            POOLS.copy_statement(),
+            "COPY pools (deployer, address, quote_token, token, init_block, created_at) FROM STDIN BINARY"
+        );
+        assert_eq!(
+            POOLS.types(false),
+            [
+                Type::TEXT,
+                Type::TEXT,
+                Type::TEXT,
+                Type::TEXT,
+                Type::INT4,
+                Type::INT8
+            ]
+        );
+        assert_eq!(
+            POOLS.types(true),
+            [
+                Type::TEXT,
+                Type::TEXT,
+                Type::TEXT,
+                Type::TEXT,
+                Type::INT8,
+                Type::INT8
+            ]
+        );
+    }
+}
@@ -0,0 +1,59 @@
+use crate::bulk::copy_all;
+use crate::db;
+use crate::error::Result;
+use crate::models::Transaction;
+use crate::report::PhaseResult;
+use crate::schema;
+use crate::variants::plain_transactions_ddl;
+use std::time::Duration;
+
+/// SO_SNDBUF sizes compared against the kernel's default, in bytes
+const SEND_BUFFER_SIZES: [u32; 4] = [16 << 10, 256 << 10, 1 << 20, 4 << 20];
+
+/// COPYs `transactions` into a scratch table over a fresh connection per send
+/// buffer size, the kernel default first, and reports the throughput of each.
+///
+/// The buffer only limits how much unacknowledged data is in flight, so sizes
+/// matter on high-latency links and barely at all over loopback.
+#[tracing::instrument(name = "phase", skip_all, fields(phase = "send_buffer_benchmark"))]
+pub async fn run_send_buffer_benchmark(
+    database_url: &str,
+    transactions: &[Transaction],
+    batch_size: usize,
+) -> Result<Vec<PhaseResult>> {
+    println!(
+        "\nSend Buffer Benchmark ({} transactions):",
+        transactions.len()
+    );
+    println!("-------------------------");
+    let mut runs: Vec<(Option<u32>, Duration)> = Vec::new();
+    for size in std::iter::once(None).chain(SEND_BUFFER_SIZES.map(Some)) {
+        let client = db::connect_with_send_buffer(database_url, size).await?;
+        let previous =
+            schema::enter_scratch_schema(&client, "send_buffer_bench", &plain_transactions_ddl())
+                .await?;
+        let duration = copy_all(&client, transactions, batch_size).await?;
+        schema::leave_scratch_schema(&client, "send_buffer_bench", &previous).await?;
+        runs.push((size, duration));
+    }
+
+    let mut results = Vec::new();
+    for (size, duration) in runs {
+        let label = match size {
+            Some(size) => format!("{} KiB", size >> 10),
+            None => "default".to_string(),
+        };
+        println!(
+            "  {:<10} {:>12.2?}  {:>10.0} rows/sec",
+            label,
+            duration,
+            transactions.len() as f64 / duration.as_secs_f64()
+        );
+        let phase = match size {
+            Some(size) => format!("send_buffer_{}k_copy", size >> 10),
+            None => "send_buffer_default_copy".to_string(),
+        };
+        results.push(PhaseResult::new(&phase, transactions.len(), duration));
+    }
+    Ok(results)
+}
@@ -0,0 +1,41 @@
+/// A termination signal that interrupted the run.
+pub struct Interrupted {
+    pub name: &'static str,
+    pub exit_code: i32,
+}
+
+/// Resolves on Ctrl-C (SIGINT) or, on Unix, on the SIGTERM that Docker and
+/// Kubernetes send when stopping a container.
+pub async fn shutdown_signal() -> Interrupted {
+    let sigint = Interrupted {
+        name: "SIGINT",
+        exit_code: 130,
+    };
+
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                tokio::select! {
+                    _ = tokio::signal::ctrl_c() => sigint,
+                    _ = sigterm.recv() => Interrupted {
+                        name: "SIGTERM",
+                        exit_code: 143,
+                    },
+                }
+            }
+            Err(e) => {
+                eprintln!("Could not install SIGTERM handler: {}", e);
+                let _ = tokio::signal::ctrl_c().await;
+                sigint
+            }
+        }
+    }
+
+    #[cfg(not(unix))]
+    {
+        let _ = tokio::signal::ctrl_c().await;
+        sigint
+    }
+}
@@ -0,0 +1,225 @@
+use crate::bulk::BulkInsert;
+use crate::cli::Args;
+use crate::error::{classify, ErrorClass, Result};
+use crate::models::Transaction;
+use crate::report::{self, PhaseResult};
+use crate::schema;
+use crate::variants::plain_transactions_ddl;
+use governor::{Quota, RateLimiter};
+use std::num::NonZeroU32;
+use std::time::{Duration, Instant};
+use tokio_postgres::Client;
+
+/// Attempts per row before a retryable error is given up on
+const MAX_ATTEMPTS: u32 = 3;
+
+/// Inserts `rows` one autocommitted INSERT at a time, timing each statement.
+/// With `--rate` the inserts are paced by a token bucket to model steady
+/// ingestion, and latency excludes the time spent waiting for a token.
+///
+/// With `--slow-threshold-ms` every insert slower than the threshold is logged
+/// as it happens, with its offset into the run, so periodic stalls such as
+/// checkpoints show up even when the percentiles hide them.
+///
+/// With `--explain-analyze-insert` the plan of inserting the first row is
+/// printed before the timed loop.
+///
+/// Transient errors are retried with a short backoff. Constraint violations are
+/// never retried: they fail the phase, or with `--skip-conflicts` are counted
+/// and the row skipped.
+#[tracing::instrument(name = "phase", skip_all, fields(phase = "single_insert"))]
+pub async fn run_single_insert<T: BulkInsert>(
+    client: &Client,
+    args: &Args,
+    rows: &[T],
+) -> Result<PhaseResult> {
+    println!(
+        "\nStarting Single Record Insert Test ({} rows):",
+        rows.len()
+    );
+    if let (true, Some(row)) = (args.explain_analyze_insert, rows.first()) {
+        explain_insert(client, row).await?;
+    }
+    let statement = client.prepare(T::INSERT).await?;
+    // No burst allowance, so rows are spaced evenly rather than front-loaded
+    let limiter = args
+        .rate
+        .map(|rate| RateLimiter::direct(Quota::per_second(rate).allow_burst(NonZeroU32::MIN)));
+
+    let slow_threshold = args.slow_threshold_ms.map(Duration::from_millis);
+    let mut slow = Vec::new();
+
+    let start = Instant::now();
+    let mut latencies = Vec::with_capacity(rows.len());
+    let mut conflicts = 0;
+    let mut retries = 0;
+    for row in rows {
+        if let Some(limiter) = &limiter {
+            limiter.until_ready().await;
+        }
+        let mut attempt = 1;
+        loop {
+            let insert_start = Instant::now();
+            match client.execute(&statement, &row.params()).await {
+                Ok(_) => {
+                    let latency = insert_start.elapsed();
+                    if slow_threshold.is_some_and(|threshold| latency > threshold) {
+                        eprintln!(
+                            "  slow insert {} at +{:.2?}: {:.2?}",
+                            row.key(),
+                            insert_start - start,
+                            latency
+                        );
+                        slow.push(latency);
+                    }
+                    latencies.push(latency);
+                    break;
+                }
+                Err(e) => match classify(&e) {
+                    ErrorClass::Retryable if attempt < MAX_ATTEMPTS => {
+                        retries += 1;
+                        tokio::time::sleep(Duration::from_millis(10 << attempt)).await;
+                        attempt += 1;
+                    }
+                    ErrorClass::Constraint if args.skip_conflicts => {
+                        conflicts += 1;
+                        break;
+                    }
+                    _ => return Err(e.into()),
+                },
+            }
+        }
+    }
+    let duration = start.elapsed();
+    let inserted = latencies.len();
+    let mean = latencies.iter().sum::<Duration>() / inserted.max(1) as u32;
+    let p50 = report::percentile(&mut latencies, 50.0);
+    let p95 = report::percentile(&mut latencies, 95.0).unwrap_or_default();
+    let p99 = report::percentile(&mut latencies, 99.0);
+
+    println!("\nSingle Record Insert Test Results:");
+    println!("-------------------------");
+    println!("Rows inserted: {}", inserted);
+    if args.skip_conflicts {
+        println!("Constraint violations skipped: {}", conflicts);
+    }
+    println!("Retried transient errors: {}", retries);
+    if let Some(threshold) = slow_threshold {
+        match slow.iter().max() {
+            Some(max) => println!(
+                "Inserts slower than {:?}: {} (max {:.2?})",
+                threshold,
+                slow.len(),
+                max
+            ),
+            None => println!("Inserts slower than {:?}: 0", threshold),
+        }
+    }
+    println!("Total duration: {:?}", duration);
+    println!(
+        "Latency: mean {:.2?}, p50 {:.2?}, p95 {:.2?}, p99 {:.2?}",
+        mean,
+        p50.unwrap_or_default(),
+        p95,
+        p99.unwrap_or_default()
+    );
+    let achieved = inserted as f64 / duration.as_secs_f64();
+    match args.rate {
+        Some(rate) => println!(
+            "Achieved rate: {:.0} records/sec of {} targeted ({:.1}%)",
+            achieved,
+            rate,
+            100.0 * achieved / rate.get() as f64
+        ),
+        None => println!("Average insertion rate: {} records/sec", achieved),
+    }
+
+    Ok(PhaseResult::new("single_insert", inserted, duration)
+        .with_p50(p50)
+        .with_p99(p99))
+}
+
+/// Prints the `EXPLAIN ANALYZE` plan of inserting `row`, whose trigger lines
+/// show the time spent on foreign key checks. Runs in a transaction that is
+/// rolled back, so the timed loop still inserts the row, though the id it
+/// drew from the sequence stays used.
+async fn explain_insert<T: BulkInsert>(client: &Client, row: &T) -> Result<()> {
+    client.batch_execute("BEGIN").await?;
+    let plan = client
+        .query(
+            &format!("EXPLAIN (ANALYZE, BUFFERS) {}", T::INSERT),
+            &row.params(),
+        )
+        .await;
+    client.batch_execute("ROLLBACK").await?;
+
+    println!("\nEXPLAIN ANALYZE of one insert ({}):", row.key());
+    for line in plan? {
+        println!("  {}", line.get::<_, &str>(0));
+    }
+    Ok(())
+}
+
+/// Inserts `rows` one autocommitted INSERT at a time into a scratch table, then
+/// again with `RETURNING id` reading back the generated key as ORMs do, and
+/// reports what the extra result row costs in throughput and latency.
+#[tracing::instrument(name = "phase", skip_all, fields(phase = "returning_benchmark"))]
+pub async fn run_returning_benchmark(
+    client: &Client,
+    rows: &[Transaction],
+) -> Result<Vec<PhaseResult>> {
+    let plain = {
+        let previous =
+            schema::enter_scratch_schema(client, "returning_bench", &plain_transactions_ddl())
+                .await?;
+        let statement = client.prepare(Transaction::INSERT).await?;
+        let mut latencies = Vec::with_capacity(rows.len());
+        for row in rows {
+            let start = Instant::now();
+            client.execute(&statement, &row.params()).await?;
+            latencies.push(start.elapsed());
+        }
+        schema::leave_scratch_schema(client, "returning_bench", &previous).await?;
+        latencies
+    };
+    let returning = {
+        let previous =
+            schema::enter_scratch_schema(client, "returning_bench", &plain_transactions_ddl())
+                .await?;
+        let statement = client
+            .prepare(&format!("{} RETURNING id", Transaction::INSERT))
+            .await?;
+        let mut latencies = Vec::with_capacity(rows.len());
+        for row in rows {
+            let start = Instant::now();
+            let _id: i32 = client.query_one(&statement, &row.params()).await?.get(0);
+            latencies.push(start.elapsed());
+        }
+        schema::leave_scratch_schema(client, "returning_bench", &previous).await?;
+        latencies
+    };
+
+    println!("\nINSERT ... RETURNING id Benchmark ({} rows):", rows.len());
+    println!("-------------------------");
+    let mut results = Vec::new();
+    for (label, mut latencies) in [("plain", plain), ("returning", returning)] {
+        let duration: Duration = latencies.iter().sum();
+        let mean = duration / latencies.len().max(1) as u32;
+        let p99 = report::percentile(&mut latencies, 99.0);
+        let result =
+            PhaseResult::new(&format!("insert_{}", label), latencies.len(), duration).with_p99(p99);
+        println!(
+            "  {:<10} {:>10.0} rows/sec  mean {:>9.2?}  p99 {:>9.2?}",
+            label,
+            result.records_per_sec(),
+            mean,
+            p99.unwrap_or_default()
+        );
+        results.push(result);
+    }
+    println!(
+        "  Throughput with RETURNING id: {:+.1}% against plain inserts",
+        100.0 * (results[1].records_per_sec() / results[0].records_per_sec() - 1.0)
+    );
+    Ok(results)
+}
@@ -0,0 +1,307 @@
+use crate::error::Result;
+use crate::schema::TABLES;
+use postgres::types::ToSql;
+use std::convert::Infallible;
+use std::io::Write;
+use std::time::{Duration, Instant};
+use tokio_postgres::error::SqlState;
+use tokio_postgres::{Client, Row};
+
+/// Runs a query feeding an optional report.
+///
+/// If the view, function or extension it relies on is missing, not loaded or
+/// not permitted for this user, logs a single warning and returns `None` so the
+/// run carries on without that report. Any other error is still returned.
+pub async fn try_stat(
+    client: &Client,
+    query: &str,
+    params: &[&(dyn ToSql + Sync)],
+) -> Result<Option<Vec<Row>>> {
+    match client.query(query, params).await {
+        Ok(rows) => Ok(Some(rows)),
+        Err(e) => {
+            let unavailable = matches!(
+                e.code(),
+                Some(&SqlState::INSUFFICIENT_PRIVILEGE)
+                    | Some(&SqlState::UNDEFINED_TABLE)
+                    | Some(&SqlState::UNDEFINED_FUNCTION)
+                    | Some(&SqlState::UNDEFINED_COLUMN)
+                    | Some(&SqlState::UNDEFINED_FILE)
+                    | Some(&SqlState::OBJECT_NOT_IN_PREREQUISITE_STATE)
+                    | Some(&SqlState::FEATURE_NOT_SUPPORTED)
+            );
+            if !unavailable {
+                return Err(e.into());
+            }
+            let first_line = query.lines().next().unwrap_or(query).trim();
+            eprintln!(
+                "Warning: skipping optional statistics ({}): {}",
+                e.as_db_error()
+                    .map(|e| e.message())
+                    .unwrap_or("unavailable"),
+                first_line
+            );
+            Ok(None)
+        }
+    }
+}
+
+/// Prints how many rows changed in each table since its statistics were last gathered.
+pub async fn report_stats_freshness(client: &Client) -> Result<()> {
+    let tables: Vec<&str> = TABLES.to_vec();
+    let Some(rows) = try_stat(
+        client,
+        "SELECT t.name, s.n_mod_since_analyze, GREATEST(s.last_analyze, s.last_autoanalyze)::TEXT
+         FROM unnest($1::TEXT[]) WITH ORDINALITY AS t(name, position)
+         LEFT JOIN pg_stat_user_tables s ON s.relid = to_regclass(t.name)
+         ORDER BY t.position",
+        &[&tables],
+    )
+    .await?
+    else {
+        return Ok(());
+    };
+
+    println!("\nPlanner statistics freshness:");
+    for row in rows {
+        let table: String = row.get(0);
+        let modified: Option<i64> = row.get(1);
+        let last_analyze: Option<String> = row.get(2);
+        match modified {
+            Some(modified) => println!(
+                "  {}: {} rows modified since last analyze ({})",
+                table,
+                modified,
+                last_analyze.as_deref().unwrap_or("never analyzed")
+            ),
+            None => println!("  {}: no statistics available", table),
+        }
+    }
+    Ok(())
+}
+
+/// Combined size of the benchmark tables in bytes, including indexes and TOAST.
+pub async fn total_table_bytes(client: &Client) -> Result<i64> {
+    let tables: Vec<&str> = TABLES.to_vec();
+    Ok(client
+        .query_one(
+            "SELECT coalesce(sum(pg_total_relation_size(to_regclass(t)))::BIGINT, 0)
+             FROM unnest($1::TEXT[]) AS t",
+            &[&tables],
+        )
+        .await?
+        .get(0))
+}
+
+/// Combined size in bytes of the tables in `schema`, including indexes and TOAST.
+pub async fn schema_bytes(client: &Client, schema: &str) -> Result<i64> {
+    Ok(client
+        .query_one(
+            "SELECT coalesce(sum(pg_total_relation_size(c.oid))::BIGINT, 0)
+             FROM pg_class c JOIN pg_namespace n ON n.oid = c.relnamespace
+             WHERE n.nspname = $1 AND c.relkind = 'r'",
+            &[&schema],
+        )
+        .await?
+        .get(0))
+}
+
+/// Prints each table's total size, including indexes and TOAST.
+pub async fn report_table_sizes(client: &Client) -> Result<()> {
+    let tables: Vec<&str> = TABLES.to_vec();
+    let rows = client
+        .query(
+            "SELECT t.name, pg_size_pretty(pg_total_relation_size(to_regclass(t.name)))
+             FROM unnest($1::TEXT[]) WITH ORDINALITY AS t(name, position)
+             ORDER BY t.position",
+            &[&tables],
+        )
+        .await?;
+    println!("\nTable sizes:");
+    for row in rows {
+        let table: String = row.get(0);
+        let size: Option<String> = row.get(1);
+        println!("  {}: {}", table, size.as_deref().unwrap_or("-"));
+    }
+    Ok(())
+}
+
+/// Rows sampled per table by `avg_row_widths`
+const ROW_WIDTH_SAMPLE: i64 = 1000;
+
+/// Average `pg_column_size` of a sample of each table's rows, in `TABLES`
+/// order, `None` for an empty table.
+pub async fn avg_row_widths(client: &Client) -> Result<Vec<(&'static str, Option<f64>)>> {
+    let mut widths = Vec::with_capacity(TABLES.len());
+    for table in TABLES {
+        let width: Option<f64> = client
+            .query_one(
+                &format!(
+                    "SELECT avg(pg_column_size(t.*))::FLOAT8 FROM (SELECT * FROM {table} LIMIT $1) t"
+                ),
+                &[&ROW_WIDTH_SAMPLE],
+            )
+            .await?
+            .get(0);
+        widths.push((table, width));
+    }
+    Ok(widths)
+}
+
+/// Appends one `elapsed_secs,table,bytes` CSV line per table with its
+/// `pg_total_relation_size` now, `elapsed` being measured from `started`.
+pub async fn write_table_sizes(
+    client: &Client,
+    started: Instant,
+    csv: &mut impl Write,
+) -> Result<()> {
+    let tables: Vec<&str> = TABLES.to_vec();
+    let rows = client
+        .query(
+            "SELECT t.name, pg_total_relation_size(to_regclass(t.name))
+             FROM unnest($1::TEXT[]) WITH ORDINALITY AS t(name, position)
+             ORDER BY t.position",
+            &[&tables],
+        )
+        .await?;
+    let elapsed = started.elapsed().as_secs_f64();
+    for row in rows {
+        let table: String = row.get(0);
+        let bytes: Option<i64> = row.get(1);
+        writeln!(csv, "{:.3},{},{}", elapsed, table, bytes.unwrap_or(0))?;
+    }
+    csv.flush()?;
+    Ok(())
+}
+
+/// Samples table sizes into `csv` every `interval` until dropped, so it can be
+/// raced against a load. Only returns on error.
+pub async fn sample_table_sizes(
+    client: &Client,
+    interval: Duration,
+    started: Instant,
+    csv: &mut impl Write,
+) -> Result<Infallible> {
+    let mut ticks = tokio::time::interval(interval);
+    loop {
+        ticks.tick().await;
+        write_table_sizes(client, started, csv).await?;
+    }
+}
+
+/// Cluster-wide checkpoint and WAL counters at a point in time.
+pub struct WalSnapshot {
+    pub checkpoints_timed: i64,
+    pub checkpoints_requested: i64,
+    pub wal_lsn_bytes: i64,
+}
+
+pub async fn wal_snapshot(client: &Client) -> Result<Option<WalSnapshot>> {
+    // Postgres 17 moved the checkpoint counters out of pg_stat_bgwriter
+    let checkpoint_query = if server_version(client).await? >= 170000 {
+        "SELECT num_timed, num_requested FROM pg_stat_checkpointer"
+    } else {
+        "SELECT checkpoints_timed, checkpoints_req FROM pg_stat_bgwriter"
+    };
+    let Some(checkpoints) = try_stat(client, checkpoint_query, &[]).await? else {
+        return Ok(None);
+    };
+    let Some(wal) = try_stat(
+        client,
+        "SELECT pg_wal_lsn_diff(pg_current_wal_lsn(), '0/0')::BIGINT",
+        &[],
+    )
+    .await?
+    else {
+        return Ok(None);
+    };
+    Ok(Some(WalSnapshot {
+        checkpoints_timed: checkpoints[0].get(0),
+        checkpoints_requested: checkpoints[0].get(1),
+        wal_lsn_bytes: wal[0].get(0),
+    }))
+}
+
+/// Prints the checkpoints triggered and WAL written between two snapshots.
+///
+/// The counters are cluster-wide, so concurrent activity on other databases is included.
+pub fn report_wal_delta(before: &WalSnapshot, after: &WalSnapshot, records: usize) {
+    let wal_bytes = after.wal_lsn_bytes - before.wal_lsn_bytes;
+    println!("\nCheckpoint/WAL Activity During Load:");
+    println!(
+        "  Checkpoints: {} timed, {} requested",
+        after.checkpoints_timed - before.checkpoints_timed,
+        after.checkpoints_requested - before.checkpoints_requested
+    );
+    println!(
+        "  WAL written: {:.1} MiB ({:.0} bytes/record)",
+        wal_bytes as f64 / (1024.0 * 1024.0),
+        wal_bytes as f64 / records.max(1) as f64
+    );
+}
+
+/// Turns on `track_io_timing` and resets `pg_stat_statements` (creating the
+/// extension if permitted) so server-side timings cover only this run.
+///
+/// Returns false if any of it isn't possible for this user or server.
+pub async fn enable_statement_timing(client: &Client) -> Result<bool> {
+    for statement in [
+        "CREATE EXTENSION IF NOT EXISTS pg_stat_statements",
+        "SET track_io_timing = on",
+        "SELECT pg_stat_statements_reset()",
+    ] {
+        if try_stat(client, statement, &[]).await?.is_none() {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+/// Prints the mean server execution time of the most expensive statements run
+/// in this database since `enable_statement_timing`.
+pub async fn report_statement_timing(client: &Client) -> Result<()> {
+    // Postgres 13 split planning from execution time and renamed the columns
+    let (mean, total) = if server_version(client).await? >= 130000 {
+        ("mean_exec_time", "total_exec_time")
+    } else {
+        ("mean_time", "total_time")
+    };
+    let Some(rows) = try_stat(
+        client,
+        &format!(
+            "SELECT calls, {mean}, {total}, left(regexp_replace(query, '\\s+', ' ', 'g'), 70)
+             FROM pg_stat_statements
+             WHERE dbid = (SELECT oid FROM pg_database WHERE datname = current_database())
+               AND query NOT ILIKE '%pg_stat_statements%'
+             ORDER BY {total} DESC
+             LIMIT 15"
+        ),
+        &[],
+    )
+    .await?
+    else {
+        return Ok(());
+    };
+
+    println!("\nServer-Side Statement Timing (pg_stat_statements):");
+    println!(
+        "{:>8}  {:>12}  {:>12}  query",
+        "calls", "mean ms", "total ms"
+    );
+    for row in rows {
+        let calls: i64 = row.get(0);
+        let mean: f64 = row.get(1);
+        let total: f64 = row.get(2);
+        let query: String = row.get(3);
+        println!("{:>8}  {:>12.3}  {:>12.1}  {}", calls, mean, total, query);
+    }
+    Ok(())
+}
+
+/// `server_version_num`, e.g. 150004 for 15.4.
+pub async fn server_version(client: &Client) -> Result<i32> {
+    Ok(client
+        .query_one("SELECT current_setting('server_version_num')::INT", &[])
+        .await?
+        .get(0))
+}
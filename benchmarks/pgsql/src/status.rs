@@ -0,0 +1,116 @@
+use crate::cli::Args;
+use crate::db;
+use crate::error::{BenchmarkError, Result};
+use crate::schema::{self, TABLES};
+use serde::Serialize;
+use tokio_postgres::{Client, Config};
+
+/// Readiness of the target database, printed as JSON by `--status`.
+#[derive(Serialize)]
+struct Status {
+    /// Connected, and every existing table has its primary key; missing
+    /// tables don't count against it since a run creates them
+    ready: bool,
+    connected: bool,
+    /// Why connecting failed, when it did
+    error: Option<String>,
+    server_version: Option<String>,
+    database: Option<String>,
+    tables: Vec<TableStatus>,
+}
+
+#[derive(Serialize)]
+struct TableStatus {
+    table: &'static str,
+    exists: bool,
+    rows: Option<i64>,
+    /// The `id` primary key index is present and valid
+    primary_key: bool,
+}
+
+/// Prints the target's readiness as JSON on stdout: the server version and
+/// database, and for each benchmark table whether it exists, its row count and
+/// whether its primary key index is present. A failure to connect is reported
+/// in the output rather than as an error; the run fails afterwards if the
+/// target isn't ready, so the exit code can gate a long benchmark.
+pub async fn print_status(args: &Args) -> Result<()> {
+    let status = match connect(args).await {
+        Ok(client) => {
+            if let Some(name) = &args.db_schema {
+                schema::use_schema(&client, name, false).await?;
+            }
+            check(&client).await?
+        }
+        Err(e) => Status {
+            ready: false,
+            connected: false,
+            error: Some(e.to_string()),
+            server_version: None,
+            database: None,
+            tables: Vec::new(),
+        },
+    };
+    println!("{}", serde_json::to_string_pretty(&status)?);
+    if !status.ready {
+        return Err(BenchmarkError::ConfigError(
+            "the target isn't ready for a benchmark run, see the status above".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Connects without `db::connect`'s progress output, which would break the JSON.
+async fn connect(args: &Args) -> Result<Client> {
+    let config: Config = args.database_url()?.parse()?;
+    let (client, connection) = config.connect(db::tls_connector()?).await?;
+    tokio::spawn(async move {
+        if let Err(e) = connection.await {
+            eprintln!("Database connection error: {}", e);
+        }
+    });
+    Ok(client)
+}
+
+async fn check(client: &Client) -> Result<Status> {
+    let row = client
+        .query_one(
+            "SELECT current_setting('server_version'), current_database()::TEXT",
+            &[],
+        )
+        .await?;
+    let mut tables = Vec::with_capacity(TABLES.len());
+    for table in TABLES {
+        let row = client
+            .query_one(
+                "SELECT to_regclass($1) IS NOT NULL,
+                        EXISTS (SELECT 1 FROM pg_index
+                                WHERE indrelid = to_regclass($1) AND indisprimary AND indisvalid)",
+                &[&table],
+            )
+            .await?;
+        let exists: bool = row.get(0);
+        let rows = if exists {
+            let count: i64 = client
+                .query_one(&format!("SELECT COUNT(*) FROM {}", table), &[])
+                .await?
+                .get(0);
+            Some(count)
+        } else {
+            None
+        };
+        tables.push(TableStatus {
+            table,
+            exists,
+            rows,
+            primary_key: row.get(1),
+        });
+    }
+    Ok(Status {
+        ready: tables.iter().all(|t| !t.exists || t.primary_key),
+        connected: true,
+        error: None,
+        server_version: Some(row.get(0)),
+        database: Some(row.get(1)),
+        tables,
+    })
+}
@@ -0,0 +1,194 @@
+use crate::dataset::DataSource;
+use crate::error::{BenchmarkError, Result};
+use serde::de::{DeserializeOwned, SeqAccess, Visitor};
+use serde::Deserializer;
+use std::fmt;
+use std::io::{BufRead, Read};
+use std::marker::PhantomData;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+/// Batches buffered between the JSON reader and the COPY loop
+const CHANNEL_DEPTH: usize = 4;
+
+/// Parses a JSON array, or with `ndjson` one JSON object per line, from
+/// `source` on a blocking thread, sending its records in batches of
+/// `batch_size` over the returned channel.
+///
+/// Streaming mode batching contract: each table's file is streamed to
+/// completion independently. Records arrive in file order, each record is in
+/// exactly one batch, and only the final batch may hold fewer than
+/// `batch_size` records. Batch N of one table has no relation to batch N of
+/// another, unlike the index-aligned batches of the in-memory load.
+///
+/// The handle resolves to the number of records parsed, or the read/parse error.
+pub fn stream_records<T>(
+    source: DataSource,
+    ndjson: bool,
+    batch_size: usize,
+) -> (mpsc::Receiver<Vec<T>>, JoinHandle<Result<usize>>)
+where
+    T: DeserializeOwned + Send + 'static,
+{
+    let (tx, rx) = mpsc::channel(CHANNEL_DEPTH);
+    let handle = tokio::task::spawn_blocking(move || {
+        let sink = |batch| tx.blocking_send(batch).is_ok();
+        if ndjson {
+            read_ndjson_batches(source.open()?, batch_size, sink)
+        } else {
+            Ok(read_batches(source.open()?, batch_size, sink)?)
+        }
+    });
+    (rx, handle)
+}
+
+/// Deserializes a JSON array element by element, handing `sink` a batch every
+/// `batch_size` records and the remainder at the end. Stops early if `sink`
+/// returns false.
+pub fn read_batches<T, R, F>(reader: R, batch_size: usize, sink: F) -> serde_json::Result<usize>
+where
+    T: DeserializeOwned,
+    R: Read,
+    F: FnMut(Vec<T>) -> bool,
+{
+    let mut deserializer = serde_json::Deserializer::from_reader(reader);
+    let parsed = deserializer.deserialize_seq(BatchVisitor {
+        batch_size: batch_size.max(1),
+        sink,
+        marker: PhantomData,
+    })?;
+    deserializer.end()?;
+    Ok(parsed)
+}
+
+/// `read_batches` for newline-delimited JSON, one record per line. Blank
+/// lines are skipped, and a line that fails to parse is reported by number.
+pub fn read_ndjson_batches<T, R, F>(reader: R, batch_size: usize, mut sink: F) -> Result<usize>
+where
+    T: DeserializeOwned,
+    R: BufRead,
+    F: FnMut(Vec<T>) -> bool,
+{
+    let batch_size = batch_size.max(1);
+    let mut parsed = 0;
+    // Not preallocated, read_records asks for a single batch of usize::MAX
+    let mut batch = Vec::new();
+    for (i, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let record = serde_json::from_str(&line).map_err(|source| BenchmarkError::NdjsonError {
+            line: i + 1,
+            source,
+        })?;
+        batch.push(record);
+        parsed += 1;
+        if batch.len() == batch_size && !sink(std::mem::take(&mut batch)) {
+            return Ok(parsed);
+        }
+    }
+    if !batch.is_empty() {
+        sink(batch);
+    }
+    Ok(parsed)
+}
+
+struct BatchVisitor<T, F> {
+    batch_size: usize,
+    sink: F,
+    marker: PhantomData<T>,
+}
+
+impl<'de, T, F> Visitor<'de> for BatchVisitor<T, F>
+where
+    T: DeserializeOwned,
+    F: FnMut(Vec<T>) -> bool,
+{
+    type Value = usize;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a JSON array of records")
+    }
+
+    fn visit_seq<A>(mut self, mut seq: A) -> std::result::Result<usize, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut parsed = 0;
+        let mut batch = Vec::with_capacity(self.batch_size);
+        while let Some(record) = seq.next_element()? {
+            batch.push(record);
+            parsed += 1;
+            if batch.len() == self.batch_size {
+                let full = std::mem::replace(&mut batch, Vec::with_capacity(self.batch_size));
+                if !(self.sink)(full) {
+                    return Ok(parsed);
+                }
+            }
+        }
+        if !batch.is_empty() {
+            (self.sink)(batch);
+        }
+        Ok(parsed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn collect_batches(records: &[u32], batch_size: usize) -> Vec<Vec<u32>> {
+        let json = serde_json::to_vec(records).unwrap();
+        let mut batches = Vec::new();
+        let parsed = read_batches(json.as_slice(), batch_size, |batch| {
+            batches.push(batch);
+            true
+        })
+        .unwrap();
+        assert_eq!(parsed, records.len());
+        batches
+    }
+
+    #[test]
+    fn every_record_is_batched_exactly_once_in_order() {
+        let records: Vec<u32> = (0..1003).collect();
+        for batch_size in [1, 7, 100, 1003, 5000] {
+            let batches = collect_batches(&records, batch_size);
+            let flattened: Vec<u32> = batches.iter().flatten().copied().collect();
+            assert_eq!(flattened, records, "batch_size {}", batch_size);
+
+            let (last, full) = batches.split_last().unwrap();
+            assert!(full.iter().all(|b| b.len() == batch_size));
+            assert!(!last.is_empty() && last.len() <= batch_size);
+        }
+    }
+
+    #[test]
+    fn empty_array_yields_no_batches() {
+        assert!(collect_batches(&[], 10).is_empty());
+    }
+
+    #[test]
+    fn trailing_garbage_is_an_error() {
+        let result = read_batches::<u32, _, _>(b"[1, 2] 3".as_slice(), 10, |_| true);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn ndjson_skips_blank_lines_and_reports_the_bad_line() {
+        let mut records = Vec::new();
+        let parsed = read_ndjson_batches(b"1\n\n2\n3\n".as_slice(), 2, |batch: Vec<u32>| {
+            records.extend(batch);
+            true
+        })
+        .unwrap();
+        assert_eq!((parsed, records), (3, vec![1, 2, 3]));
+
+        let result = read_ndjson_batches::<u32, _, _>(b"1\n2\nthree\n".as_slice(), 10, |_| true);
+        assert!(matches!(
+            result,
+            Err(BenchmarkError::NdjsonError { line: 3, .. })
+        ));
+    }
+}
@@ -0,0 +1,295 @@
+use crate::bulk::{bind_columns_with, copy_rows, BulkInsert};
+use crate::error::{BenchmarkError, Result};
+use crate::models::Transaction;
+use crate::report::{self, PhaseResult};
+use crate::schema::{self, column, height, TableSpec};
+use crate::stats;
+use crate::variants::plain_transactions_ddl;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use postgres::types::ToSql;
+use rand::Rng;
+use std::time::{Duration, Instant};
+use tokio_postgres::types::Type;
+use tokio_postgres::Client;
+
+/// Format of the dataset's offset-less timestamps, e.g. `2023-01-01T00:00:00`
+const NAIVE_FORMAT: &str = "%Y-%m-%dT%H:%M:%S%.f";
+
+/// Format of the TEXT table's range bounds without `--timestamp-format`
+const BOUND_FORMAT: &str = "%Y-%m-%dT%H:%M:%S";
+
+/// Range queries timed per variant
+const RANGE_QUERIES: usize = 200;
+
+const TIMESTAMP_INDEX_DDL: &str = "
+    CREATE INDEX ON transactions (timestamp);";
+
+/// Transactions with `timestamp` stored as TIMESTAMPTZ and indexed
+fn timestamptz_ddl() -> String {
+    schema::TRANSACTIONS
+        .scratch_table()
+        .column("timestamp", "TIMESTAMPTZ NOT NULL")
+        .ddl()
+        + TIMESTAMP_INDEX_DDL
+}
+
+/// `schema::TRANSACTIONS` with the timestamp bound as TIMESTAMPTZ
+const TRANSACTIONS_TIMESTAMPTZ: TableSpec = TableSpec {
+    table: "transactions",
+    columns: &[
+        height("block"),
+        column("index", Type::INT4),
+        column("timestamp", Type::TIMESTAMPTZ),
+        column("hash", Type::TEXT),
+        column("from_address", Type::TEXT),
+        column("to_address", Type::TEXT),
+        column("value", Type::TEXT),
+    ],
+};
+
+/// Parses a timestamp taken as UTC with the strftime `format` of
+/// `--timestamp-format`, or without one as RFC 3339 or offset-less ISO 8601.
+pub fn parse_timestamp(value: &str, format: Option<&str>) -> Option<DateTime<Utc>> {
+    if let Some(format) = format {
+        return NaiveDateTime::parse_from_str(value, format)
+            .map(|t| t.and_utc())
+            .ok();
+    }
+    DateTime::parse_from_rfc3339(value)
+        .map(|t| t.with_timezone(&Utc))
+        .or_else(|_| NaiveDateTime::parse_from_str(value, NAIVE_FORMAT).map(|t| t.and_utc()))
+        .ok()
+}
+
+/// Parses each transaction's timestamp, leaving out those that don't parse,
+/// unless an explicit `format` is given: then the data is expected to match
+/// it and the first mismatch fails with its index and value.
+fn parse_transactions<'a>(
+    transactions: &'a [Transaction],
+    format: Option<&str>,
+) -> Result<Vec<(&'a Transaction, DateTime<Utc>)>> {
+    let mut parsed = Vec::with_capacity(transactions.len());
+    for (i, tx) in transactions.iter().enumerate() {
+        match (parse_timestamp(&tx.timestamp, format), format) {
+            (Some(timestamp), _) => parsed.push((tx, timestamp)),
+            (None, Some(format)) => {
+                return Err(BenchmarkError::ValidationError(format!(
+                "transaction {} has timestamp {:?}, which doesn't match --timestamp-format {:?}",
+                i, tx.timestamp, format
+            )))
+            }
+            (None, None) => {}
+        }
+    }
+    Ok(parsed)
+}
+
+/// Insert and range query timings of one timestamp representation.
+struct TimestampRun {
+    copy: Duration,
+    bytes: i64,
+    queries: Vec<Duration>,
+    /// Rows matched by each range query, to compare answers across variants
+    matched: Vec<i64>,
+}
+
+/// Compares transactions stored with `timestamp` as TEXT, as the benchmark
+/// tables do, against native TIMESTAMPTZ, both indexed: COPY throughput,
+/// table size and the latency of random time-range queries, which the TEXT
+/// table can only answer by string comparison.
+///
+/// String comparison only orders timestamps correctly while every value uses
+/// the same format and offset, and that format sorts chronologically, so
+/// differing match counts are reported too. The TEXT table's range bounds are
+/// written in `timestamp_format` when it is set.
+/// Transactions whose timestamp doesn't parse are left out of both tables,
+/// unless `timestamp_format` is set, in which case they fail the benchmark.
+#[tracing::instrument(name = "phase", skip_all, fields(phase = "timestamp_benchmark"))]
+pub async fn run_timestamp_benchmark(
+    client: &Client,
+    transactions: &[Transaction],
+    timestamp_format: Option<&str>,
+    batch_size: usize,
+) -> Result<Vec<PhaseResult>> {
+    let parsed = parse_transactions(transactions, timestamp_format)?;
+    let unparsed = transactions.len() - parsed.len();
+    let (Some(first), Some(last)) = (
+        parsed.iter().map(|(_, t)| *t).min(),
+        parsed.iter().map(|(_, t)| *t).max(),
+    ) else {
+        println!("\nSkipping the timestamp benchmark, no transaction has a parseable timestamp");
+        return Ok(Vec::new());
+    };
+
+    // Windows of a hundredth of the dataset's time span, at random offsets
+    let window = (last - first) / 100;
+    let mut rng = rand::thread_rng();
+    let ranges: Vec<(DateTime<Utc>, DateTime<Utc>)> = (0..RANGE_QUERIES)
+        .map(|_| {
+            let offset = rng.gen_range(0..=(last - first - window).num_seconds().max(0));
+            let from = first + chrono::Duration::seconds(offset);
+            (from, from + window)
+        })
+        .collect();
+
+    let text = {
+        let previous = schema::enter_scratch_schema(
+            client,
+            "timestamp_bench_text",
+            &(plain_transactions_ddl() + TIMESTAMP_INDEX_DDL),
+        )
+        .await?;
+        let start = Instant::now();
+        for batch in parsed.chunks(batch_size.max(1)) {
+            copy_rows(client, &schema::TRANSACTIONS, batch, false, |(tx, _)| {
+                tx.params()
+            })
+            .await?;
+        }
+        let copy = start.elapsed();
+        // Bounds in the format the column's strings are in, as a query
+        // against it would write them
+        let bound_format = timestamp_format.unwrap_or(BOUND_FORMAT);
+        let bounds: Vec<(String, String)> = ranges
+            .iter()
+            .map(|(from, to)| {
+                (
+                    from.format(bound_format).to_string(),
+                    to.format(bound_format).to_string(),
+                )
+            })
+            .collect();
+        let run = time_ranges(client, "timestamp_bench_text", copy, &bounds).await?;
+        schema::leave_scratch_schema(client, "timestamp_bench_text", &previous).await?;
+        run
+    };
+
+    let native = {
+        let previous =
+            schema::enter_scratch_schema(client, "timestamp_bench_native", &timestamptz_ddl())
+                .await?;
+        let start = Instant::now();
+        for batch in parsed.chunks(batch_size.max(1)) {
+            copy_rows(
+                client,
+                &TRANSACTIONS_TIMESTAMPTZ,
+                batch,
+                false,
+                |(tx, timestamp)| {
+                    bind_columns_with(*tx, &TRANSACTIONS_TIMESTAMPTZ, ("timestamp", timestamp))
+                },
+            )
+            .await?;
+        }
+        let copy = start.elapsed();
+        let run = time_ranges(client, "timestamp_bench_native", copy, &ranges).await?;
+        schema::leave_scratch_schema(client, "timestamp_bench_native", &previous).await?;
+        run
+    };
+
+    println!(
+        "\nTEXT vs TIMESTAMPTZ Timestamp Benchmark ({} transactions, {} range queries):",
+        parsed.len(),
+        RANGE_QUERIES
+    );
+    println!("-------------------------");
+    if unparsed > 0 {
+        println!(
+            "  {} transactions with unparseable timestamps left out",
+            unparsed
+        );
+    }
+    let mut results = Vec::new();
+    for (label, run) in [("text", &text), ("timestamptz", &native)] {
+        let mut queries = run.queries.clone();
+        let mean = queries.iter().sum::<Duration>() / queries.len().max(1) as u32;
+        let p99 = report::percentile(&mut queries, 99.0);
+        println!(
+            "  {:<12} COPY {:>10.0} rows/sec  {:>8.1} MiB  range query mean {:>9.2?}  p99 {:>9.2?}",
+            label,
+            parsed.len() as f64 / run.copy.as_secs_f64(),
+            run.bytes as f64 / (1024.0 * 1024.0),
+            mean,
+            p99.unwrap_or_default()
+        );
+        results.push(PhaseResult::new(
+            &format!("timestamp_{}_copy", label),
+            parsed.len(),
+            run.copy,
+        ));
+        results.push(
+            PhaseResult::new(
+                &format!("timestamp_{}_range", label),
+                queries.len(),
+                queries.iter().sum(),
+            )
+            .with_p99(p99),
+        );
+    }
+    let disagreements = text
+        .matched
+        .iter()
+        .zip(&native.matched)
+        .filter(|(a, b)| a != b)
+        .count();
+    if disagreements > 0 {
+        println!(
+            "  {} of {} range queries matched different rows by string comparison",
+            disagreements, RANGE_QUERIES
+        );
+    }
+    Ok(results)
+}
+
+/// Times `COUNT(*)` over each `timestamp BETWEEN from AND to` range in the
+/// scratch schema `name`, whose size is measured too.
+async fn time_ranges<T: ToSql + Sync>(
+    client: &Client,
+    name: &str,
+    copy: Duration,
+    ranges: &[(T, T)],
+) -> Result<TimestampRun> {
+    client.execute("ANALYZE transactions", &[]).await?;
+    let statement = client
+        .prepare("SELECT COUNT(*) FROM transactions WHERE timestamp BETWEEN $1 AND $2")
+        .await?;
+    let mut queries = Vec::with_capacity(ranges.len());
+    let mut matched = Vec::with_capacity(ranges.len());
+    for (from, to) in ranges {
+        let start = Instant::now();
+        let count: i64 = client.query_one(&statement, &[from, to]).await?.get(0);
+        queries.push(start.elapsed());
+        matched.push(count);
+    }
+    Ok(TimestampRun {
+        copy,
+        bytes: stats::schema_bytes(client, name).await?,
+        queries,
+        matched,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn naive_timestamps_parse_as_utc() {
+        assert_eq!(
+            parse_timestamp("2023-01-01T00:00:00", None),
+            parse_timestamp("2023-01-01T01:00:00+01:00", None)
+        );
+        assert!(parse_timestamp("2023-01-01T00:00:00", None).is_some());
+        assert!(parse_timestamp("yesterday", None).is_none());
+    }
+
+    #[test]
+    fn custom_formats_replace_the_defaults() {
+        let format = Some("%d/%m/%Y %H:%M");
+        assert_eq!(
+            parse_timestamp("01/02/2023 10:30", format),
+            parse_timestamp("2023-02-01T10:30:00", None)
+        );
+        assert!(parse_timestamp("2023-02-01T10:30:00", format).is_none());
+    }
+}
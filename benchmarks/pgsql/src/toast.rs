@@ -0,0 +1,120 @@
+use crate::bulk::{copy_rows, BulkInsert};
+use crate::error::Result;
+use crate::models::Transaction;
+use crate::report::PhaseResult;
+use crate::schema;
+use rand::distributions::Alphanumeric;
+use rand::Rng;
+use std::time::{Duration, Instant};
+use tokio_postgres::Client;
+
+/// Transactions copied into each scratch table; padding the full dataset to
+/// several KB per row would need tens of GB of memory
+const TOAST_BENCH_ROWS: usize = 100_000;
+
+struct ToastRun {
+    insert: Duration,
+    read: Duration,
+    heap_bytes: i64,
+    toast_bytes: i64,
+}
+
+/// Loads the same transactions into two scratch tables, one as-is and one with
+/// `value` padded to `pad_bytes` of incompressible text so it is TOASTed, then
+/// compares insert throughput, a full detoasting read and table sizes.
+#[tracing::instrument(name = "phase", skip_all, fields(phase = "toast_benchmark"))]
+pub async fn run_toast_benchmark(
+    client: &Client,
+    transactions: &[Transaction],
+    pad_bytes: usize,
+    bigint_blocks: bool,
+) -> Result<Vec<PhaseResult>> {
+    let rows = &transactions[..transactions.len().min(TOAST_BENCH_ROWS)];
+    let mut rng = rand::thread_rng();
+    let padded: Vec<Transaction> = rows
+        .iter()
+        .map(|tx| {
+            let mut tx = tx.clone();
+            let pad = pad_bytes.saturating_sub(tx.value.len());
+            tx.value.extend(
+                (&mut rng)
+                    .sample_iter(Alphanumeric)
+                    .take(pad)
+                    .map(char::from),
+            );
+            tx
+        })
+        .collect();
+
+    let inline = toast_run(client, "toast_bench_inline", rows, bigint_blocks).await?;
+    let toasted = toast_run(client, "toast_bench_padded", &padded, bigint_blocks).await?;
+
+    println!(
+        "\nTOAST Wide-Row Benchmark ({} transactions, value padded to {} bytes):",
+        rows.len(),
+        pad_bytes
+    );
+    println!("-------------------------");
+    for (name, run) in [("inline", &inline), ("padded", &toasted)] {
+        println!(
+            "  {:<7} insert {:>10.0} rows/sec  read {:>10.0} rows/sec  heap {:.1} MiB  toast {:.1} MiB",
+            name,
+            rows.len() as f64 / run.insert.as_secs_f64(),
+            rows.len() as f64 / run.read.as_secs_f64(),
+            run.heap_bytes as f64 / (1024.0 * 1024.0),
+            run.toast_bytes as f64 / (1024.0 * 1024.0)
+        );
+    }
+
+    Ok(vec![
+        PhaseResult::new("toast_insert_inline", rows.len(), inline.insert),
+        PhaseResult::new("toast_insert_padded", rows.len(), toasted.insert),
+        PhaseResult::new("toast_read_inline", rows.len(), inline.read),
+        PhaseResult::new("toast_read_padded", rows.len(), toasted.read),
+    ])
+}
+
+/// COPYs `rows` into a scratch transactions table in the schema `name`,
+/// then times a detoasting read and measures the heap and TOAST sizes.
+async fn toast_run(
+    client: &Client,
+    name: &str,
+    rows: &[Transaction],
+    bigint_blocks: bool,
+) -> Result<ToastRun> {
+    let ddl = schema::TRANSACTIONS
+        .scratch_table()
+        .bigint_blocks(bigint_blocks)
+        .ddl();
+    let previous = schema::enter_scratch_schema(client, name, &ddl).await?;
+
+    let start = Instant::now();
+    copy_rows(client, &schema::TRANSACTIONS, rows, bigint_blocks, |tx| {
+        tx.params()
+    })
+    .await?;
+    let insert = start.elapsed();
+
+    // length() has to detoast every value, unlike octet_length() on compressed data
+    let start = Instant::now();
+    client
+        .query_one("SELECT SUM(length(value)) FROM transactions", &[])
+        .await?;
+    let read = start.elapsed();
+
+    let sizes = client
+        .query_one(
+            "SELECT pg_relation_size(c.oid), COALESCE(pg_relation_size(NULLIF(c.reltoastrelid, 0)), 0)
+             FROM pg_class c WHERE c.oid = 'transactions'::regclass",
+            &[],
+        )
+        .await?;
+    schema::leave_scratch_schema(client, name, &previous).await?;
+
+    Ok(ToastRun {
+        insert,
+        read,
+        heap_bytes: sizes.get(0),
+        toast_bytes: sizes.get(1),
+    })
+}
@@ -0,0 +1,118 @@
+use crate::bulk::copy_all;
+use crate::error::{BenchmarkError, Result};
+use crate::models::Transaction;
+use crate::report::PhaseResult;
+use crate::schema;
+use crate::variants::plain_transactions_ddl;
+use std::time::Duration;
+use tokio_postgres::Client;
+
+const SCHEMA: &str = "trigger_bench";
+
+/// Per-block transaction counts the triggers keep up to date
+const COUNTS_DDL: &str = "
+    CREATE TABLE block_tx_counts (
+        block INTEGER PRIMARY KEY,
+        transactions BIGINT NOT NULL
+    );";
+
+/// Bumps the count of the inserted row's block, once per row
+const ROW_TRIGGER_DDL: &str = "
+    CREATE FUNCTION count_row() RETURNS trigger LANGUAGE plpgsql AS $$
+    BEGIN
+        INSERT INTO block_tx_counts VALUES (NEW.block, 1)
+        ON CONFLICT (block) DO UPDATE
+            SET transactions = block_tx_counts.transactions + 1;
+        RETURN NULL;
+    END $$;
+    CREATE TRIGGER count_transactions AFTER INSERT ON transactions
+        FOR EACH ROW EXECUTE FUNCTION count_row();";
+
+/// Adds up the counts of every block in the statement's rows at once, read
+/// from its transition table
+const STATEMENT_TRIGGER_DDL: &str = "
+    CREATE FUNCTION count_statement() RETURNS trigger LANGUAGE plpgsql AS $$
+    BEGIN
+        INSERT INTO block_tx_counts
+            SELECT block, COUNT(*) FROM new_rows GROUP BY block
+        ON CONFLICT (block) DO UPDATE
+            SET transactions = block_tx_counts.transactions + EXCLUDED.transactions;
+        RETURN NULL;
+    END $$;
+    CREATE TRIGGER count_transactions AFTER INSERT ON transactions
+        REFERENCING NEW TABLE AS new_rows
+        FOR EACH STATEMENT EXECUTE FUNCTION count_statement();";
+
+/// Trigger variants, by label and the DDL adding the trigger
+const VARIANTS: [(&str, &str); 3] = [
+    ("none", ""),
+    ("row", ROW_TRIGGER_DDL),
+    ("statement", STATEMENT_TRIGGER_DDL),
+];
+
+/// Compares COPY throughput of the transactions into a scratch table without
+/// a trigger, with a `FOR EACH ROW` trigger keeping per-block counts, and
+/// with a `FOR EACH STATEMENT` trigger doing the same work once per COPY
+/// batch from its transition table. The row trigger runs a PL/pgSQL call
+/// and an upsert per row, which is where triggers usually slow loads down.
+///
+/// Both triggers are checked to have counted every row.
+#[tracing::instrument(name = "phase", skip_all, fields(phase = "trigger_benchmark"))]
+pub async fn run_trigger_benchmark(
+    client: &Client,
+    transactions: &[Transaction],
+    batch_size: usize,
+) -> Result<Vec<PhaseResult>> {
+    if transactions.is_empty() {
+        println!("\nSkipping the trigger benchmark, there are no transactions");
+        return Ok(Vec::new());
+    }
+
+    let mut runs: Vec<(&str, Duration)> = Vec::with_capacity(VARIANTS.len());
+    for (label, trigger) in VARIANTS {
+        let ddl = format!("{}{COUNTS_DDL}{trigger}", plain_transactions_ddl());
+        let previous = schema::enter_scratch_schema(client, SCHEMA, &ddl).await?;
+        let duration = copy_all(client, transactions, batch_size).await?;
+        let counted: i64 = client
+            .query_one(
+                "SELECT COALESCE(SUM(transactions), 0)::BIGINT FROM block_tx_counts",
+                &[],
+            )
+            .await?
+            .get(0);
+        schema::leave_scratch_schema(client, SCHEMA, &previous).await?;
+        if !trigger.is_empty() && counted != transactions.len() as i64 {
+            return Err(BenchmarkError::ValidationError(format!(
+                "the {} trigger counted {} transactions, expected {}",
+                label,
+                counted,
+                transactions.len()
+            )));
+        }
+        runs.push((label, duration));
+    }
+
+    println!(
+        "\nRow vs Statement Trigger Benchmark ({} transactions, batches of {}):",
+        transactions.len(),
+        batch_size
+    );
+    println!("-------------------------");
+    let baseline = runs[0].1;
+    let mut results = Vec::with_capacity(runs.len());
+    for (label, duration) in runs {
+        println!(
+            "  {:<10} {:>10.2?}  {:>10.0} rows/sec  ({:.2}x the untriggered time)",
+            label,
+            duration,
+            transactions.len() as f64 / duration.as_secs_f64(),
+            duration.as_secs_f64() / baseline.as_secs_f64()
+        );
+        results.push(PhaseResult::new(
+            &format!("copy_trigger_{}", label),
+            transactions.len(),
+            duration,
+        ));
+    }
+    Ok(results)
+}
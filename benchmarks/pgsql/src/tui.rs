@@ -0,0 +1,169 @@
+use crate::schema::TABLES;
+use ratatui::backend::CrosstermBackend;
+use ratatui::crossterm::cursor::{Hide, Show};
+use ratatui::crossterm::execute;
+use ratatui::crossterm::terminal::{EnterAlternateScreen, LeaveAlternateScreen};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Style};
+use ratatui::widgets::{Block, Borders, Gauge, Row, Sparkline, Table};
+use ratatui::Terminal;
+use std::collections::VecDeque;
+use std::io::{self, Stdout};
+use std::time::{Duration, Instant};
+
+/// Number of recent batch latencies kept for the sparkline
+const LATENCY_WINDOW: usize = 120;
+/// Minimum time between redraws, so tiny batches don't spend their time drawing
+const REDRAW_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Live view of the bulk load: batches completed, ETA, per-table throughput
+/// and a sparkline of recent batch latencies.
+///
+/// The terminal is left in cooked mode so Ctrl-C still interrupts the run.
+pub struct Dashboard {
+    terminal: Terminal<CrosstermBackend<Stdout>>,
+    started: Instant,
+    last_draw: Option<Instant>,
+    total_batches: usize,
+    batches_done: usize,
+    table_rows: [usize; 4],
+    table_time: [Duration; 4],
+    latencies_ms: VecDeque<u64>,
+}
+
+impl Dashboard {
+    pub fn start(total_batches: usize) -> io::Result<Self> {
+        let mut stdout = io::stdout();
+        execute!(stdout, EnterAlternateScreen, Hide)?;
+        let terminal = Terminal::new(CrosstermBackend::new(stdout))?;
+        Ok(Dashboard {
+            terminal,
+            started: Instant::now(),
+            last_draw: None,
+            total_batches,
+            batches_done: 0,
+            table_rows: [0; 4],
+            table_time: [Duration::ZERO; 4],
+            latencies_ms: VecDeque::with_capacity(LATENCY_WINDOW),
+        })
+    }
+
+    /// Records one finished batch: rows and COPY time per table, in `TABLES` order.
+    pub fn record_batch(
+        &mut self,
+        rows: [usize; 4],
+        time: [Duration; 4],
+        latency: Duration,
+    ) -> io::Result<()> {
+        self.batches_done += 1;
+        for i in 0..TABLES.len() {
+            self.table_rows[i] += rows[i];
+            self.table_time[i] += time[i];
+        }
+        if self.latencies_ms.len() == LATENCY_WINDOW {
+            self.latencies_ms.pop_front();
+        }
+        self.latencies_ms.push_back(latency.as_millis() as u64);
+
+        let due = self
+            .last_draw
+            .is_none_or(|last| last.elapsed() >= REDRAW_INTERVAL);
+        if due || self.batches_done == self.total_batches {
+            self.draw()?;
+        }
+        Ok(())
+    }
+
+    fn eta(&self) -> Option<Duration> {
+        if self.batches_done == 0 {
+            return None;
+        }
+        let per_batch = self.started.elapsed() / self.batches_done as u32;
+        Some(per_batch * (self.total_batches - self.batches_done) as u32)
+    }
+
+    fn draw(&mut self) -> io::Result<()> {
+        self.last_draw = Some(Instant::now());
+        let ratio = if self.total_batches == 0 {
+            1.0
+        } else {
+            self.batches_done as f64 / self.total_batches as f64
+        };
+        let label = format!(
+            "{}/{} batches  elapsed {:.1?}  ETA {}",
+            self.batches_done,
+            self.total_batches,
+            self.started.elapsed(),
+            self.eta()
+                .map(|eta| format!("{:.1?}", eta))
+                .unwrap_or_else(|| "-".to_string())
+        );
+        let rows: Vec<Row> = TABLES
+            .iter()
+            .enumerate()
+            .map(|(i, table)| {
+                let secs = self.table_time[i].as_secs_f64();
+                let rate = if secs > 0.0 {
+                    self.table_rows[i] as f64 / secs
+                } else {
+                    0.0
+                };
+                Row::new(vec![
+                    table.to_string(),
+                    self.table_rows[i].to_string(),
+                    format!("{:.0}", rate),
+                ])
+            })
+            .collect();
+        let latencies: Vec<u64> = self.latencies_ms.iter().copied().collect();
+
+        self.terminal.draw(|frame| {
+            let [progress, tables, sparkline] = Layout::vertical([
+                Constraint::Length(3),
+                Constraint::Length(TABLES.len() as u16 + 3),
+                Constraint::Min(5),
+            ])
+            .areas(frame.area());
+
+            frame.render_widget(
+                Gauge::default()
+                    .block(Block::default().borders(Borders::ALL).title("Bulk insert"))
+                    .gauge_style(Style::default().fg(Color::Green))
+                    .ratio(ratio)
+                    .label(label),
+                progress,
+            );
+            frame.render_widget(
+                Table::new(
+                    rows,
+                    [
+                        Constraint::Length(14),
+                        Constraint::Length(14),
+                        Constraint::Length(14),
+                    ],
+                )
+                .header(Row::new(vec!["Table", "Rows", "Rows/sec"]))
+                .block(Block::default().borders(Borders::ALL).title("Throughput")),
+                tables,
+            );
+            frame.render_widget(
+                Sparkline::default()
+                    .block(
+                        Block::default()
+                            .borders(Borders::ALL)
+                            .title("Batch latency (ms)"),
+                    )
+                    .style(Style::default().fg(Color::Cyan))
+                    .data(&latencies),
+                sparkline,
+            );
+        })?;
+        Ok(())
+    }
+}
+
+impl Drop for Dashboard {
+    fn drop(&mut self) {
+        let _ = execute!(self.terminal.backend_mut(), LeaveAlternateScreen, Show);
+    }
+}
@@ -0,0 +1,159 @@
+use crate::cli::Args;
+use crate::dataset::{self, DataSource, Dataset};
+use crate::error::{BenchmarkError, Result};
+use serde::Deserialize;
+
+/// Block-height columns stored as INTEGER, by table
+const BLOCK_COLUMNS: [(&str, &str); 4] = [
+    ("blocks", "block_number"),
+    ("transactions", "block"),
+    ("transfers", "block_number"),
+    ("pools", "init_block"),
+];
+
+/// Out-of-range records listed per table before the rest are only counted
+const MAX_LISTED: usize = 10;
+
+/// The block-height field of any record, read wide enough to see overflow
+#[derive(Deserialize)]
+struct BlockHeight {
+    #[serde(alias = "block", alias = "init_block")]
+    block_number: i64,
+}
+
+/// Checks that every block-height value in the selected tables is
+/// non-negative and fits the INTEGER (INT4) columns, or BIGINT ones under
+/// `--bigint-blocks`, listing the records that don't. Values that don't fit
+/// would otherwise fail the load part way through.
+///
+/// Tables read from stdin are skipped, since stdin can only be read once.
+pub fn check_block_ranges(args: &Args) -> Result<()> {
+    let max = if args.bigint_blocks {
+        i64::MAX
+    } else {
+        i32::MAX as i64
+    };
+    let mut failures = Vec::new();
+    let mut overflow = false;
+    for (table, column) in BLOCK_COLUMNS {
+        let source = DataSource::for_table(args, table);
+        if !args.includes_table(table) || matches!(source, DataSource::Stdin) {
+            continue;
+        }
+        let heights: Vec<BlockHeight> = dataset::read_records(&source, args.ndjson)?;
+        let out_of_range: Vec<(usize, i64)> = heights
+            .iter()
+            .enumerate()
+            .map(|(i, h)| (i, h.block_number))
+            .filter(|&(_, value)| !(0..=max).contains(&value))
+            .collect();
+        if out_of_range.is_empty() {
+            continue;
+        }
+
+        eprintln!(
+            "{}: {} records with {} outside 0..={}",
+            source,
+            out_of_range.len(),
+            column,
+            max
+        );
+        for (i, value) in out_of_range.iter().take(MAX_LISTED) {
+            eprintln!("  record {}: {} = {}", i, column, value);
+        }
+        if out_of_range.len() > MAX_LISTED {
+            eprintln!("  ... and {} more", out_of_range.len() - MAX_LISTED);
+        }
+        overflow |= out_of_range
+            .iter()
+            .any(|&(_, value)| value > i32::MAX as i64);
+        failures.push(format!("{}.{}", table, column));
+    }
+
+    if failures.is_empty() {
+        println!("Block heights are in range in every selected table");
+        return Ok(());
+    }
+    let mut message = format!("out-of-range block heights in {}", failures.join(", "));
+    if overflow {
+        message.push_str("; heights past 2^31-1 need BIGINT columns (--bigint-blocks)");
+    }
+    Err(BenchmarkError::ValidationError(message))
+}
+
+/// Number of `records` whose `field` is empty or only whitespace.
+fn count_empty<T>(records: &[T], field: impl Fn(&T) -> &str) -> usize {
+    records
+        .iter()
+        .filter(|r| field(r).trim().is_empty())
+        .count()
+}
+
+/// Counts records with an empty hash, from, to or token field, per table.
+///
+/// Empty strings there usually mean the export's parser dropped a value. They
+/// are reported as a warning, or as an error under `--strict`.
+pub fn check_empty_fields(dataset: &Dataset, strict: bool) -> Result<()> {
+    let checks = [
+        (
+            "blocks",
+            "block_hash",
+            count_empty(&dataset.blocks, |b| &b.block_hash),
+        ),
+        (
+            "transactions",
+            "hash",
+            count_empty(&dataset.transactions, |t| &t.hash),
+        ),
+        (
+            "transactions",
+            "from",
+            count_empty(&dataset.transactions, |t| &t.from),
+        ),
+        (
+            "transactions",
+            "to",
+            count_empty(&dataset.transactions, |t| &t.to),
+        ),
+        (
+            "transfers",
+            "tx_hash",
+            count_empty(&dataset.transfers, |t| &t.tx_hash),
+        ),
+        (
+            "transfers",
+            "from",
+            count_empty(&dataset.transfers, |t| &t.from),
+        ),
+        (
+            "transfers",
+            "to",
+            count_empty(&dataset.transfers, |t| &t.to),
+        ),
+        (
+            "transfers",
+            "token",
+            count_empty(&dataset.transfers, |t| &t.token),
+        ),
+        ("pools", "token", count_empty(&dataset.pools, |p| &p.token)),
+    ];
+    let failures: Vec<String> = checks
+        .iter()
+        .filter(|&&(_, _, empty)| empty > 0)
+        .map(|&(table, column, empty)| {
+            eprintln!("{}: {} records with an empty {}", table, empty, column);
+            format!("{}.{}", table, column)
+        })
+        .collect();
+
+    if failures.is_empty() {
+        println!("No empty hash, from, to or token fields in the dataset");
+        return Ok(());
+    }
+    let message = format!("empty values in {}", failures.join(", "));
+    if strict {
+        return Err(BenchmarkError::ValidationError(message));
+    }
+    eprintln!("Warning: {}", message);
+    Ok(())
+}
@@ -0,0 +1,726 @@
+use crate::bulk::{
+    batch_slice, compute_batches, copy_all, copy_rows_frozen, unnest_blocks, BulkInsert,
+};
+use crate::dataset::Dataset;
+use crate::error::{classify, BenchmarkError, ErrorClass, Result};
+use crate::models::{Block, Transaction};
+use crate::report::PhaseResult;
+use crate::schema;
+use crate::stats;
+use postgres::types::ToSql;
+use std::collections::HashSet;
+use std::time::{Duration, Instant};
+use tokio_postgres::Client;
+
+/// Blocks and transactions, each keyed by a SERIAL `id`
+fn serial_key_ddl() -> String {
+    schema::BLOCKS.scratch_table().ddl() + &schema::TRANSACTIONS.scratch_table().ddl()
+}
+
+/// Blocks keyed by their height
+fn natural_key_blocks_ddl() -> String {
+    schema::BLOCKS
+        .scratch_table()
+        .id(None)
+        .column("block_number", "INTEGER PRIMARY KEY")
+        .ddl()
+}
+
+/// Blocks keyed by their height and transactions by their hash
+fn natural_key_ddl() -> String {
+    natural_key_blocks_ddl()
+        + &schema::TRANSACTIONS
+            .scratch_table()
+            .id(None)
+            .column("hash", "TEXT PRIMARY KEY")
+            .ddl()
+}
+
+fn indexed_blocks_ddl() -> String {
+    schema::BLOCKS.scratch_table().ddl()
+        + "\n    CREATE INDEX blocks_block_number_idx ON blocks (block_number);"
+}
+
+/// Blocks keyed by height and transactions referencing them, with the
+/// foreign key's deferral clause `deferrable`
+fn fk_ddl(deferrable: &str) -> String {
+    natural_key_blocks_ddl()
+        + &schema::TRANSACTIONS
+            .scratch_table()
+            .column(
+                "block",
+                format!(
+                    "INTEGER NOT NULL REFERENCES blocks (block_number) {}",
+                    deferrable
+                ),
+            )
+            .ddl()
+}
+
+pub(crate) fn plain_transactions_ddl() -> String {
+    schema::TRANSACTIONS.scratch_table().ddl()
+}
+
+fn generated_column_ddl() -> String {
+    schema::TRANSACTIONS
+        .scratch_table()
+        .extra_column("from_address_lower TEXT GENERATED ALWAYS AS (lower(from_address)) STORED")
+        .ddl()
+}
+
+/// COPYs `rows` into the `T` table of a scratch schema built from `ddl`,
+/// returning the COPY time and the scratch tables' total size afterwards.
+async fn copy_into_variant<T: BulkInsert>(
+    client: &Client,
+    name: &str,
+    ddl: &str,
+    rows: &[T],
+    batch_size: usize,
+) -> Result<(Duration, i64)> {
+    let previous = schema::enter_scratch_schema(client, name, ddl).await?;
+    let duration = copy_all(client, rows, batch_size).await?;
+    let bytes = stats::schema_bytes(client, name).await?;
+    schema::leave_scratch_schema(client, name, &previous).await?;
+    Ok((duration, bytes))
+}
+
+/// Loads blocks and transactions into a scratch copy of the schema, returning
+/// the COPY time of each.
+async fn load_variant(
+    client: &Client,
+    name: &str,
+    ddl: &str,
+    dataset: &Dataset,
+    batch_size: usize,
+) -> Result<(Duration, Duration)> {
+    let previous = schema::enter_scratch_schema(client, name, ddl).await?;
+    let blocks = copy_all(client, &dataset.blocks, batch_size).await?;
+    let transactions = copy_all(client, &dataset.transactions, batch_size).await?;
+    schema::leave_scratch_schema(client, name, &previous).await?;
+    Ok((blocks, transactions))
+}
+
+/// Compares COPY throughput of the standard `id SERIAL PRIMARY KEY` tables
+/// against natural primary keys (`block_number` for blocks, `hash` for
+/// transactions), quantifying the sequence and surrogate index cost.
+#[tracing::instrument(name = "phase", skip_all, fields(phase = "key_benchmark"))]
+pub async fn run_surrogate_key_benchmark(
+    client: &Client,
+    dataset: &Dataset,
+    batch_size: usize,
+) -> Result<Vec<PhaseResult>> {
+    let serial = load_variant(
+        client,
+        "key_bench_serial",
+        &serial_key_ddl(),
+        dataset,
+        batch_size,
+    )
+    .await?;
+    let natural = load_variant(
+        client,
+        "key_bench_natural",
+        &natural_key_ddl(),
+        dataset,
+        batch_size,
+    )
+    .await?;
+
+    println!("\nSurrogate vs Natural Key Benchmark:");
+    println!("-------------------------");
+    for (table, rows, serial, natural) in [
+        ("blocks", dataset.blocks.len(), serial.0, natural.0),
+        (
+            "transactions",
+            dataset.transactions.len(),
+            serial.1,
+            natural.1,
+        ),
+    ] {
+        let serial_rate = rows as f64 / serial.as_secs_f64();
+        let natural_rate = rows as f64 / natural.as_secs_f64();
+        println!(
+            "  {:<13} SERIAL {:>10.0} rows/sec  natural {:>10.0} rows/sec  ({:+.1}%)",
+            table,
+            serial_rate,
+            natural_rate,
+            100.0 * (natural_rate - serial_rate) / serial_rate
+        );
+    }
+
+    let records = dataset.blocks.len() + dataset.transactions.len();
+    Ok(vec![
+        PhaseResult::new("serial_key_insert", records, serial.0 + serial.1),
+        PhaseResult::new("natural_key_insert", records, natural.0 + natural.1),
+    ])
+}
+
+/// Compares COPY throughput and size of transactions with and without a
+/// `GENERATED ALWAYS AS (lower(from_address)) STORED` column.
+///
+/// COPY rejects generated columns, so this relies on the COPY statement naming
+/// its columns rather than writing every column of the table.
+#[tracing::instrument(name = "phase", skip_all, fields(phase = "generated_column_benchmark"))]
+pub async fn run_generated_column_benchmark(
+    client: &Client,
+    dataset: &Dataset,
+    batch_size: usize,
+) -> Result<Vec<PhaseResult>> {
+    // Generated columns arrived in Postgres 12
+    if stats::server_version(client).await? < 120000 {
+        println!("\nSkipping the generated column benchmark, it needs Postgres 12 or later");
+        return Ok(Vec::new());
+    }
+    let rows = &dataset.transactions;
+    let (plain, plain_bytes) = copy_into_variant(
+        client,
+        "generated_bench_plain",
+        &plain_transactions_ddl(),
+        rows,
+        batch_size,
+    )
+    .await?;
+    let (generated, generated_bytes) = copy_into_variant(
+        client,
+        "generated_bench_stored",
+        &generated_column_ddl(),
+        rows,
+        batch_size,
+    )
+    .await?;
+
+    println!(
+        "\nGenerated Column Benchmark ({} transactions):",
+        rows.len()
+    );
+    println!("-------------------------");
+    for (label, duration, bytes) in [
+        ("plain", plain, plain_bytes),
+        ("generated", generated, generated_bytes),
+    ] {
+        println!(
+            "  {:<10} {:>12.2?}  {:>10.0} rows/sec  {:>8.1} MiB",
+            label,
+            duration,
+            rows.len() as f64 / duration.as_secs_f64(),
+            bytes as f64 / (1024.0 * 1024.0)
+        );
+    }
+
+    Ok(vec![
+        PhaseResult::new("plain_copy", rows.len(), plain),
+        PhaseResult::new("generated_col_copy", rows.len(), generated),
+    ])
+}
+
+/// COPYs `blocks` sorted by `block_number` into a scratch table with a
+/// `block_number` index, returning the COPY time and the index size afterwards.
+async fn copy_blocks_in_order(
+    client: &Client,
+    name: &str,
+    blocks: &[Block],
+    batch_size: usize,
+) -> Result<(Duration, i64)> {
+    let previous = schema::enter_scratch_schema(client, name, &indexed_blocks_ddl()).await?;
+    let duration = copy_all(client, blocks, batch_size).await?;
+    let index_bytes: i64 = client
+        .query_one(
+            "SELECT pg_relation_size(to_regclass('blocks_block_number_idx'))",
+            &[],
+        )
+        .await?
+        .get(0);
+    schema::leave_scratch_schema(client, name, &previous).await?;
+    Ok((duration, index_bytes))
+}
+
+/// Compares COPY of the blocks in ascending `block_number` order against
+/// descending order, into scratch tables with a `block_number` B-tree index.
+///
+/// Ascending keys always land on the rightmost leaf page, which Postgres
+/// appends to cheaply; descending keys split the leftmost page over and over,
+/// costing time and leaving a larger, half-empty index.
+#[tracing::instrument(name = "phase", skip_all, fields(phase = "insert_order_benchmark"))]
+pub async fn run_insert_order_benchmark(
+    client: &Client,
+    blocks: &[Block],
+    batch_size: usize,
+) -> Result<Vec<PhaseResult>> {
+    let mut ascending = blocks.to_vec();
+    ascending.sort_by_key(|b| b.block_number);
+    let descending: Vec<Block> = ascending.iter().rev().cloned().collect();
+
+    let (ascending_time, ascending_index) =
+        copy_blocks_in_order(client, "order_bench_ascending", &ascending, batch_size).await?;
+    let (descending_time, descending_index) =
+        copy_blocks_in_order(client, "order_bench_descending", &descending, batch_size).await?;
+
+    println!(
+        "\nInsert Order Benchmark ({} blocks, indexed block_number):",
+        blocks.len()
+    );
+    println!("-------------------------");
+    for (label, duration, index_bytes) in [
+        ("ascending", ascending_time, ascending_index),
+        ("descending", descending_time, descending_index),
+    ] {
+        println!(
+            "  {:<10} {:>12.2?}  {:>10.0} rows/sec  index {:>8.1} MiB",
+            label,
+            duration,
+            blocks.len() as f64 / duration.as_secs_f64(),
+            index_bytes as f64 / (1024.0 * 1024.0)
+        );
+    }
+    println!(
+        "  Descending order took {:.2}x as long as ascending",
+        descending_time.as_secs_f64() / ascending_time.as_secs_f64()
+    );
+
+    Ok(vec![
+        PhaseResult::new("blocks_ascending_insert", blocks.len(), ascending_time),
+        PhaseResult::new("blocks_descending_insert", blocks.len(), descending_time),
+    ])
+}
+
+/// COPYs blocks and transactions into the foreign-keyed scratch tables, one
+/// transaction per batch holding the blocks batch and then the transactions
+/// batch. Returns the load time, or `None` if a foreign key check failed.
+async fn load_with_fks(
+    client: &Client,
+    name: &str,
+    deferrable: &str,
+    blocks: &[Block],
+    transactions: &[Transaction],
+    batch_size: usize,
+) -> Result<Option<Duration>> {
+    let ddl = fk_ddl(deferrable);
+    let previous = schema::enter_scratch_schema(client, name, &ddl).await?;
+    let batch_size = batch_size.max(1);
+    let batches = compute_batches(blocks.len().max(transactions.len()), batch_size);
+    let start = Instant::now();
+    let loaded = async {
+        for i in 0..batches {
+            let (from, to) = (i * batch_size, (i + 1).saturating_mul(batch_size));
+            client.batch_execute("BEGIN").await?;
+            Block::copy_in(client, batch_slice(blocks, from, to), false).await?;
+            Transaction::copy_in(client, batch_slice(transactions, from, to), false).await?;
+            client.batch_execute("COMMIT").await?;
+        }
+        Ok(())
+    }
+    .await;
+    let duration = start.elapsed();
+    let outcome = match loaded {
+        Ok(()) => Some(duration),
+        Err(BenchmarkError::DatabaseError(e)) if classify(&e) == ErrorClass::Constraint => {
+            // A deferred check fails at COMMIT, which has already rolled back
+            client.batch_execute("ROLLBACK").await?;
+            eprintln!("  {}: {}", name, e);
+            None
+        }
+        Err(e) => return Err(e),
+    };
+    schema::leave_scratch_schema(client, name, &previous).await?;
+    Ok(outcome)
+}
+
+/// Loads blocks and the transactions referencing them into scratch tables with
+/// a `transactions.block` foreign key, checked per row and then deferred to
+/// each batch's commit (`DEFERRABLE INITIALLY DEFERRED`), and compares them.
+///
+/// Each batch commits its slice of blocks and transactions together, so a
+/// transaction referencing a block from a later batch fails the immediate
+/// check but is fine once deferred. Repeated block numbers and transactions
+/// whose block isn't in the dataset are dropped first.
+#[tracing::instrument(name = "phase", skip_all, fields(phase = "deferred_fk_benchmark"))]
+pub async fn run_deferred_fk_benchmark(
+    client: &Client,
+    dataset: &Dataset,
+    batch_size: usize,
+) -> Result<Vec<PhaseResult>> {
+    let mut seen = HashSet::new();
+    let blocks: Vec<Block> = dataset
+        .blocks
+        .iter()
+        .filter(|b| seen.insert(b.block_number.0))
+        .cloned()
+        .collect();
+    let transactions: Vec<Transaction> = dataset
+        .transactions
+        .iter()
+        .filter(|t| seen.contains(&t.block.0))
+        .cloned()
+        .collect();
+    let records = blocks.len() + transactions.len();
+
+    let immediate = load_with_fks(
+        client,
+        "fk_bench_immediate",
+        "",
+        &blocks,
+        &transactions,
+        batch_size,
+    )
+    .await?;
+    let deferred = load_with_fks(
+        client,
+        "fk_bench_deferred",
+        "DEFERRABLE INITIALLY DEFERRED",
+        &blocks,
+        &transactions,
+        batch_size,
+    )
+    .await?;
+
+    println!(
+        "\nDeferred vs Immediate Foreign Key Benchmark ({} blocks, {} transactions):",
+        blocks.len(),
+        transactions.len()
+    );
+    println!("-------------------------");
+    let mut results = Vec::new();
+    for (label, phase, duration) in [
+        ("immediate", "fk_immediate_copy", immediate),
+        ("deferred", "fk_deferred_copy", deferred),
+    ] {
+        match duration {
+            Some(duration) => {
+                println!(
+                    "  {:<10} {:>12.2?}  {:>10.0} rows/sec",
+                    label,
+                    duration,
+                    records as f64 / duration.as_secs_f64()
+                );
+                results.push(PhaseResult::new(phase, records, duration));
+            }
+            None => println!("  {:<10} failed a foreign key check", label),
+        }
+    }
+    match (immediate, deferred) {
+        (Some(immediate), Some(deferred)) => println!(
+            "  Deferring the checks changed the load time by {:+.1}%",
+            100.0 * (deferred.as_secs_f64() - immediate.as_secs_f64()) / immediate.as_secs_f64()
+        ),
+        (None, Some(_)) => println!(
+            "  Batches reference blocks committed in later batches, only deferred checks can load them"
+        ),
+        _ => {}
+    }
+    Ok(results)
+}
+
+/// Load and follow-up `VACUUM (FREEZE)` time of one `--copy-freeze` variant.
+struct FreezeRun {
+    load: Duration,
+    vacuum: Duration,
+}
+
+/// Loads blocks and transactions into scratch tables, with `freeze` as one
+/// transaction that truncates them and then `COPY ... FREEZE`s every batch,
+/// otherwise with plain COPY, then times the `VACUUM (FREEZE)` a freshly
+/// loaded table eventually needs.
+async fn freeze_run(
+    client: &Client,
+    name: &str,
+    dataset: &Dataset,
+    batch_size: usize,
+    freeze: bool,
+) -> Result<FreezeRun> {
+    let previous = schema::enter_scratch_schema(client, name, &serial_key_ddl()).await?;
+    let start = Instant::now();
+    if freeze {
+        client
+            .batch_execute("BEGIN; TRUNCATE blocks, transactions")
+            .await?;
+        for batch in dataset.blocks.chunks(batch_size.max(1)) {
+            copy_rows_frozen(client, batch).await?;
+        }
+        for batch in dataset.transactions.chunks(batch_size.max(1)) {
+            copy_rows_frozen(client, batch).await?;
+        }
+        client.batch_execute("COMMIT").await?;
+    } else {
+        copy_all(client, &dataset.blocks, batch_size).await?;
+        copy_all(client, &dataset.transactions, batch_size).await?;
+    }
+    let load = start.elapsed();
+
+    // VACUUM can't run inside the implicit transaction of a multi-statement string
+    let start = Instant::now();
+    client.batch_execute("VACUUM (FREEZE) blocks").await?;
+    client.batch_execute("VACUUM (FREEZE) transactions").await?;
+    let vacuum = start.elapsed();
+    schema::leave_scratch_schema(client, name, &previous).await?;
+    Ok(FreezeRun { load, vacuum })
+}
+
+/// Compares a plain COPY load of blocks and transactions against `TRUNCATE`
+/// and `COPY ... FREEZE` in one transaction, each followed by the
+/// `VACUUM (FREEZE)` that anti-wraparound maintenance would otherwise run
+/// later. Frozen rows leave that vacuum little to do, at the cost of holding
+/// one transaction open for the whole load.
+#[tracing::instrument(name = "phase", skip_all, fields(phase = "copy_freeze_benchmark"))]
+pub async fn run_copy_freeze_benchmark(
+    client: &Client,
+    dataset: &Dataset,
+    batch_size: usize,
+) -> Result<Vec<PhaseResult>> {
+    let plain = freeze_run(client, "freeze_bench_plain", dataset, batch_size, false).await?;
+    let frozen = freeze_run(client, "freeze_bench_frozen", dataset, batch_size, true).await?;
+
+    let records = dataset.blocks.len() + dataset.transactions.len();
+    println!(
+        "\nCOPY FREEZE Benchmark ({} blocks and transactions):",
+        records
+    );
+    println!("-------------------------");
+    for (label, run) in [("plain", &plain), ("freeze", &frozen)] {
+        println!(
+            "  {:<7} load {:>10.2?} ({:>10.0} rows/sec)  VACUUM (FREEZE) {:>10.2?}  total {:>10.2?}",
+            label,
+            run.load,
+            records as f64 / run.load.as_secs_f64(),
+            run.vacuum,
+            run.load + run.vacuum
+        );
+    }
+
+    Ok(vec![
+        PhaseResult::new("copy_plain_load", records, plain.load),
+        PhaseResult::new("copy_freeze_load", records, frozen.load),
+        PhaseResult::new("vacuum_freeze_after_plain", 0, plain.vacuum),
+        PhaseResult::new("vacuum_freeze_after_freeze", 0, frozen.vacuum),
+    ])
+}
+
+/// Times `INSERT ... ON CONFLICT (block_number) DO UPDATE SET updated_at` of
+/// every block into a scratch table that already holds `conflict_ratio` of
+/// them, spread evenly, modelling an indexer replaying blocks with updates.
+///
+/// Repeated block numbers are dropped first, since one statement can't update
+/// the same row twice.
+#[tracing::instrument(name = "phase", skip_all, fields(phase = "upsert_update_benchmark"))]
+pub async fn run_upsert_update_benchmark(
+    client: &Client,
+    blocks: &[Block],
+    conflict_ratio: f64,
+    batch_size: usize,
+) -> Result<Vec<PhaseResult>> {
+    let (blocks, existing) = upsert_input(blocks, conflict_ratio);
+    let duration = time_upsert(
+        client,
+        "upsert_bench",
+        schema::BLOCKS_UPSERT,
+        &blocks,
+        &existing,
+        batch_size,
+    )
+    .await?;
+
+    let result = PhaseResult::new("upsert_update", blocks.len(), duration);
+    println!("\nUpsert (ON CONFLICT DO UPDATE) Benchmark:");
+    println!("-------------------------");
+    println!(
+        "  {} blocks, {} updated and {} inserted",
+        blocks.len(),
+        existing.len(),
+        blocks.len() - existing.len()
+    );
+    println!(
+        "  {:.2?}, {:.0} rows/sec",
+        duration,
+        result.records_per_sec()
+    );
+    Ok(vec![result])
+}
+
+/// Compares `MERGE INTO blocks ... WHEN MATCHED THEN UPDATE ... WHEN NOT
+/// MATCHED THEN INSERT` against the `ON CONFLICT` upsert of
+/// `run_upsert_update_benchmark`, on the same blocks with the same share
+/// already present, each in its own scratch table. Skipped before Postgres 15,
+/// which introduced MERGE.
+///
+/// Unlike `ON CONFLICT`, MERGE takes no lock on the key it finds missing, so
+/// concurrent MERGEs of the same new block can fail with a unique violation
+/// where the upsert would update; only a single writer is timed here.
+#[tracing::instrument(name = "phase", skip_all, fields(phase = "merge_benchmark"))]
+pub async fn run_merge_benchmark(
+    client: &Client,
+    blocks: &[Block],
+    conflict_ratio: f64,
+    batch_size: usize,
+) -> Result<Vec<PhaseResult>> {
+    let version = stats::server_version(client).await?;
+    if version < 150000 {
+        println!(
+            "\nSkipping the MERGE benchmark, it needs Postgres 15 or later (server is {})",
+            version
+        );
+        return Ok(Vec::new());
+    }
+
+    let (blocks, existing) = upsert_input(blocks, conflict_ratio);
+    let on_conflict = time_upsert(
+        client,
+        "merge_bench_on_conflict",
+        schema::BLOCKS_UPSERT,
+        &blocks,
+        &existing,
+        batch_size,
+    )
+    .await?;
+    let merge = time_upsert(
+        client,
+        "merge_bench_merge",
+        schema::BLOCKS_MERGE,
+        &blocks,
+        &existing,
+        batch_size,
+    )
+    .await?;
+
+    println!("\nMERGE vs ON CONFLICT Upsert Benchmark:");
+    println!("-------------------------");
+    println!(
+        "  {} blocks, {} updated and {} inserted",
+        blocks.len(),
+        existing.len(),
+        blocks.len() - existing.len()
+    );
+    let results = vec![
+        PhaseResult::new("upsert_on_conflict", blocks.len(), on_conflict),
+        PhaseResult::new("upsert_merge", blocks.len(), merge),
+    ];
+    for (label, result) in ["ON CONFLICT", "MERGE"].iter().zip(&results) {
+        println!(
+            "  {:<12} {:>10.2?}  {:>10.0} rows/sec",
+            label,
+            result.duration,
+            result.records_per_sec()
+        );
+    }
+    println!(
+        "  MERGE took {:.2}x the time of ON CONFLICT",
+        merge.as_secs_f64() / on_conflict.as_secs_f64()
+    );
+    Ok(results)
+}
+
+/// The blocks to upsert, with repeated block numbers dropped since one
+/// statement can't update the same row twice, and the `conflict_ratio` share
+/// of them, spread evenly, to load beforehand.
+fn upsert_input(blocks: &[Block], conflict_ratio: f64) -> (Vec<Block>, Vec<Block>) {
+    let mut seen = HashSet::new();
+    let blocks: Vec<Block> = blocks
+        .iter()
+        .filter(|b| seen.insert(b.block_number.0))
+        .cloned()
+        .collect();
+    // Block i exists beforehand when the running share of existing blocks steps up at i
+    let existing: Vec<Block> = blocks
+        .iter()
+        .enumerate()
+        .filter(|&(i, _)| {
+            ((i + 1) as f64 * conflict_ratio).floor() > (i as f64 * conflict_ratio).floor()
+        })
+        .map(|(_, b)| b.clone())
+        .collect();
+    (blocks, existing)
+}
+
+/// COPYs `existing` into the blocks table of the scratch schema `name`, then
+/// times upserting `blocks` with the unnest-based `statement` in batches.
+async fn time_upsert(
+    client: &Client,
+    name: &str,
+    statement: &str,
+    blocks: &[Block],
+    existing: &[Block],
+    batch_size: usize,
+) -> Result<Duration> {
+    let previous = schema::enter_scratch_schema(client, name, &natural_key_blocks_ddl()).await?;
+    copy_all(client, existing, batch_size).await?;
+    let start = Instant::now();
+    for batch in blocks.chunks(batch_size.max(1)) {
+        unnest_blocks(client, statement, batch).await?;
+    }
+    let duration = start.elapsed();
+    schema::leave_scratch_schema(client, name, &previous).await?;
+    Ok(duration)
+}
+
+/// Most parameters a single Postgres statement can bind
+const MAX_BIND_PARAMS: usize = 65535;
+
+/// Turns a single-row `INSERT ... VALUES ($1, ...)` into one inserting `rows`
+/// rows of `columns` parameters each.
+fn multirow_statement(insert: &str, columns: usize, rows: usize) -> String {
+    let prefix = &insert[..insert.find("VALUES").expect("INSERT has a VALUES list")];
+    let tuples: Vec<String> = (0..rows)
+        .map(|row| {
+            let params: Vec<String> = (1..=columns)
+                .map(|column| format!("${}", row * columns + column))
+                .collect();
+            format!("({})", params.join(", "))
+        })
+        .collect();
+    format!("{}VALUES {}", prefix, tuples.join(", "))
+}
+
+/// Inserts transactions into a scratch table with prepared
+/// `INSERT ... VALUES (...), (...)` statements of `rows_per_stmt` rows each,
+/// the pattern most application frameworks generate. The size is capped so a
+/// statement stays within Postgres's 65535 bind parameters.
+#[tracing::instrument(name = "phase", skip_all, fields(phase = "multirow_insert"))]
+pub async fn run_multirow_insert(
+    client: &Client,
+    rows: &[Transaction],
+    rows_per_stmt: usize,
+) -> Result<PhaseResult> {
+    let columns = rows.first().map_or(1, |row| row.params().len());
+    let max_rows = MAX_BIND_PARAMS / columns;
+    if rows_per_stmt > max_rows {
+        println!(
+            "\n{} rows per statement would bind more than {} parameters, using {}",
+            rows_per_stmt, MAX_BIND_PARAMS, max_rows
+        );
+    }
+    let rows_per_stmt = rows_per_stmt.min(max_rows);
+
+    let previous =
+        schema::enter_scratch_schema(client, "multirow_bench", &plain_transactions_ddl()).await?;
+    let start = Instant::now();
+    let full = client
+        .prepare(&multirow_statement(
+            Transaction::INSERT,
+            columns,
+            rows_per_stmt,
+        ))
+        .await?;
+    for chunk in rows.chunks(rows_per_stmt) {
+        let params: Vec<&(dyn ToSql + Sync)> = chunk.iter().flat_map(|row| row.params()).collect();
+        if chunk.len() == rows_per_stmt {
+            client.execute(&full, &params).await?;
+        } else {
+            let last = multirow_statement(Transaction::INSERT, columns, chunk.len());
+            client.execute(last.as_str(), &params).await?;
+        }
+    }
+    let duration = start.elapsed();
+    schema::leave_scratch_schema(client, "multirow_bench", &previous).await?;
+
+    let result = PhaseResult::new(
+        &format!("multirow_insert_{}", rows_per_stmt),
+        rows.len(),
+        duration,
+    );
+    println!(
+        "\nMulti-row INSERT ({} rows per statement): {} rows in {:.2?}, {:.0} rows/sec",
+        rows_per_stmt,
+        rows.len(),
+        duration,
+        result.records_per_sec()
+    );
+    Ok(result)
+}
@@ -0,0 +1,119 @@
+use crate::bulk::copy_all;
+use crate::error::Result;
+use crate::models::Transaction;
+use crate::report::PhaseResult;
+use crate::schema;
+use crate::stats::try_stat;
+use crate::variants::plain_transactions_ddl;
+use std::time::{Duration, Instant};
+use tokio_postgres::Client;
+
+const SCHEMA: &str = "wal_level_bench";
+
+/// COPY time and WAL volume of one load.
+struct WalRun {
+    copy: Duration,
+    /// `None` if the WAL position can't be read, e.g. without permission
+    wal_bytes: Option<i64>,
+}
+
+/// Reports the server's `wal_level` and compares COPYing the transactions
+/// into a scratch table created beforehand against creating the table and
+/// COPYing into it in one transaction, with the WAL each writes.
+///
+/// Under `wal_level = minimal` the second load skips WAL for the new table,
+/// syncing its files at commit instead. `wal_level` only changes with a
+/// restart, so on `replica` or `logical` both loads are WAL-logged and the
+/// report says what setting `minimal` would take instead of changing it.
+#[tracing::instrument(name = "phase", skip_all, fields(phase = "wal_level_benchmark"))]
+pub async fn run_wal_level_benchmark(
+    client: &Client,
+    transactions: &[Transaction],
+    batch_size: usize,
+) -> Result<Vec<PhaseResult>> {
+    if transactions.is_empty() {
+        println!("\nSkipping the WAL level benchmark, there are no transactions");
+        return Ok(Vec::new());
+    }
+    let wal_level: String = client.query_one("SHOW wal_level", &[]).await?.get(0);
+
+    let previous = schema::enter_scratch_schema(client, SCHEMA, &plain_transactions_ddl()).await?;
+    let existing = wal_run(client, async {
+        copy_all(client, transactions, batch_size).await?;
+        Ok(())
+    })
+    .await?;
+    schema::leave_scratch_schema(client, SCHEMA, &previous).await?;
+
+    let previous = schema::enter_scratch_schema(client, SCHEMA, "").await?;
+    let same_transaction = wal_run(client, async {
+        client.batch_execute("BEGIN").await?;
+        client.batch_execute(&plain_transactions_ddl()).await?;
+        copy_all(client, transactions, batch_size).await?;
+        client.batch_execute("COMMIT").await?;
+        Ok(())
+    })
+    .await?;
+    schema::leave_scratch_schema(client, SCHEMA, &previous).await?;
+
+    println!(
+        "\nWAL Level Benchmark ({} transactions, wal_level = {}):",
+        transactions.len(),
+        wal_level
+    );
+    println!("-------------------------");
+    let mut results = Vec::new();
+    for (label, phase, run) in [
+        ("existing table", "copy_existing_table", &existing),
+        ("created in txn", "copy_created_in_txn", &same_transaction),
+    ] {
+        println!(
+            "  {:<15} {:>10.2?}  {:>10.0} rows/sec  WAL {}",
+            label,
+            run.copy,
+            transactions.len() as f64 / run.copy.as_secs_f64(),
+            run.wal_bytes
+                .map(|bytes| format!("{:.1} MiB", bytes as f64 / (1024.0 * 1024.0)))
+                .unwrap_or_else(|| "unknown".to_string())
+        );
+        results.push(PhaseResult::new(phase, transactions.len(), run.copy));
+    }
+    if wal_level == "minimal" {
+        println!("  The table created in the same transaction skips WAL and is synced at commit");
+    } else {
+        println!(
+            "  Both loads are WAL-logged under wal_level = {}. Setting wal_level = minimal and \
+             max_wal_senders = 0 and restarting lets a COPY into a table created or truncated \
+             in the same transaction skip WAL, at the cost of replicas and point-in-time recovery",
+            wal_level
+        );
+    }
+    Ok(results)
+}
+
+/// Times `load` and measures the WAL written while it runs.
+async fn wal_run(
+    client: &Client,
+    load: impl std::future::Future<Output = Result<()>>,
+) -> Result<WalRun> {
+    let before = wal_lsn(client).await?;
+    let start = Instant::now();
+    load.await?;
+    let copy = start.elapsed();
+    let after = wal_lsn(client).await?;
+    Ok(WalRun {
+        copy,
+        wal_bytes: before.zip(after).map(|(before, after)| after - before),
+    })
+}
+
+/// The current WAL insert position in bytes, if it can be read.
+async fn wal_lsn(client: &Client) -> Result<Option<i64>> {
+    Ok(try_stat(
+        client,
+        "SELECT pg_wal_lsn_diff(pg_current_wal_insert_lsn(), '0/0')::BIGINT",
+        &[],
+    )
+    .await?
+    .map(|rows| rows[0].get(0)))
+}